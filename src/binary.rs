@@ -0,0 +1,394 @@
+//! CBOR, MessagePack and BSON. Byte strings are kept as maps under `BYTES`,
+//! and BSON's own types as relaxed Extended JSON such as `{"$oid": "..."}`.
+//! A map read from a document whose only key is `BYTES` isn't a byte string,
+//! so its key gets another `$` in front while it's read, which comes off
+//! again when it's written.
+
+use std::{fmt, io};
+
+use anyhow::anyhow;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bson::{Bson, Document};
+use serde::{
+    de::{self, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use serde_json::{Map, Number, Value};
+
+use super::{compare, number, FileFormat, TranscodeError};
+
+/// The key of the map byte strings are kept as until they're written
+pub const BYTES: &str = "$scribe::bytes";
+
+/// Whether `key`, as the only one in a map, is `BYTES` with any number of
+/// `$` escapes in front
+fn escapable(key: &str) -> bool {
+    key.strip_prefix('$')
+        .is_some_and(|rest| rest.trim_start_matches('$') == &BYTES[1..])
+}
+
+/// The map `entries` read from a document, with its key escaped if it would
+/// be taken for a byte string
+pub fn escaped(mut entries: Map<String, Value>) -> Value {
+    if entries.len() == 1 {
+        if let Some(key) = entries.keys().next().filter(|key| escapable(key)).cloned() {
+            let value = entries.remove(&key).unwrap_or_default();
+            entries.insert(format!("${key}"), value);
+        }
+    }
+    Value::Object(entries)
+}
+
+/// `value` read from a format without byte strings, with the keys of the
+/// maps that would be taken for them escaped
+pub fn escape(value: Value) -> Value {
+    match value {
+        Value::Array(items) => Value::Array(items.into_iter().map(escape).collect()),
+        Value::Object(entries) => escaped(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key, escape(value)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// What to write `key` as, given whether it's the only key in its map:
+/// without the escape `escaped` gave it, if it has one
+pub fn written_key(key: &str, only: bool) -> &str {
+    match key.strip_prefix('$') {
+        Some(unescaped) if only && escapable(unescaped) => unescaped,
+        _ => key,
+    }
+}
+
+/// `bytes` as the map `BYTES` keeps them in, base64 encoded
+pub fn bytes(bytes: &[u8]) -> Value {
+    let mut entries = Map::new();
+    entries.insert(BYTES.to_string(), Value::String(STANDARD.encode(bytes)));
+    Value::Object(entries)
+}
+
+/// The base64 of the byte string `value` holds, if it holds one
+pub fn base64(value: &Value) -> Option<&str> {
+    match value {
+        Value::Object(entries) if entries.len() == 1 => entries.get(BYTES)?.as_str(),
+        _ => None,
+    }
+}
+
+/// The bytes `value` holds, if it holds a byte string
+pub fn decoded(value: &Value) -> Option<Vec<u8>> {
+    STANDARD.decode(base64(value)?).ok()
+}
+
+/// Whether `format` has a type for byte strings, so they stay bytes in it
+pub fn holds_bytes(format: FileFormat) -> bool {
+    matches!(
+        format,
+        FileFormat::MsgPack | FileFormat::Cbor | FileFormat::Bson | FileFormat::Plist
+    )
+}
+
+/// `value` with its byte strings as base64 strings, for formats without bytes
+pub fn to_text(value: Value) -> Value {
+    map_bytes(value, &|base64| Value::String(base64))
+}
+
+/// `value` with each byte string replaced by what `map` makes of its base64,
+/// and escaped keys as they were read
+fn map_bytes(value: Value, map: &impl Fn(String) -> Value) -> Value {
+    if let Some(base64) = base64(&value) {
+        return map(base64.to_string());
+    }
+    match value {
+        Value::Array(items) => {
+            Value::Array(items.into_iter().map(|item| map_bytes(item, map)).collect())
+        }
+        Value::Object(entries) => {
+            let only = entries.len() == 1;
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| {
+                        (written_key(&key, only).to_string(), map_bytes(value, map))
+                    })
+                    .collect(),
+            )
+        }
+        value => value,
+    }
+}
+
+/// A byte string as BSON's Extended JSON for generic binary data
+fn extended_bytes(base64: String) -> Value {
+    serde_json::json!({"$binary": {"base64": base64, "subType": "00"}})
+}
+
+/// `value` with byte strings as Extended JSON, for --bson-extended
+pub fn to_extended(value: Value) -> Value {
+    map_bytes(value, &extended_bytes)
+}
+
+pub fn from_msgpack(content: &[u8]) -> Result<Value, TranscodeError> {
+    match rmp_serde::from_slice::<Pivot>(content) {
+        Ok(Pivot(value)) => Ok(value),
+        Err(err) => Err(TranscodeError::syntax(err)),
+    }
+}
+
+pub fn from_cbor(content: &[u8]) -> Result<Value, TranscodeError> {
+    match ciborium::from_reader::<Pivot, _>(content) {
+        Ok(Pivot(value)) => Ok(value),
+        Err(ciborium::de::Error::Semantic(_, message)) => Err(TranscodeError::syntax(message)),
+        // ciborium displays the rest as their Debug form
+        Err(ciborium::de::Error::Io(err)) if err.kind() == io::ErrorKind::UnexpectedEof => Err(
+            TranscodeError::syntax("the data ends partway through a value"),
+        ),
+        Err(ciborium::de::Error::Io(err)) => Err(TranscodeError::syntax(err)),
+        Err(ciborium::de::Error::Syntax(offset)) => Err(TranscodeError::syntax(format_args!(
+            "invalid CBOR at byte {offset}"
+        ))),
+        Err(ciborium::de::Error::RecursionLimitExceeded) => {
+            Err(TranscodeError::syntax("the values are nested too deeply"))
+        }
+    }
+}
+
+/// The documents of a BSON file, which may hold several one after another.
+/// Several read as an array of them
+pub fn from_bson(mut content: &[u8]) -> Result<Value, TranscodeError> {
+    let mut documents = Vec::new();
+    while !content.is_empty() {
+        let document = Document::from_reader(&mut content).map_err(|err| {
+            // Reading from a slice only fails once it runs out
+            let problem = match err.kind {
+                bson::error::ErrorKind::Io { .. } | bson::error::ErrorKind::EndOfStream { .. } => {
+                    "the data ends partway through it".to_string()
+                }
+                kind => err.message.unwrap_or_else(|| kind.to_string()),
+            };
+            let at = err
+                .key
+                .map(|key| format!(" at `{key}`"))
+                .unwrap_or_default();
+            TranscodeError::syntax(format_args!(
+                "Failed to read document {}{at}: {problem}",
+                documents.len() + 1
+            ))
+        })?;
+        documents.push(from_bson_value(Bson::Document(document)));
+    }
+    match documents.len() {
+        0 => Ok(Value::Null),
+        1 => Ok(documents.remove(0)),
+        _ => Ok(Value::Array(documents)),
+    }
+}
+
+/// `bson` as relaxed Extended JSON, with generic binary data as byte strings
+fn from_bson_value(bson: Bson) -> Value {
+    match bson {
+        Bson::Binary(binary) if binary.subtype == bson::spec::BinarySubtype::Generic => {
+            bytes(&binary.bytes)
+        }
+        Bson::Array(items) => Value::Array(items.into_iter().map(from_bson_value).collect()),
+        Bson::Document(document) => escaped(
+            document
+                .into_iter()
+                .map(|(key, value)| (key, from_bson_value(value)))
+                .collect(),
+        ),
+        bson => bson.into_relaxed_extjson(),
+    }
+}
+
+/// `value` as a BSON document, or with `multi_doc` an array of them as one
+/// after another. Extended JSON maps become the types they stand for
+pub fn to_bson(value: &Value, multi_doc: bool) -> anyhow::Result<Vec<u8>> {
+    number::check(value, "BSON", &mut String::new())?;
+    check_unsigned(value, &mut String::new())?;
+    let value = &map_bytes(value.clone(), &extended_bytes);
+    let documents = match value {
+        Value::Array(documents) if multi_doc => documents.iter().collect(),
+        _ if multi_doc => return Err(anyhow!("--multi-doc needs an array at the root")),
+        Value::Array(_) => {
+            return Err(anyhow!(
+                "BSON needs a map at the root; pass --multi-doc to write each item of the \
+                 array as its own document"
+            ))
+        }
+        value => vec![value],
+    };
+
+    let mut buffer = Vec::new();
+    for (index, document) in documents.into_iter().enumerate() {
+        match Bson::try_from(document.clone())? {
+            Bson::Document(document) => document.to_writer(&mut buffer)?,
+            _ if multi_doc => return Err(anyhow!("BSON needs a map at `/{index}`")),
+            _ => return Err(anyhow!("BSON needs a map at the root")),
+        }
+    }
+    Ok(buffer)
+}
+
+/// Fail on an integer past `i64`, which BSON has no type for
+fn check_unsigned(value: &Value, path: &mut String) -> anyhow::Result<()> {
+    let children: Vec<(String, &Value)> = match value {
+        Value::Number(number) if number.is_u64() && !number.is_i64() => {
+            let at = if path.is_empty() { "the root" } else { path };
+            return Err(anyhow!(
+                "{number} at `{at}` is too large for a BSON integer"
+            ));
+        }
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| (index.to_string(), item))
+            .collect(),
+        Value::Object(entries) => entries
+            .iter()
+            .map(|(key, value)| (key.clone(), value))
+            .collect(),
+        _ => return Ok(()),
+    };
+    for (key, child) in children {
+        let len = compare::push_segment(path, &key);
+        check_unsigned(child, path)?;
+        path.truncate(len);
+    }
+    Ok(())
+}
+
+/// `value` read from BSON with the Extended JSON maps of its ObjectIds, dates,
+/// binary data and decimals replaced by plain strings: the ObjectId in hex,
+/// the date in RFC 3339, the bytes in base64 and the decimal as written
+pub fn plain_bson(value: Value) -> Value {
+    match value {
+        Value::Array(items) => Value::Array(items.into_iter().map(plain_bson).collect()),
+        Value::Object(entries) => {
+            if let Some(plain) = plain_extended(&entries) {
+                return plain;
+            }
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, plain_bson(value)))
+                    .collect(),
+            )
+        }
+        value => value,
+    }
+}
+
+fn plain_extended(entries: &Map<String, Value>) -> Option<Value> {
+    let (key, value) = entries.iter().next().filter(|_| entries.len() == 1)?;
+    let text = match (key.as_str(), value) {
+        ("$oid" | "$date" | "$numberDecimal", Value::String(text)) => text,
+        ("$binary", Value::Object(binary)) => binary.get("base64")?.as_str()?,
+        _ => return None,
+    };
+    Some(Value::String(text.to_string()))
+}
+
+/// A `Value` read from CBOR or MessagePack. Map keys that aren't strings
+/// become their JSON text, `1` rather than `"1"`, and integers must fit in
+/// 64 bits
+struct Pivot(Value);
+
+impl<'de> Deserialize<'de> for Pivot {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(PivotVisitor).map(Pivot)
+    }
+}
+
+struct PivotVisitor;
+
+impl<'de> Visitor<'de> for PivotVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_i128<E: de::Error>(self, v: i128) -> Result<Value, E> {
+        i64::try_from(v)
+            .map(Value::from)
+            .map_err(|_| E::custom(format!("integer {v} does not fit in 64 bits")))
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<Value, E> {
+        u64::try_from(v)
+            .map(Value::from)
+            .map_err(|_| E::custom(format!("integer {v} does not fit in 64 bits")))
+    }
+
+    // NaN and the infinities become null, as they do when parsing JSON
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(Number::from_f64(v).map_or(Value::Null, Value::Number))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Value, E> {
+        Ok(bytes(v))
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(Pivot(item)) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut entries = Map::new();
+        while let Some((Pivot(key), Pivot(value))) = map.next_entry()? {
+            let key = match key {
+                Value::String(key) => key,
+                key => key.to_string(),
+            };
+            entries.insert(key, value);
+        }
+        Ok(escaped(entries))
+    }
+}
@@ -0,0 +1,115 @@
+//! Structural comparison of parsed documents.
+//!
+//! Maps compare without regard to key order and numbers compare by value, so
+//! `1` and `1.0` are equal even though one is an integer and the other a float.
+//! Strings that are both TOML datetimes compare by the time they name, so
+//! `1979-05-27 07:32:00Z`, as TOML can write it, equals `1979-05-27T07:32:00Z`.
+
+use std::fmt::Display;
+
+use serde_json::Value;
+use toml::value::Datetime;
+
+use super::number;
+
+/// A single path at which two documents disagree
+pub struct Difference {
+    /// JSON Pointer to the differing value
+    pub path: String,
+    /// Value in the first document, `None` if the key is missing there
+    pub before: Option<Value>,
+    /// Value in the second document, `None` if the key is missing there
+    pub after: Option<Value>,
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        match (&self.before, &self.after) {
+            (Some(before), Some(after)) => write!(f, "{path}: {before} became {after}"),
+            (Some(before), None) => write!(f, "{path}: {before} was removed"),
+            (None, Some(after)) => write!(f, "{path}: {after} was added"),
+            (None, None) => write!(f, "{path}"),
+        }
+    }
+}
+
+/// Every path at which `before` and `after` differ, in document order
+pub fn differences(before: &Value, after: &Value) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    walk(before, after, &mut String::new(), &mut differences);
+    differences
+}
+
+fn walk(before: &Value, after: &Value, path: &mut String, differences: &mut Vec<Difference>) {
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) => {
+            for (key, old) in before {
+                let len = push_segment(path, key);
+                match after.get(key) {
+                    Some(new) => walk(old, new, path, differences),
+                    None => differences.push(Difference {
+                        path: path.clone(),
+                        before: Some(old.clone()),
+                        after: None,
+                    }),
+                }
+                path.truncate(len);
+            }
+            for (key, new) in after.iter().filter(|(key, _)| !before.contains_key(*key)) {
+                let len = push_segment(path, key);
+                differences.push(Difference {
+                    path: path.clone(),
+                    before: None,
+                    after: Some(new.clone()),
+                });
+                path.truncate(len);
+            }
+        }
+        (Value::Array(before), Value::Array(after)) => {
+            for index in 0..before.len().max(after.len()) {
+                let len = push_segment(path, &index.to_string());
+                match (before.get(index), after.get(index)) {
+                    (Some(old), Some(new)) => walk(old, new, path, differences),
+                    (old, new) => differences.push(Difference {
+                        path: path.clone(),
+                        before: old.cloned(),
+                        after: new.cloned(),
+                    }),
+                }
+                path.truncate(len);
+            }
+        }
+        (Value::Number(old), Value::Number(new)) if number::equal(old, new) => {}
+        (Value::String(old), Value::String(new)) if same_datetime(old, new) => {}
+        (before, after) if before != after => differences.push(changed(path, before, after)),
+        _ => {}
+    }
+}
+
+fn same_datetime(a: &str, b: &str) -> bool {
+    match (a.parse::<Datetime>(), b.parse::<Datetime>()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn changed(path: &str, before: &Value, after: &Value) -> Difference {
+    Difference {
+        path: path.to_string(),
+        before: Some(before.clone()),
+        after: Some(after.clone()),
+    }
+}
+
+/// Append an escaped JSON Pointer segment, returning the length to truncate back to
+pub fn push_segment(path: &mut String, segment: &str) -> usize {
+    let len = path.len();
+    path.push('/');
+    path.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+    len
+}
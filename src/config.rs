@@ -0,0 +1,159 @@
+//! Defaults for options from a config file, `./.rscribe.toml` or else
+//! `$XDG_CONFIG_HOME/rscribe/config.toml`:
+//!
+//! ```toml
+//! to = "json"
+//! indent = 2
+//! sort-keys = true
+//! newline = "lf"
+//! ```
+//!
+//! Keys are the long names of the options they set. An option given on the
+//! command line or in its `RSCRIBE_*` environment variable wins over the file,
+//! as does one that conflicts with it, such as `--compact` over `indent`. Each
+//! setting the file adds is passed to clap as the flag it names, ahead of the
+//! real arguments, so it's checked like one.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use clap::{parser::ValueSource, ArgMatches, Command, CommandFactory, FromArgMatches};
+
+use super::{edit, failure, Cli, Exit};
+
+/// The keys a config file can set, with the ids of the arguments they set
+const KEYS: &[(&str, &str)] = &[
+    ("to", "output_format"),
+    ("indent", "indent"),
+    ("compact", "compact"),
+    ("sort-keys", "sort_keys"),
+    ("newline", "newline"),
+    ("null-policy", "null_policy"),
+    ("force", "force"),
+    ("backup", "backup"),
+    ("mkdir", "mkdir"),
+    ("yaml-ext", "yaml_ext"),
+    ("yaml-style", "yaml_style"),
+    ("yaml-quote", "yaml_quote"),
+];
+
+/// Parse the command line, filling in what it leaves out from the config file
+pub fn parse() -> anyhow::Result<Cli> {
+    let command = Cli::command();
+    let matches = command.clone().get_matches();
+    let from_matches = |matches: &ArgMatches, mut defaulted: Vec<&'static str>| {
+        let mut cli = Cli::from_arg_matches(matches).unwrap_or_else(|err| err.exit());
+        defaulted.extend(
+            KEYS.iter()
+                .map(|(_, id)| *id)
+                .filter(|id| matches.value_source(id) == Some(ValueSource::EnvVariable)),
+        );
+        cli.defaulted = defaulted;
+        cli.edits = edit::ordered(matches);
+        Ok(cli)
+    };
+    if matches.get_flag("no_config") || matches.contains_id("completions") {
+        return from_matches(&matches, Vec::new());
+    }
+    let Some(path) = find() else {
+        return from_matches(&matches, Vec::new());
+    };
+
+    let config_error = |message: &dyn std::fmt::Display| {
+        failure(Exit::Usage, format_args!("{}: {message}", path.display()))
+    };
+    let content = fs::read_to_string(&path).map_err(|err| {
+        failure(
+            Exit::Io,
+            format_args!("Failed to read {}: {err}", path.display()),
+        )
+    })?;
+    let table: toml::value::Table = toml::from_str(&content).map_err(|err| config_error(&err))?;
+
+    let paths = matches
+        .get_many::<PathBuf>("paths")
+        .map_or(0, Iterator::count);
+    let mut args = vec![env::args_os().next().unwrap_or_default()];
+    let mut defaulted = Vec::new();
+    for (key, value) in &table {
+        let Some((_, id)) = KEYS.iter().find(|(name, _)| name == key) else {
+            if !matches.get_flag("quiet") {
+                eprintln!("warning: {}: unknown key `{key}`, ignored", path.display());
+            }
+            continue;
+        };
+        if given(&command, &matches, id) {
+            continue;
+        }
+        // Two paths without --to name an input and its output
+        if *id == "output_format" && paths == 2 && !matches.contains_id("out_dir") {
+            continue;
+        }
+
+        let long = command
+            .get_arguments()
+            .find(|arg| arg.get_id() == id)
+            .and_then(|arg| arg.get_long())
+            .unwrap_or(key);
+        defaulted.push(*id);
+        let values = match value {
+            toml::Value::Array(values) => values.clone(),
+            value => vec![value.clone()],
+        };
+        for value in values {
+            match value {
+                toml::Value::Boolean(true) => args.push(format!("--{long}").into()),
+                toml::Value::Boolean(false) => {}
+                toml::Value::String(value) => args.push(format!("--{long}={value}").into()),
+                toml::Value::Integer(value) => args.push(format!("--{long}={value}").into()),
+                _ => {
+                    return Err(config_error(&format_args!(
+                        "`{key}` must be a string, integer or boolean, or a list of them"
+                    )))
+                }
+            }
+        }
+    }
+
+    args.extend(env::args_os().skip(1));
+    let matches = command.try_get_matches_from(args).map_err(|err| {
+        let message = err.to_string();
+        let message = message.lines().next().unwrap_or_default();
+        config_error(&message.trim_start_matches("error: "))
+    })?;
+    from_matches(&matches, defaulted)
+}
+
+/// The config file to read, if there is one
+fn find() -> Option<PathBuf> {
+    let local = PathBuf::from(".rscribe.toml");
+    if local.is_file() {
+        return Some(local);
+    }
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config_home.join("rscribe").join("config.toml")).filter(|path| path.is_file())
+}
+
+/// Whether the argument `id`, or one that conflicts with it, was set on the
+/// command line or in the environment
+fn given(command: &Command, matches: &ArgMatches, id: &str) -> bool {
+    let explicit = |id: &str| {
+        matches!(
+            matches.value_source(id),
+            Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+        )
+    };
+    let Some(arg) = command.get_arguments().find(|arg| arg.get_id() == id) else {
+        return false;
+    };
+    explicit(id)
+        || command.get_arguments().any(|other| {
+            let conflicts = command.get_arg_conflicts_with(arg).contains(&other)
+                || command.get_arg_conflicts_with(other).contains(&arg);
+            conflicts && explicit(other.get_id().as_str())
+        })
+}
@@ -0,0 +1,179 @@
+//! Dotenv files of `KEY=value` lines.
+//!
+//! Nested maps are flattened on the way out, joining keys with a separator and
+//! uppercasing them, so `{"db": {"host": "x"}}` becomes `DB_HOST=x`. Reading a
+//! dotenv file gives a flat map of strings; nothing is unflattened.
+
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use serde_json::{Map, Value};
+
+use super::TranscodeError;
+
+/// How to write a document as dotenv lines
+pub struct EnvStyle<'a> {
+    /// Joins the keys of nested maps
+    pub separator: &'a str,
+    /// Write arrays of plain values as comma separated lists rather than failing
+    pub join_arrays: bool,
+}
+
+pub fn to_env(value: &Value, style: &EnvStyle) -> anyhow::Result<String> {
+    let Value::Object(root) = value else {
+        return Err(anyhow!("dotenv output needs a map at the root"));
+    };
+
+    let mut lines = Vec::new();
+    flatten(root, "", "", style, &mut lines)?;
+
+    let mut sources = HashMap::new();
+    for (name, path, _) in &lines {
+        if let Some(other) = sources.insert(name.as_str(), path.as_str()) {
+            return Err(anyhow!("both `{other}` and `{path}` become {name}"));
+        }
+    }
+
+    Ok(lines
+        .into_iter()
+        .map(|(name, _, value)| format!("{name}={value}\n"))
+        .collect())
+}
+
+/// Collect `(name, dotted path, quoted value)` for every plain value below `map`
+fn flatten(
+    map: &Map<String, Value>,
+    prefix: &str,
+    path: &str,
+    style: &EnvStyle,
+    lines: &mut Vec<(String, String, String)>,
+) -> anyhow::Result<()> {
+    for (key, value) in map {
+        let name = format!("{prefix}{}", variable_name(key));
+        let path = if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{path}.{key}")
+        };
+
+        let value = match value {
+            Value::Object(entries) => {
+                let prefix = format!("{name}{}", style.separator);
+                flatten(entries, &prefix, &path, style, lines)?;
+                continue;
+            }
+            Value::Array(items) if style.join_arrays => {
+                let items = items
+                    .iter()
+                    .map(|item| match item {
+                        Value::Array(_) | Value::Object(_) => Err(anyhow!(
+                            "`{path}` holds nested values, which can't be joined into one variable"
+                        )),
+                        item => Ok(plain_text(item)),
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                items.join(",")
+            }
+            Value::Array(_) => {
+                return Err(anyhow!(
+                    "`{path}` is an array; pass --env-join-arrays to write it as a comma separated list"
+                ))
+            }
+            value => plain_text(value),
+        };
+        lines.push((name, path, quote(&value)));
+    }
+    Ok(())
+}
+
+/// `key` uppercased, with anything but letters, digits and `_` replaced by `_`
+fn variable_name(key: &str) -> String {
+    key.chars()
+        .map(|char| {
+            if char.is_ascii_alphanumeric() {
+                char.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn plain_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(string) => string.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Double quote `value` if a shell or dotenv parser would otherwise misread it
+fn quote(value: &str) -> String {
+    let plain = value
+        .chars()
+        .all(|char| char.is_ascii_alphanumeric() || "_-.,:/@%+".contains(char));
+    if plain {
+        return value.to_string();
+    }
+
+    let mut quoted = String::from("\"");
+    for char in value.chars() {
+        match char {
+            '"' | '\\' | '$' | '`' => {
+                quoted.push('\\');
+                quoted.push(char);
+            }
+            '\n' => quoted.push_str("\\n"),
+            char => quoted.push(char),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+pub fn from_env(content: &str) -> Result<Value, TranscodeError> {
+    let mut variables = Map::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((name, value)) = line.split_once('=') else {
+            return Err(TranscodeError::at(index + 1, None, "expected `KEY=value`"));
+        };
+        variables.insert(
+            name.trim().to_string(),
+            Value::String(unquote(value.trim())),
+        );
+    }
+    Ok(Value::Object(variables))
+}
+
+fn unquote(value: &str) -> String {
+    if let Some(literal) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        return literal.to_string();
+    }
+    let Some(quoted) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) else {
+        // An unquoted value ends at a comment
+        return match value.split_once(" #") {
+            Some((value, _)) => value.trim_end().to_string(),
+            None => value.to_string(),
+        };
+    };
+
+    let mut unquoted = String::new();
+    let mut chars = quoted.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => match chars.next() {
+                Some('n') => unquoted.push('\n'),
+                Some(escaped) => unquoted.push(escaped),
+                None => unquoted.push('\\'),
+            },
+            char => unquoted.push(char),
+        }
+    }
+    unquoted
+}
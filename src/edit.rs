@@ -0,0 +1,148 @@
+//! `--set` and `--unset`: changing single values by JSON Pointer while
+//! converting, applied in the order they're given on the command line.
+//!
+//! Setting a path creates the maps leading to it that are missing or null,
+//! and an array index one past the last item, or `-`, appends to the array.
+//! Unsetting a path that leads nowhere changes nothing.
+
+use anyhow::anyhow;
+use clap::ArgMatches;
+use serde_json::{Map, Value};
+
+use super::table;
+
+#[derive(Clone)]
+pub enum Edit {
+    Set(String, Value),
+    Unset(String),
+}
+
+/// Parse `POINTER=JSON` for --set
+pub fn set(arg: &str) -> anyhow::Result<Edit> {
+    let (pointer, value) = arg
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Expected POINTER=VALUE, got `{arg}`"))?;
+    check_pointer(pointer)?;
+    let value = serde_json::from_str(value)
+        .map_err(|err| anyhow!("`{value}` isn't JSON ({err}); quote a string as '\"{value}\"'"))?;
+    Ok(Edit::Set(pointer.to_string(), value))
+}
+
+/// Parse `POINTER` for --unset
+pub fn unset(pointer: &str) -> anyhow::Result<Edit> {
+    if pointer.is_empty() {
+        return Err(anyhow!("--unset needs a pointer below the root"));
+    }
+    check_pointer(pointer)?;
+    Ok(Edit::Unset(pointer.to_string()))
+}
+
+fn check_pointer(pointer: &str) -> anyhow::Result<()> {
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        return Err(anyhow!("`{pointer}` must start with `/`"));
+    }
+    Ok(())
+}
+
+/// The --set and --unset edits in `matches`, in the order they were given
+pub fn ordered(matches: &ArgMatches) -> Vec<Edit> {
+    let mut edits: Vec<(usize, Edit)> = ["set", "unset"]
+        .into_iter()
+        .filter_map(|id| Some(matches.indices_of(id)?.zip(matches.get_many::<Edit>(id)?)))
+        .flatten()
+        .map(|(index, edit)| (index, edit.clone()))
+        .collect();
+    edits.sort_by_key(|(index, _)| *index);
+    edits.into_iter().map(|(_, edit)| edit).collect()
+}
+
+/// The keys of `pointer`, with `~1` and `~0` standing for `/` and `~`
+fn segments(pointer: &str) -> Vec<String> {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect()
+}
+
+/// The index `segment` names in an array of `len` items, where `len` itself
+/// and `-` mean appending. RFC 6901 indices have no leading zeros
+fn index(segment: &str, len: usize) -> Option<usize> {
+    if segment == "-" {
+        return Some(len);
+    }
+    if segment.starts_with('0') && segment != "0" {
+        return None;
+    }
+    segment.parse().ok().filter(|index| *index <= len)
+}
+
+/// Set what `pointer` leads to in `value` to `new`
+pub fn set_value(value: &mut Value, pointer: &str, new: Value) -> anyhow::Result<()> {
+    let mut node = value;
+    let mut path = String::new();
+    for segment in segments(pointer) {
+        if node.is_null() {
+            *node = Value::Object(Map::new());
+        }
+        let parent = if path.is_empty() { "/" } else { &path };
+        node = match node {
+            Value::Object(entries) => entries.entry(segment.as_str()).or_insert(Value::Null),
+            Value::Array(items) => {
+                let Some(index) = index(&segment, items.len()) else {
+                    return Err(anyhow!(
+                        "--set `{pointer}`: {parent} has no item `{segment}`"
+                    ));
+                };
+                if index == items.len() {
+                    items.push(Value::Null);
+                }
+                &mut items[index]
+            }
+            node => {
+                return Err(anyhow!(
+                    "--set `{pointer}`: {parent} is {}, which has no `{segment}`",
+                    table::kind(node)
+                ))
+            }
+        };
+        path.push('/');
+        path.push_str(&segment);
+    }
+    *node = new;
+    Ok(())
+}
+
+/// Remove what `pointer` leads to from `value`, returning whether it was there
+pub fn unset_value(value: &mut Value, pointer: &str) -> bool {
+    let mut segments = segments(pointer);
+    let Some(last) = segments.pop() else {
+        return false;
+    };
+    let mut node = value;
+    for segment in &segments {
+        let found = match node {
+            Value::Object(entries) => entries.get_mut(segment),
+            Value::Array(items) => match index(segment, items.len()) {
+                Some(index) => items.get_mut(index),
+                None => None,
+            },
+            _ => None,
+        };
+        let Some(found) = found else {
+            return false;
+        };
+        node = found;
+    }
+    match node {
+        Value::Object(entries) => entries.shift_remove(&last).is_some(),
+        Value::Array(items) => match index(&last, items.len()) {
+            Some(index) if index < items.len() => {
+                items.remove(index);
+                true
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
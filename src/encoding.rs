@@ -0,0 +1,86 @@
+//! Text encodings of input, which every parser expects as UTF-8.
+//!
+//! A UTF-8 byte order mark is dropped, and UTF-16 with one is transcoded.
+//! Input without one is UTF-8 unless `--input-encoding` names another
+//! encoding, and anything else fails saying what it looks like instead.
+
+use std::borrow::Cow;
+
+use anyhow::anyhow;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+/// The byte order mark `--output-bom` starts text output with
+pub const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Parse an `--input-encoding` label, such as `utf-16le` or `latin1`
+pub fn from_label(label: &str) -> Result<&'static Encoding, String> {
+    Encoding::for_label(label.as_bytes()).ok_or_else(|| {
+        format!("`{label}` isn't an encoding; try utf-8, utf-16le, utf-16be or latin1")
+    })
+}
+
+/// Whether `start`, the first bytes of an input, hold a byte order mark or
+/// aren't plain UTF-8, so the input needs `decode`
+pub fn marked(start: &[u8]) -> bool {
+    Encoding::for_bom(start).is_some()
+        || start.iter().take(2).any(|byte| *byte == 0)
+        // An error without a length is a character cut off at the end
+        || std::str::from_utf8(start).is_err_and(|err| err.error_len().is_some())
+}
+
+/// `content` as UTF-8 without a byte order mark, read as `encoding` if it has
+/// none
+pub fn decode<'a>(
+    content: &'a [u8],
+    encoding: Option<&'static Encoding>,
+) -> anyhow::Result<Cow<'a, [u8]>> {
+    let (encoding, content) = match Encoding::for_bom(content) {
+        Some((encoding, length)) => (encoding, &content[length..]),
+        None => (encoding.unwrap_or(UTF_8), content),
+    };
+    if encoding != UTF_8 {
+        return match encoding.decode_without_bom_handling_and_without_replacement(content) {
+            Some(text) => Ok(Cow::Owned(text.into_owned().into_bytes())),
+            None => Err(anyhow!("isn't valid {}", encoding.name())),
+        };
+    }
+
+    if let Some(encoding) = utf_16(content) {
+        return Err(anyhow!(
+            "appears to be {} without a byte order mark; re-encode it as UTF-8 or pass \
+             --input-encoding {}",
+            encoding.name(),
+            encoding.name().to_lowercase()
+        ));
+    }
+    match std::str::from_utf8(content) {
+        Ok(_) => Ok(Cow::Borrowed(content)),
+        Err(err) => Err(anyhow!(
+            "isn't valid UTF-8 at byte {}, and may be Latin-1; re-encode it as UTF-8 or pass \
+             --input-encoding latin1",
+            err.valid_up_to()
+        )),
+    }
+}
+
+/// The UTF-16 byte order `content` seems to be in, telling by the zero byte
+/// that goes with each ASCII character
+fn utf_16(content: &[u8]) -> Option<&'static Encoding> {
+    let pairs: Vec<&[u8]> = content.chunks_exact(2).take(64).collect();
+    let count = |zero: usize| {
+        pairs
+            .iter()
+            .filter(|pair| pair[zero] == 0 && pair[1 - zero] != 0)
+            .count()
+    };
+    let half = pairs.len() / 2;
+    if pairs.is_empty() {
+        None
+    } else if count(1) > half {
+        Some(UTF_16LE)
+    } else if count(0) > half {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,144 @@
+//! `--flatten` and `--unflatten`: nested maps and arrays to and from a single
+//! map of joined keys, so `{"server": {"hosts": ["a"]}}` becomes
+//! `{"server.hosts.0": "a"}`.
+//!
+//! Empty maps and arrays stay as they are under their joined key, so that
+//! nothing is lost flattening them. Unflattening makes an array of a map whose
+//! keys are `0`, `1` and so on up, which is what flattening makes of an array.
+
+use anyhow::anyhow;
+use serde_json::{Map, Value};
+
+use super::{binary, compare};
+
+/// Every plain value below `value` under its keys joined with `separator`.
+/// A key holding `separator` couldn't be told apart from a nested one, so it
+/// fails
+pub fn flatten(value: Value, separator: &str) -> anyhow::Result<Value> {
+    let nested = match &value {
+        Value::Object(entries) => !entries.is_empty() && binary::base64(&value).is_none(),
+        Value::Array(items) => !items.is_empty(),
+        _ => false,
+    };
+    if !nested {
+        return Ok(value);
+    }
+    Ok(Value::Object(join_keys(value, separator, false)?))
+}
+
+/// The plain values below the map or array `value`, and the empty maps and
+/// arrays, under their keys joined with `separator`. Keys holding `separator`
+/// fail unless `dotted`, when they join as they are and only fail if they
+/// then name the same value as another key
+pub fn join_keys(
+    value: Value,
+    separator: &str,
+    dotted: bool,
+) -> anyhow::Result<Map<String, Value>> {
+    let mut flat = Map::new();
+    let joining = Joining { separator, dotted };
+    joining.collect(value, None, &mut String::new(), &mut flat)?;
+    Ok(flat)
+}
+
+struct Joining<'a> {
+    separator: &'a str,
+    dotted: bool,
+}
+
+impl Joining<'_> {
+    fn collect(
+        &self,
+        value: Value,
+        name: Option<&str>,
+        path: &mut String,
+        flat: &mut Map<String, Value>,
+    ) -> anyhow::Result<()> {
+        let separator = self.separator;
+        let entries: Vec<(String, Value)> = match value {
+            Value::Object(entries) if !entries.is_empty() && binary::base64(&value).is_none() => {
+                entries.into_iter().collect()
+            }
+            Value::Array(items) if !items.is_empty() => items
+                .into_iter()
+                .enumerate()
+                .map(|(index, item)| (index.to_string(), item))
+                .collect(),
+            value => {
+                let name = name.unwrap_or_default();
+                if flat.insert(name.to_string(), value).is_some() {
+                    return Err(anyhow!("`{path}` and another value both become `{name}`"));
+                }
+                return Ok(());
+            }
+        };
+
+        for (key, value) in entries {
+            let len = compare::push_segment(path, &key);
+            if !self.dotted && key.contains(separator) {
+                return Err(anyhow!(
+                    "The key at `{path}` holds the separator `{separator}`, so it can't be \
+                     flattened; pass another with --flatten-sep"
+                ));
+            }
+            let name = match name {
+                Some(name) => format!("{name}{separator}{key}"),
+                None => key,
+            };
+            self.collect(value, Some(&name), path, flat)?;
+            path.truncate(len);
+        }
+        Ok(())
+    }
+}
+
+/// The nested document the keys of the map `value` name when split on
+/// `separator`
+pub fn unflatten(value: Value, separator: &str) -> anyhow::Result<Value> {
+    let Value::Object(flat) = value else {
+        return Ok(value);
+    };
+
+    let mut root = Value::Object(Map::new());
+    for (name, value) in flat {
+        let mut node = &mut root;
+        let mut prefix = String::new();
+        for segment in name.split(separator) {
+            let Value::Object(entries) = node else {
+                return Err(anyhow!(
+                    "`{name}` needs `{prefix}` to be a map, but it's set to {node}"
+                ));
+            };
+            if !prefix.is_empty() {
+                prefix.push_str(separator);
+            }
+            prefix.push_str(segment);
+            node = entries
+                .entry(segment)
+                .or_insert_with(|| Value::Object(Map::new()));
+        }
+        if !matches!(node, Value::Object(entries) if entries.is_empty()) {
+            return Err(anyhow!("`{name}` is set both as a map and as a value"));
+        }
+        *node = value;
+    }
+    Ok(arrays(root))
+}
+
+/// `value` with each map whose keys count up from `0` made an array
+fn arrays(value: Value) -> Value {
+    let Value::Object(entries) = value else {
+        return value;
+    };
+    let indexes = !entries.is_empty()
+        && entries
+            .keys()
+            .enumerate()
+            .all(|(index, key)| *key == index.to_string());
+    let entries = entries.into_iter().map(|(key, value)| (key, arrays(value)));
+    if indexes {
+        Value::Array(entries.map(|(_, value)| value).collect())
+    } else {
+        Value::Object(entries.collect())
+    }
+}
@@ -0,0 +1,36 @@
+//! Discovery of changed files in the surrounding git working tree.
+
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use git2::{Repository, Status, StatusOptions};
+
+/// Files that are added, modified or untracked in the repository containing
+/// the current directory, as paths below its working tree
+pub fn changed_files() -> anyhow::Result<Vec<PathBuf>> {
+    let repo = Repository::discover(".")?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("Repository has no working tree"))?;
+
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false);
+
+    let changed = Status::INDEX_NEW
+        | Status::INDEX_MODIFIED
+        | Status::INDEX_RENAMED
+        | Status::WT_NEW
+        | Status::WT_MODIFIED
+        | Status::WT_RENAMED;
+
+    let statuses = repo.statuses(Some(&mut options))?;
+    let files = statuses
+        .iter()
+        .filter(|entry| entry.status().intersects(changed))
+        .filter_map(|entry| entry.path().ok().map(|path| workdir.join(path)))
+        .collect();
+    Ok(files)
+}
@@ -0,0 +1,39 @@
+//! Fetching inputs from HTTP(S) URLs.
+
+use std::time::Duration;
+
+use anyhow::anyhow;
+
+/// Redirects to follow before giving up on a URL
+const MAX_REDIRECTS: u32 = 10;
+
+/// The body of a successful GET of `url`, with its `Content-Type` if it has one
+pub fn fetch(url: &str, timeout: Duration) -> anyhow::Result<(Vec<u8>, Option<String>)> {
+    let agent = ureq::Agent::config_builder()
+        .timeout_global(Some(timeout))
+        .max_redirects(MAX_REDIRECTS)
+        .build()
+        .new_agent();
+
+    let mut response = agent.get(url).call().map_err(|err| match err {
+        ureq::Error::StatusCode(status) => anyhow!("the server answered {status}"),
+        ureq::Error::Timeout(_) => anyhow!(
+            "timed out after {}; pass a longer --timeout",
+            humantime::format_duration(timeout)
+        ),
+        ureq::Error::TooManyRedirects => anyhow!("more than {MAX_REDIRECTS} redirects"),
+        err => err.into(),
+    })?;
+
+    let content_type = response
+        .headers()
+        .get("content-type")
+        .and_then(|value| value.to_str().ok())
+        .map(String::from);
+    let body = response
+        .body_mut()
+        .with_config()
+        .limit(u64::MAX)
+        .read_to_vec()?;
+    Ok((body, content_type))
+}
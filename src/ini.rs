@@ -0,0 +1,112 @@
+//! INI files, whose values are all strings. Keys before the first `[section]`
+//! header are at the root, and each section is a map under its name.
+
+use anyhow::anyhow;
+use serde_json::{Map, Value};
+
+use super::TranscodeError;
+
+pub fn from_ini(content: &str) -> Result<Value, TranscodeError> {
+    let mut root = Map::new();
+    let mut section: Option<String> = None;
+
+    for (index, line) in content.lines().enumerate() {
+        let number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            let name = name.trim().to_string();
+            match root
+                .entry(name.clone())
+                .or_insert_with(|| Value::Object(Map::new()))
+            {
+                Value::Object(_) => section = Some(name),
+                _ => {
+                    return Err(TranscodeError::at(
+                        number,
+                        None,
+                        format_args!("section [{name}] has the same name as a key"),
+                    ))
+                }
+            }
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once(['=', ':']) else {
+            return Err(TranscodeError::at(
+                number,
+                None,
+                "expected `key = value` or a `[section]` header",
+            ));
+        };
+        let key = key.trim().to_string();
+        let value = Value::String(unquote(value.trim()).to_string());
+
+        if section.is_none() && root.get(&key).is_some_and(Value::is_object) {
+            return Err(TranscodeError::at(
+                number,
+                None,
+                format_args!("key `{key}` has the same name as a section"),
+            ));
+        }
+        let entries = match section.as_ref().and_then(|name| root.get_mut(name)) {
+            Some(Value::Object(entries)) => entries,
+            _ => &mut root,
+        };
+        entries.insert(key, value);
+    }
+
+    Ok(Value::Object(root))
+}
+
+fn unquote(value: &str) -> &str {
+    ['"', '\'']
+        .iter()
+        .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+        .unwrap_or(value)
+}
+
+/// `value` as INI, which holds only one level of maps below the root
+pub fn to_ini(value: &Value) -> anyhow::Result<String> {
+    let Value::Object(root) = value else {
+        return Err(anyhow!("INI output needs a map at the root"));
+    };
+
+    let mut ini = String::new();
+    let mut sections = String::new();
+    for (key, value) in root {
+        match value {
+            Value::Object(entries) => {
+                sections.push_str(&format!("\n[{key}]\n"));
+                for (name, value) in entries {
+                    write_entry(&mut sections, &format!("{key}.{name}"), name, value)?;
+                }
+            }
+            value => write_entry(&mut ini, key, key, value)?,
+        }
+    }
+
+    ini.push_str(&sections);
+    Ok(ini.trim_start_matches('\n').to_string())
+}
+
+fn write_entry(ini: &mut String, path: &str, key: &str, value: &Value) -> anyhow::Result<()> {
+    let value = match value {
+        Value::Array(_) | Value::Object(_) => {
+            return Err(anyhow!(
+                "`{path}` is nested too deeply for INI, which only has sections of plain values"
+            ))
+        }
+        Value::Null => String::new(),
+        Value::String(string) if string.trim() != string || unquote(string) != string => {
+            format!("\"{string}\"")
+        }
+        Value::String(string) => string.clone(),
+        value => value.to_string(),
+    };
+    ini.push_str(&format!("{key} = {value}\n"));
+    Ok(())
+}
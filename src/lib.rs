@@ -1,39 +1,1029 @@
-use std::{fmt::Display, fs, path::PathBuf, str::FromStr};
+use std::{
+    borrow::Cow,
+    ffi::OsStr,
+    fmt::{self, Display},
+    fs,
+    io::{self, BufRead, IsTerminal, Read, Write},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime},
+};
 
 use anyhow::anyhow;
-use clap::{command, Parser, ValueEnum};
+use clap::{CommandFactory, Parser, ValueEnum};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use rayon::{prelude::*, ThreadPoolBuilder};
+use ron::ser::PrettyConfig;
+use serde::Serialize;
+use serde_json::ser::PrettyFormatter;
+
+use edit::Edit;
+use report::{Conversion, Report, Status};
+use schema::Schema;
+use stats::Stats;
+use template::Template;
+
+mod binary;
+mod compare;
+mod config;
+mod dotenv;
+mod edit;
+mod encoding;
+mod flatten;
+#[cfg(feature = "git")]
+mod git;
+#[cfg(feature = "http")]
+mod http;
+mod ini;
+mod ndjson;
+mod newline;
+mod number;
+mod properties;
+mod property_list;
+mod report;
+mod ron_names;
+mod schema;
+#[cfg(feature = "sops")]
+mod sops;
+mod stats;
+mod stream;
+mod table;
+mod template;
+mod toml_value;
+mod value;
+mod watch;
+mod xml;
+mod yaml;
 
 #[derive(Parser, Clone)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
-    /// Input file to transcode
-    #[arg(value_name = "INPUT")]
-    input: PathBuf,
+    /// Input files to transcode, or `-` to read standard input. Without
+    /// --to or --out-dir, a single input followed by the output file to write
+    #[arg(
+        value_name = "PATH",
+        required_unless_present_any = ["git_changed", "files_from", "completions"]
+    )]
+    paths: Vec<PathBuf>,
+
+    /// Output format, repeatable to write several. `--format` still works but
+    /// is deprecated
+    #[arg(
+        value_enum,
+        long,
+        short = 'f',
+        value_name = "FORMAT",
+        visible_alias = "to",
+        alias = "format",
+        env = "RSCRIBE_TO"
+    )]
+    output_format: Vec<FileFormat>,
+
+    /// Write outputs into DIR rather than next to their inputs
+    #[arg(long, value_name = "DIR")]
+    out_dir: Option<PathBuf>,
+
+    /// Print the converted document to standard output instead of writing a
+    /// file. Binary formats aren't printed to a terminal without --force
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "out_dir", "recursive", "merge", "diff", "split", "watch", "check", "dry_run",
+            "in_place", "backup", "git_changed", "files_from"
+        ]
+    )]
+    stdout: bool,
+
+    /// Only check that each PATH parses, reading it as converting it would,
+    /// and print whether it does. Fails if any input doesn't, and writes nothing
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "out_dir", "recursive", "merge", "diff", "split", "watch", "check", "dry_run",
+            "in_place", "backup", "stdout"
+        ]
+    )]
+    validate: bool,
+
+    /// Print a summary of the shape of each PATH instead of converting it:
+    /// its size, how many documents, keys and values of each type it holds,
+    /// how deep it nests, and its longest array and string. With --report,
+    /// the summary goes in the report instead
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "out_dir", "recursive", "merge", "diff", "split", "watch", "check", "dry_run",
+            "in_place", "backup", "git_changed", "files_from", "stdout", "validate"
+        ]
+    )]
+    stats: bool,
+
+    /// Stop at the first input that fails to convert
+    #[arg(long)]
+    fail_fast: bool,
+
+    /// Number of inputs to convert at once, one per CPU by default
+    #[arg(short, long, value_name = "N")]
+    jobs: Option<NonZeroUsize>,
+
+    /// Convert every file found below directory inputs. Symlinked directories
+    /// aren't followed
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Input format, overriding the one the extension gives
+    #[arg(value_enum, long, value_name = "FORMAT", visible_alias = "from")]
+    input_format: Option<FileFormat>,
+
+    /// Indent JSON output across multiple lines (the default)
+    #[arg(long, conflicts_with = "compact")]
+    pretty: bool,
+
+    /// Write JSON output on a single line
+    #[arg(long, env = "RSCRIBE_COMPACT")]
+    compact: bool,
+
+    /// Number of spaces to indent JSON output by, 0 meaning --compact
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with = "compact",
+        env = "RSCRIBE_INDENT",
+        value_parser = clap::value_parser!(u8).range(0..=16)
+    )]
+    indent: Option<u8>,
+
+    /// The line breaks to write in text output: `native` is CRLF on Windows
+    /// and LF elsewhere. Text output always ends with one
+    #[arg(
+        long,
+        value_name = "STYLE",
+        default_value = "lf",
+        env = "RSCRIBE_NEWLINE"
+    )]
+    newline: Newline,
+
+    /// Start text output with a UTF-8 byte order mark, for tools that expect one
+    #[arg(long)]
+    output_bom: bool,
+
+    /// Gzip the output, whatever its extension. Outputs ending in `.gz` are
+    /// gzipped anyway
+    #[arg(long)]
+    compress: bool,
+
+    /// Write the output uncompressed, even if it ends in `.gz`
+    #[arg(long, conflicts_with = "compress")]
+    no_compress: bool,
+
+    /// Allow the output to overwrite the input file, e.g. to reformat it
+    #[arg(long)]
+    in_place: bool,
+
+    /// Whether --in-place keeps the input once it's converted to a new file.
+    /// Pass `--keep-original=false` to remove it after the output is written
+    #[arg(
+        long,
+        value_name = "BOOL",
+        requires = "in_place",
+        num_args = 0..=1,
+        require_equals = true,
+        default_value_t = true,
+        default_missing_value = "true",
+        action = clap::ArgAction::Set
+    )]
+    keep_original: bool,
+
+    /// Convert in memory and fail if the output file doesn't already hold the
+    /// result, writing nothing. Exits 1 if an output is out of date, and 2 if
+    /// one is missing or an input can't be converted
+    #[arg(long, conflicts_with = "dry_run")]
+    check: bool,
+
+    /// Convert in memory and report what would be written, without writing
+    /// or removing anything
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Overwrite output files that already exist
+    #[arg(short = 'F', long, env = "RSCRIBE_FORCE")]
+    force: bool,
+
+    /// Copy an existing output file to its name plus SUFFIX (default `.bak`)
+    /// before replacing it
+    #[arg(
+        long,
+        value_name = "SUFFIX",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = ".bak",
+        env = "RSCRIBE_BACKUP"
+    )]
+    backup: Option<String>,
+
+    /// Set the permissions of the files written, in octal such as `600`.
+    /// Without it, a file that's replaced keeps its permissions, and its owner
+    /// where that's allowed. On Windows only the read-only flag is set, when
+    /// the mode lets no one write
+    #[arg(long, value_name = "OCTAL", value_parser = parse_mode)]
+    mode: Option<u32>,
+
+    /// Create the directories an output goes in when they don't exist.
+    /// --out-dir always creates the ones below it
+    #[arg(short = 'p', long, env = "RSCRIBE_MKDIR")]
+    mkdir: bool,
+
+    /// Exit successfully without writing when the output would overwrite the
+    /// input and --in-place wasn't given
+    #[arg(long)]
+    exit_zero_on_noop: bool,
+
+    /// Decrypt a SOPS encrypted input before converting it
+    #[arg(long)]
+    sops_decrypt: bool,
+
+    /// Re-parse the output and warn of each value it loses or changes, such as
+    /// a null the --null-policy omits, or with `strict` refuse to write it.
+    /// Numbers compare by value and datetimes by the time they name, so an
+    /// integer read back as an equal float passes
+    #[arg(
+        long,
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "warn"
+    )]
+    verify: Option<Verify>,
+
+    /// Write `null` values as empty tables when converting to TOML
+    #[arg(long, conflicts_with = "null_policy")]
+    null_as_empty_table: bool,
+
+    /// What to do with `null` values when converting to TOML, which has no null
+    #[arg(
+        long,
+        value_name = "POLICY",
+        default_value = "error",
+        env = "RSCRIBE_NULL_POLICY"
+    )]
+    null_policy: NullPolicy,
+
+    /// Put a root that isn't a table, such as an array, under KEY when
+    /// converting to TOML, which needs a table at the root
+    #[arg(long, value_name = "KEY")]
+    toml_wrap: Option<String>,
+
+    /// Lift the value under the single key at the root of a TOML input to the
+    /// root of the output, undoing --toml-wrap
+    #[arg(long)]
+    toml_unwrap: bool,
+
+    /// Collapse nested maps and arrays into one map, joining their keys and
+    /// array indexes with --flatten-sep, as in `server.hosts.0`
+    #[arg(long, conflicts_with = "unflatten")]
+    flatten: bool,
+
+    /// Expand a map of keys joined with --flatten-sep into the nested maps and
+    /// arrays they name, undoing --flatten
+    #[arg(long)]
+    unflatten: bool,
+
+    /// The separator --flatten and --unflatten join keys with
+    #[arg(
+        long,
+        value_name = "SEP",
+        default_value = ".",
+        value_parser = clap::builder::NonEmptyStringValueParser::new()
+    )]
+    flatten_sep: String,
+
+    /// How to map TOML datetimes, which other formats lack
+    #[arg(long, value_name = "MODE", default_value = "string")]
+    datetime: DatetimeMode,
+
+    /// The YAML spec to resolve plain scalars in YAML input by. Under 1.1,
+    /// `yes`, `no`, `on` and `off` are booleans, and `0755` and `22:22` integers
+    #[arg(long, value_name = "VERSION", default_value = "1.2")]
+    yaml_compat: YamlCompat,
+
+    /// What to do with aliases in YAML input. `expand` copies in the nodes they
+    /// refer to and merges the maps under `<<` keys
+    #[arg(long, value_name = "POLICY", default_value = "expand")]
+    aliases: Aliases,
+
+    /// What to do with a key repeated in a map of JSON or YAML input. TOML
+    /// input with one always fails
+    #[arg(long, value_name = "POLICY", default_value = "error")]
+    duplicate_keys: DuplicateKeys,
+
+    /// Read text inputs without a byte order mark as ENCODING, such as
+    /// `utf-16le` or `latin1`, rather than UTF-8. UTF-8 and UTF-16 inputs with
+    /// one are always read by it
+    #[arg(long, value_name = "ENCODING", value_parser = encoding::from_label)]
+    input_encoding: Option<&'static encoding_rs::Encoding>,
+
+    /// Fail on inputs whose extension doesn't name a format, rather than
+    /// detecting JSON, TOML or YAML from their content
+    #[arg(long)]
+    no_sniff: bool,
+
+    /// How long to wait fetching an input URL, e.g. `30s` or `2m`
+    #[arg(long, value_name = "DURATION", default_value = "30s", value_parser = humantime::parse_duration)]
+    timeout: Duration,
+
+    /// Read numbers and booleans in INI, CSV, XML and properties input as
+    /// numbers and booleans
+    #[arg(long)]
+    infer_types: bool,
+
+    /// Convert only the part of the document a JSON Pointer such as
+    /// `/services/web/env` or `/items/0` leads to
+    #[arg(long, value_name = "POINTER")]
+    query: Option<String>,
+
+    /// Apply the JSON Merge Patch (RFC 7386) in FILE, which may be in any
+    /// format, after --query. Repeat to apply several in order
+    #[arg(long, value_name = "FILE")]
+    patch: Vec<PathBuf>,
+
+    /// Set the value a JSON Pointer leads to, such as `/server/port=8080`,
+    /// after --query and --patch. VALUE is JSON, so a string is quoted, as
+    /// in `/name='"web"'`. Repeat to set several
+    #[arg(long, value_name = "POINTER=VALUE", value_parser = edit::set)]
+    set: Vec<Edit>,
+
+    /// Remove the key or array item a JSON Pointer leads to, after --query and
+    /// --patch. Repeat to remove several; edits apply in the order given
+    #[arg(long, value_name = "POINTER", value_parser = edit::unset)]
+    unset: Vec<Edit>,
+
+    /// Check the document against the JSON Schema (draft 2020-12) in FILE,
+    /// which may be in any format, after --query and --patch, listing every
+    /// violation. Given a single PATH and nothing to convert it to, only checks
+    /// it
+    #[arg(long, value_name = "FILE")]
+    schema: Option<PathBuf>,
+
+    /// Write the document through the Handlebars template in FILE instead of
+    /// in a format, to the OUTPUT or with --stdout. The document is the
+    /// template's root context, and a key it doesn't have is an error
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = [
+            "output_format", "out_dir", "recursive", "git_changed", "files_from", "split",
+            "multi_doc", "diff", "validate", "stats"
+        ]
+    )]
+    template: Option<PathBuf>,
+
+    /// Sort the keys of every map, bytewise, so uppercase letters come before
+    /// lowercase ones. Arrays keep their order
+    #[arg(long, env = "RSCRIBE_SORT_KEYS")]
+    sort_keys: bool,
+
+    /// Write each YAML document, or each item of an array, to its own file
+    /// numbered after OUTPUT, e.g. `out-0.json`, `out-1.json`
+    #[arg(long)]
+    split: bool,
+
+    /// Write each item of an array as its own document in YAML or BSON output
+    #[arg(long)]
+    multi_doc: bool,
+
+    /// Keep BSON ObjectIds, dates, binary data and decimals as Extended JSON
+    /// maps such as `{"$oid": "..."}` in other formats, so converting back
+    /// restores their types
+    #[arg(long)]
+    bson_extended: bool,
+
+    /// Join the keys of nested maps with SEP in dotenv output
+    #[arg(long, value_name = "SEP", default_value = "_")]
+    env_separator: String,
+
+    /// Write arrays as comma separated lists in dotenv output, rather than failing
+    #[arg(long)]
+    env_join_arrays: bool,
+
+    /// Compare PATH with FILE, in any formats, and print where they differ
+    /// rather than converting anything. Exits 1 if they differ, and 2 if
+    /// either can't be read
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["output_format", "out_dir", "recursive", "merge", "watch", "check", "git_changed", "files_from"]
+    )]
+    diff: Option<PathBuf>,
+
+    /// Deep-merge the inputs in order into the last path, the OUTPUT. Maps merge
+    /// key by key, and anything else in a later input replaces what came before
+    #[arg(
+        long,
+        conflicts_with_all = ["out_dir", "recursive", "in_place", "split", "git_changed", "files_from"]
+    )]
+    merge: bool,
+
+    /// Concatenate arrays when merging rather than replacing them
+    #[arg(long, requires = "merge")]
+    append_arrays: bool,
+
+    /// Keep running after converting, and convert again whenever an input changes
+    #[arg(long, conflicts_with_all = ["git_changed", "files_from", "merge"])]
+    watch: bool,
+
+    /// Convert every added or modified file in the current git working tree,
+    /// or with --validate check it
+    #[arg(long, conflicts_with = "paths")]
+    git_changed: bool,
+
+    /// Convert every path listed in FILE, one per line, or with --validate
+    /// check it. Use `-` to read stdin
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["paths", "git_changed"]
+    )]
+    files_from: Option<PathBuf>,
+
+    /// Override the extension of derived output paths per source extension,
+    /// e.g. `.yml=.toml,.json=.toml`
+    #[arg(long, value_name = "MAP", value_delimiter = ',')]
+    ext_map: Vec<ExtMapping>,
+
+    /// The extension of derived YAML output paths. Inputs may have either
+    #[arg(
+        long,
+        value_name = "EXT",
+        default_value = "yaml",
+        env = "RSCRIBE_YAML_EXT"
+    )]
+    yaml_ext: YamlExt,
+
+    /// Write YAML maps and sequences as indented blocks, or as `{a: 1, b: [2]}`
+    /// on a single line
+    #[arg(
+        long,
+        value_name = "STYLE",
+        default_value = "block",
+        env = "RSCRIBE_YAML_STYLE"
+    )]
+    yaml_style: YamlStyle,
+
+    /// Which strings to quote in YAML output. Keys are only ever quoted when
+    /// they'd be misread
+    #[arg(
+        long,
+        value_name = "WHEN",
+        default_value = "auto",
+        env = "RSCRIBE_YAML_QUOTE"
+    )]
+    yaml_quote: YamlQuote,
+
+    /// Start YAML and TOML output with a comment naming the source file
+    #[arg(long)]
+    emit_header: bool,
+
+    /// Record the header in JSON output under KEY (default `_generated_by`),
+    /// since JSON has no comments
+    #[arg(
+        long,
+        value_name = "KEY",
+        requires = "emit_header",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "_generated_by"
+    )]
+    header_json_key: Option<String>,
+
+    /// Print nothing but errors
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Report detected formats and sizes, and repeat (`-vv`) to also time
+    /// parsing and serializing. Reports go to stderr
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// After the run, print a report of each conversion in FORMAT to stdout,
+    /// or to --report-file.
+    ///
+    /// The JSON report is an object with `conversions`, one per input and
+    /// output pair, and a `summary`. Each conversion has `input` and `output`
+    /// paths, `input_format` and `output_format`, `bytes_read` and
+    /// `bytes_written`, `duration_ms`, a `status` of `ok`, `skipped` or
+    /// `error`, the `error` message, and the `stats` of --stats. Unknown or
+    /// inapplicable values are null.
+    ///
+    /// The summary has `total`, `ok`, `skipped` and `error` counts and the
+    /// run's `exit_status`
+    #[arg(long, value_name = "FORMAT")]
+    report: Option<ReportFormat>,
+
+    /// Write the --report to FILE rather than stdout
+    #[arg(long, value_name = "FILE", requires = "report")]
+    report_file: Option<PathBuf>,
+
+    /// Ignore the config file, `./.rscribe.toml` or
+    /// `$XDG_CONFIG_HOME/rscribe/config.toml`, that sets defaults for options
+    #[arg(long)]
+    no_config: bool,
+
+    /// Print a completion script for SHELL and exit
+    #[arg(long, value_name = "SHELL", exclusive = true)]
+    completions: Option<clap_complete::Shell>,
+
+    /// The ids of the options set by the environment or the config file
+    /// rather than the command line
+    #[arg(skip)]
+    defaulted: Vec<&'static str>,
+
+    /// --set and --unset together, in the order they were given
+    #[arg(skip)]
+    edits: Vec<Edit>,
+}
+
+/// A `SOURCE=TARGET` extension pair from `--ext-map`
+#[derive(Clone)]
+struct ExtMapping {
+    from: String,
+    to: String,
+}
+
+impl FromStr for ExtMapping {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (from, to) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Expected SOURCE=TARGET, got `{s}`"))?;
+        let from = from.trim().trim_start_matches('.');
+        let to = to.trim().trim_start_matches('.');
+        if from.is_empty() || to.is_empty() {
+            return Err(anyhow!("Extensions in `{s}` must not be empty"));
+        }
+
+        Ok(Self {
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+    }
+}
+
+/// Settings that affect how a document is converted. The defaults match the
+/// command line's
+#[derive(Clone)]
+pub struct Options {
+    in_place: bool,
+    keep_original: bool,
+    force: bool,
+    backup: Option<String>,
+    mode: Option<u32>,
+    mkdir: bool,
+    dry_run: bool,
+    check: bool,
+    exit_zero_on_noop: bool,
+    sops_decrypt: bool,
+    verify: Option<Verify>,
+    null_as_empty_table: bool,
+    null_policy: NullPolicy,
+    toml_wrap: Option<String>,
+    toml_unwrap: bool,
+    flatten: bool,
+    unflatten: bool,
+    flatten_sep: String,
+    datetime: DatetimeMode,
+    yaml_compat: YamlCompat,
+    yaml_style: YamlStyle,
+    yaml_quote: YamlQuote,
+    aliases: Aliases,
+    duplicate_keys: DuplicateKeys,
+    input_encoding: Option<&'static encoding_rs::Encoding>,
+    sniff: bool,
+    timeout: Duration,
+    append_arrays: bool,
+    query: Option<String>,
+    patches: Vec<serde_json::Value>,
+    edits: Vec<Edit>,
+    schema: Option<Arc<Schema>>,
+    template: Option<Arc<Template>>,
+    infer_types: bool,
+    sort_keys: bool,
+    split: bool,
+    multi_doc: bool,
+    bson_extended: bool,
+    env_separator: String,
+    env_join_arrays: bool,
+    emit_header: bool,
+    header_json_key: Option<String>,
+    compact: bool,
+    indent: Option<u8>,
+    /// Whether --compact or --indent came from the environment or the config
+    /// file, as a default for JSON output that other formats ignore
+    layout_defaulted: bool,
+    newline: Newline,
+    output_bom: bool,
+    /// Whether to gzip outputs, rather than going by a `.gz` extension
+    compress: Option<bool>,
+    quiet: bool,
+    verbose: u8,
+    /// Where to record each conversion for --report
+    report: Option<Arc<Report>>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            in_place: false,
+            keep_original: true,
+            force: false,
+            backup: None,
+            mode: None,
+            mkdir: false,
+            dry_run: false,
+            check: false,
+            exit_zero_on_noop: false,
+            sops_decrypt: false,
+            verify: None,
+            null_as_empty_table: false,
+            null_policy: NullPolicy::Error,
+            toml_wrap: None,
+            toml_unwrap: false,
+            flatten: false,
+            unflatten: false,
+            flatten_sep: ".".to_string(),
+            datetime: DatetimeMode::String,
+            yaml_compat: YamlCompat::V1_2,
+            yaml_style: YamlStyle::Block,
+            yaml_quote: YamlQuote::Auto,
+            aliases: Aliases::Expand,
+            duplicate_keys: DuplicateKeys::Error,
+            input_encoding: None,
+            sniff: true,
+            timeout: Duration::from_secs(30),
+            append_arrays: false,
+            query: None,
+            patches: Vec::new(),
+            edits: Vec::new(),
+            schema: None,
+            template: None,
+            infer_types: false,
+            sort_keys: false,
+            split: false,
+            multi_doc: false,
+            bson_extended: false,
+            env_separator: "_".to_string(),
+            env_join_arrays: false,
+            emit_header: false,
+            header_json_key: None,
+            compact: false,
+            indent: None,
+            layout_defaulted: false,
+            newline: Newline::Lf,
+            output_bom: false,
+            compress: None,
+            quiet: false,
+            verbose: 0,
+            report: None,
+        }
+    }
+}
+
+/// Builder-style setters for the options that shape a conversion, each named
+/// after the flag it stands for, as in `Options::default().indent(4)`
+impl Options {
+    /// Spaces to indent JSON output by
+    pub fn indent(mut self, indent: u8) -> Self {
+        self.indent = Some(indent);
+        self
+    }
+
+    /// Write JSON on one line
+    pub fn compact(mut self, compact: bool) -> Self {
+        self.compact = compact;
+        self
+    }
+
+    /// Sort the keys of every map
+    pub fn sort_keys(mut self, sort_keys: bool) -> Self {
+        self.sort_keys = sort_keys;
+        self
+    }
+
+    /// What to do with a key given twice in a JSON, YAML or TOML map
+    pub fn duplicate_keys(mut self, duplicate_keys: DuplicateKeys) -> Self {
+        self.duplicate_keys = duplicate_keys;
+        self
+    }
+
+    /// How TOML datetimes are read and written
+    pub fn datetime(mut self, datetime: DatetimeMode) -> Self {
+        self.datetime = datetime;
+        self
+    }
+
+    /// What to do with null in formats that can't hold it
+    pub fn null_policy(mut self, null_policy: NullPolicy) -> Self {
+        self.null_policy = null_policy;
+        self
+    }
+
+    /// Write null as an empty TOML table rather than going by `null_policy`
+    pub fn null_as_empty_table(mut self, null_as_empty_table: bool) -> Self {
+        self.null_as_empty_table = null_as_empty_table;
+        self
+    }
+
+    /// Put a root that isn't a table under `key` when converting to TOML
+    pub fn toml_wrap(mut self, key: impl Into<String>) -> Self {
+        self.toml_wrap = Some(key.into());
+        self
+    }
+
+    /// Lift the value under the single key at the root of a TOML input to the
+    /// root of the output
+    pub fn toml_unwrap(mut self, toml_unwrap: bool) -> Self {
+        self.toml_unwrap = toml_unwrap;
+        self
+    }
+
+    /// Which YAML version plain scalars are read as
+    pub fn yaml_compat(mut self, yaml_compat: YamlCompat) -> Self {
+        self.yaml_compat = yaml_compat;
+        self
+    }
+
+    /// Whether YAML output is written in block or flow style
+    pub fn yaml_style(mut self, yaml_style: YamlStyle) -> Self {
+        self.yaml_style = yaml_style;
+        self
+    }
+
+    /// Which strings YAML output quotes
+    pub fn yaml_quote(mut self, yaml_quote: YamlQuote) -> Self {
+        self.yaml_quote = yaml_quote;
+        self
+    }
+
+    /// What to do with YAML aliases
+    pub fn aliases(mut self, aliases: Aliases) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// Collapse nested maps and arrays into one map, joining their keys with
+    /// the flatten separator
+    pub fn flatten(mut self, flatten: bool) -> Self {
+        self.flatten = flatten;
+        self
+    }
+
+    /// Expand a map of keys joined with the flatten separator into the nested
+    /// maps and arrays they name
+    pub fn unflatten(mut self, unflatten: bool) -> Self {
+        self.unflatten = unflatten;
+        self
+    }
+
+    /// What `flatten` joins keys with and `unflatten` splits them on
+    pub fn flatten_separator(mut self, separator: impl Into<String>) -> Self {
+        self.flatten_sep = separator.into();
+        self
+    }
+
+    /// Convert only the part of the document at this JSON Pointer
+    pub fn query(mut self, pointer: impl Into<String>) -> Self {
+        self.query = Some(pointer.into());
+        self
+    }
+
+    /// Read numbers and booleans in INI, CSV, XML and properties input as
+    /// numbers and booleans
+    pub fn infer_types(mut self, infer_types: bool) -> Self {
+        self.infer_types = infer_types;
+        self
+    }
+
+    /// Write an array as one YAML or BSON document per item
+    pub fn multi_doc(mut self, multi_doc: bool) -> Self {
+        self.multi_doc = multi_doc;
+        self
+    }
+
+    /// Keep BSON ObjectIds, dates, binary data and decimals as Extended JSON
+    /// maps in other formats
+    pub fn bson_extended(mut self, bson_extended: bool) -> Self {
+        self.bson_extended = bson_extended;
+        self
+    }
+
+    /// Check that the output reads back as the same document
+    pub fn verify(mut self, verify: Verify) -> Self {
+        self.verify = Some(verify);
+        self
+    }
+
+    /// Which line endings text output is written with
+    pub fn newline(mut self, newline: Newline) -> Self {
+        self.newline = newline;
+        self
+    }
+}
+
+impl From<&Cli> for Options {
+    fn from(cli: &Cli) -> Self {
+        Self {
+            in_place: cli.in_place,
+            keep_original: cli.keep_original,
+            force: cli.force,
+            backup: cli.backup.clone(),
+            mode: cli.mode,
+            mkdir: cli.mkdir,
+            dry_run: cli.dry_run,
+            check: cli.check,
+            exit_zero_on_noop: cli.exit_zero_on_noop,
+            sops_decrypt: cli.sops_decrypt,
+            verify: cli.verify,
+            null_as_empty_table: cli.null_as_empty_table,
+            null_policy: cli.null_policy,
+            toml_wrap: cli.toml_wrap.clone(),
+            toml_unwrap: cli.toml_unwrap,
+            flatten: cli.flatten,
+            unflatten: cli.unflatten,
+            flatten_sep: cli.flatten_sep.clone(),
+            datetime: cli.datetime,
+            yaml_compat: cli.yaml_compat,
+            yaml_style: cli.yaml_style,
+            yaml_quote: cli.yaml_quote,
+            aliases: cli.aliases,
+            duplicate_keys: cli.duplicate_keys,
+            input_encoding: cli.input_encoding,
+            sniff: !cli.no_sniff,
+            timeout: cli.timeout,
+            append_arrays: cli.append_arrays,
+            query: cli.query.clone(),
+            patches: Vec::new(),
+            edits: cli.edits.clone(),
+            schema: None,
+            template: None,
+            infer_types: cli.infer_types,
+            sort_keys: cli.sort_keys,
+            split: cli.split,
+            multi_doc: cli.multi_doc,
+            bson_extended: cli.bson_extended,
+            env_separator: cli.env_separator.clone(),
+            env_join_arrays: cli.env_join_arrays,
+            emit_header: cli.emit_header,
+            header_json_key: cli.header_json_key.clone(),
+            compact: cli.compact,
+            compress: match (cli.compress, cli.no_compress) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            },
+            quiet: cli.quiet,
+            verbose: cli.verbose,
+            report: None,
+            indent: cli.indent,
+            layout_defaulted: cli
+                .defaulted
+                .iter()
+                .any(|id| matches!(*id, "compact" | "indent")),
+            newline: cli.newline,
+            output_bom: cli.output_bom,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum ReportFormat {
+    Json,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum NullPolicy {
+    /// Fail, naming the first key that holds null
+    Error,
+    /// Drop keys and array items that hold null
+    Omit,
+    /// Write null as the string "null"
+    String,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Verify {
+    /// Write the output anyway, after listing what it loses
+    Warn,
+    /// Fail, listing what the output would lose
+    Strict,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum DatetimeMode {
+    /// Read TOML datetimes as RFC 3339 strings, and write strings as strings
+    String,
+    /// Also write strings that look like datetimes as TOML datetimes
+    Detect,
+    /// Read TOML datetimes with an offset as Unix timestamps
+    Epoch,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum YamlCompat {
+    /// Only `true` and `false` are booleans, as serde_yaml reads them
+    #[value(name = "1.2")]
+    V1_2,
+    /// Also read `yes`, `no`, `on`, `off`, `y` and `n` as booleans, and octal
+    /// and base 60 integers, as older parsers do
+    #[value(name = "1.1")]
+    V1_1,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Aliases {
+    /// Copy in the node each alias refers to, and apply `<<` merge keys
+    Expand,
+    /// Fail on the first alias
+    Error,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Newline {
+    Lf,
+    Crlf,
+    /// CRLF on Windows and LF elsewhere
+    Native,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum YamlExt {
+    Yaml,
+    Yml,
+}
 
-    /// Output file to write
-    #[arg(value_name = "OUTPUT", required_unless_present = "format")]
-    output: Option<PathBuf>,
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum YamlStyle {
+    Block,
+    Flow,
+}
 
-    /// Output format
-    #[arg(value_enum, long, short)]
-    format: Option<FileFormat>,
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum YamlQuote {
+    /// Strings a YAML 1.1 or 1.2 parser would misread, such as `yes`, `0755`
+    /// or `*name`
+    Auto,
+    /// Every string that isn't a key
+    Always,
+    /// Only strings YAML 1.2 would misread, leaving those such as `yes` and
+    /// `22:22` plain for 1.1 parsers to misread
+    NeverUnsafe,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
-enum FileFormat {
+pub enum DuplicateKeys {
+    /// Fail, naming the key and where it's repeated
+    Error,
+    /// Keep the first value given for the key
+    First,
+    /// Keep the last value given for the key
+    Last,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum FileFormat {
     Json,
     Yaml,
     Toml,
+    #[value(name = "msgpack")]
+    MsgPack,
+    Cbor,
+    Bson,
+    Ron,
+    Xml,
+    Csv,
+    Ini,
+    Json5,
+    Ndjson,
+    Plist,
+    Env,
+    Properties,
+    Hcl,
+    /// A format that couldn't be detected from a file's extension
+    #[value(skip)]
     Unknown,
 }
 
+/// The name `--from` and `--to` take, which reports and messages use too
 impl Display for FileFormat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            FileFormat::Json => write!(f, "json"),
-            FileFormat::Yaml => write!(f, "yml"),
-            FileFormat::Toml => write!(f, "toml"),
-            FileFormat::Unknown => write!(f, "txt"),
+        match self.to_possible_value() {
+            Some(value) => f.write_str(value.get_name()),
+            None => write!(f, "txt"),
         }
     }
 }
@@ -46,22 +1036,146 @@ impl FromStr for FileFormat {
             "json" => Ok(Self::Json),
             "yaml" | "yml" => Ok(Self::Yaml),
             "toml" => Ok(Self::Toml),
-            _ => Ok(Self::Unknown),
+            "msgpack" | "mpk" => Ok(Self::MsgPack),
+            "cbor" => Ok(Self::Cbor),
+            "bson" => Ok(Self::Bson),
+            "ron" => Ok(Self::Ron),
+            "xml" => Ok(Self::Xml),
+            "csv" => Ok(Self::Csv),
+            "ini" => Ok(Self::Ini),
+            "json5" => Ok(Self::Json5),
+            "ndjson" | "jsonl" => Ok(Self::Ndjson),
+            "plist" => Ok(Self::Plist),
+            "env" => Ok(Self::Env),
+            "properties" => Ok(Self::Properties),
+            "hcl" | "tf" => Ok(Self::Hcl),
+            _ => Err(anyhow!(
+                "Unknown format `{s}`; expected one of {}",
+                FileFormat::names()
+            )),
+        }
+    }
+}
+
+impl FileFormat {
+    /// The extension given to output paths derived for this format
+    fn default_extension(&self, yaml_ext: YamlExt) -> &'static str {
+        match (self, yaml_ext) {
+            (FileFormat::Yaml, YamlExt::Yaml) => "yaml",
+            (FileFormat::Yaml, YamlExt::Yml) => "yml",
+            (FileFormat::Json, _) => "json",
+            (FileFormat::Toml, _) => "toml",
+            (FileFormat::MsgPack, _) => "msgpack",
+            (FileFormat::Cbor, _) => "cbor",
+            (FileFormat::Bson, _) => "bson",
+            (FileFormat::Ron, _) => "ron",
+            (FileFormat::Xml, _) => "xml",
+            (FileFormat::Csv, _) => "csv",
+            (FileFormat::Ini, _) => "ini",
+            (FileFormat::Json5, _) => "json5",
+            (FileFormat::Ndjson, _) => "ndjson",
+            (FileFormat::Plist, _) => "plist",
+            (FileFormat::Env, _) => "env",
+            (FileFormat::Properties, _) => "properties",
+            (FileFormat::Hcl, _) => "hcl",
+            (FileFormat::Unknown, _) => "txt",
+        }
+    }
+
+    /// The names of the supported formats, for messages
+    fn names() -> String {
+        FileFormat::value_variants()
+            .iter()
+            .filter_map(|format| Some(format.to_possible_value()?.get_name().to_string()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Detect the format from the extension of `path`, looking past a `.gz`
+    fn from_path(path: &Path) -> Self {
+        let path = &uncompressed_path(path);
+        // A bare `.env` is all stem as far as `Path` is concerned
+        if path.file_name() == Some(OsStr::new(".env")) {
+            return FileFormat::Env;
         }
+        path.extension()
+            .and_then(OsStr::to_str)
+            .and_then(|ext| ext.parse().ok())
+            .unwrap_or(FileFormat::Unknown)
+    }
+}
+
+/// `path` with symlinks, `.` and `..` resolved. One that doesn't exist yet
+/// resolves through its parent directory
+fn resolve(path: &Path) -> Option<PathBuf> {
+    fs::canonicalize(path).ok().or_else(|| {
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        Some(fs::canonicalize(parent).ok()?.join(path.file_name()?))
+    })
+}
+
+/// Whether `path` names a gzipped file
+fn is_gzip(path: &Path) -> bool {
+    path.extension() == Some(OsStr::new("gz"))
+}
+
+/// `path` without a trailing `.gz`
+fn uncompressed_path(path: &Path) -> PathBuf {
+    if is_gzip(path) {
+        path.with_extension("")
+    } else {
+        path.to_path_buf()
     }
 }
 
-trait IO {
+/// The path part of `url`, without its query or fragment, or `None` if it
+/// isn't an HTTP(S) URL
+fn url_path(url: &Path) -> Option<&str> {
+    let url = url.to_str()?;
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    let path = rest.find('/').map_or("", |start| &rest[start..]);
+    Some(path.split(['?', '#']).next().unwrap_or_default())
+}
+
+/// The format a `Content-Type` names, such as `application/json` or
+/// `application/x-yaml`. Suffixes like `+json` count too
+fn served_format(content_type: &str) -> FileFormat {
+    let media_type = content_type.split(';').next().unwrap_or_default().trim();
+    let subtype = media_type
+        .split_once('/')
+        .map_or("", |(_, subtype)| subtype);
+    let subtype = subtype.rsplit('+').next().unwrap_or(subtype);
+    let subtype = subtype.strip_prefix("x-").unwrap_or(subtype);
+    subtype
+        .to_ascii_lowercase()
+        .parse()
+        .unwrap_or(FileFormat::Unknown)
+}
+
+/// One end of a conversion: where a document lives and the format it's in
+pub trait IO {
     fn path(&self) -> &PathBuf;
     fn format(&self) -> &FileFormat;
+
+    /// How to refer to this end of the conversion in messages
+    fn name(&self) -> String {
+        self.path().display().to_string()
+    }
 }
 
-struct Input {
+pub struct Input {
     path: PathBuf,
     format: FileFormat,
+    /// Directory `--recursive` found the input under, which `--out-dir` mirrors
+    root: Option<PathBuf>,
 }
 
-struct Output {
+pub struct Output {
     path: PathBuf,
     format: FileFormat,
 }
@@ -74,6 +1188,14 @@ impl IO for Input {
     fn format(&self) -> &FileFormat {
         &self.format
     }
+
+    fn name(&self) -> String {
+        if self.is_stdin() {
+            "standard input".to_string()
+        } else {
+            self.path.display().to_string()
+        }
+    }
 }
 
 impl IO for Output {
@@ -84,119 +1206,2516 @@ impl IO for Output {
     fn format(&self) -> &FileFormat {
         &self.format
     }
+
+    fn name(&self) -> String {
+        if self.path == Path::new("-") {
+            "standard output".to_string()
+        } else {
+            self.path.display().to_string()
+        }
+    }
 }
 
 impl Input {
-    fn new(path: PathBuf) -> Self {
-        let format = match path.extension() {
-            None => FileFormat::Unknown,
-            Some(os_str) => match os_str.to_str() {
-                Some("json") => FileFormat::Json,
-                Some("toml") => FileFormat::Toml,
-                Some("yaml") | Some("yml") => FileFormat::Yaml,
-                _ => FileFormat::Unknown,
-            },
-        };
+    /// An input read from `path`, or standard input for `-`, in `format` or
+    /// else the one its extension names
+    pub fn new(path: PathBuf, format: Option<FileFormat>) -> Self {
+        let format = format.unwrap_or_else(|| match url_path(&path) {
+            Some(url_path) => FileFormat::from_path(Path::new(url_path)),
+            None => FileFormat::from_path(&path),
+        });
+        Self {
+            path,
+            format,
+            root: None,
+        }
+    }
 
-        Self { path, format }
+    /// Whether the document comes from stdin rather than a file
+    fn is_stdin(&self) -> bool {
+        self.path == Path::new("-")
     }
-}
 
-impl Output {
-    fn new(path: PathBuf, format: Option<FileFormat>) -> Self {
-        if let Some(format) = format {
-            Self { path, format }
-        } else {
-            let format = match path.extension() {
-                None => FileFormat::Unknown,
-                Some(os_str) => match os_str.to_str() {
-                    Some("json") => FileFormat::Json,
-                    Some("toml") => FileFormat::Toml,
-                    Some("yaml") | Some("yml") => FileFormat::Yaml,
-                    _ => FileFormat::Unknown,
-                },
-            };
+    /// Whether the document is fetched from an HTTP(S) URL rather than a file
+    fn is_url(&self) -> bool {
+        url_path(&self.path).is_some()
+    }
+
+    /// Whether writing `output` would replace this file, even by another path
+    /// to it through a symlink or `..`
+    fn is_written_by(&self, output: &Output) -> bool {
+        if self.is_stdin() || self.is_url() {
+            return false;
+        }
+        self.path == output.path
+            || matches!(
+                (resolve(&self.path), resolve(&output.path)),
+                (Some(input), Some(output)) if input == output
+            )
+    }
 
-            Self { path, format }
+    fn read(&self) -> anyhow::Result<Vec<u8>> {
+        if self.is_stdin() {
+            let mut content = Vec::new();
+            io::stdin().read_to_end(&mut content)?;
+            Ok(content)
+        } else {
+            read_file(&self.path, is_gzip(&self.path)).map_err(|err| {
+                failure(
+                    Exit::Io,
+                    format_args!("Failed to read {}: {err}", self.path.display()),
+                )
+            })
         }
     }
+
+    /// A buffered reader over the input file, decompressing it if need be
+    fn open(&self) -> anyhow::Result<Box<dyn BufRead>> {
+        let file = fs::File::open(&self.path).map_err(|err| {
+            failure(
+                Exit::Io,
+                format_args!("Failed to read {}: {err}", self.path.display()),
+            )
+        })?;
+        Ok(if is_gzip(&self.path) {
+            Box::new(io::BufReader::new(Gunzip(GzDecoder::new(file))))
+        } else {
+            Box::new(io::BufReader::new(file))
+        })
+    }
 }
 
-pub fn start() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+/// The content of `path`, gunzipped if `compressed`
+fn read_file(path: &Path, compressed: bool) -> io::Result<Vec<u8>> {
+    let content = fs::read(path)?;
+    if !compressed {
+        return Ok(content);
+    }
+    gunzip(&content)
+}
 
-    let input = Input::new(cli.clone().input);
+fn gunzip(content: &[u8]) -> io::Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    Gunzip(GzDecoder::new(content)).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
 
-    let output_path = match cli.output {
-        None => {
-            let mut path = cli.clone().input;
-            path.set_extension(
-                cli.format
-                    .expect("If no output was given, we must have a format flag")
-                    .to_string(),
-            );
-            path
-        }
-        Some(path) => path,
-    };
+/// A gzip decoder whose errors say the data isn't valid gzip, rather than
+/// reading as though the file itself were cut short
+struct Gunzip<R>(GzDecoder<R>);
 
-    let output = Output::new(output_path, cli.format);
+impl<R: Read> Read for Gunzip<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0
+            .read(buf)
+            .map_err(|err| io::Error::new(err.kind(), format!("invalid gzip data: {err}")))
+    }
+}
 
-    if input.format == output.format {
-        return Err(anyhow!("Input format is the same as output format."));
+impl Output {
+    /// An output written to `path`, in `format` or else the one its extension
+    /// names
+    pub fn new(path: PathBuf, format: Option<FileFormat>) -> Self {
+        let format = format.unwrap_or_else(|| FileFormat::from_path(&path));
+        Self { path, format }
     }
 
-    let content = transcode(&input, &output)?;
+    /// Whether the output is gzipped: if its path ends in `.gz`, unless
+    /// --compress or --no-compress says otherwise
+    fn compressed(&self, options: &Options) -> bool {
+        options.compress.unwrap_or_else(|| is_gzip(&self.path))
+    }
+}
 
-    if fs::write(&output.path, content).is_ok() {
-        println!(
-            "Wrote {} to {}",
-            input.path.to_str().unwrap(),
-            output.path.to_str().unwrap()
-        );
-        Ok(())
-    } else {
-        Err(anyhow!("Failed to write file"))
+/// Why a document couldn't be converted
+#[derive(Debug, thiserror::Error)]
+pub enum TranscodeError {
+    /// The input isn't valid in its format. The line and column are 1-based,
+    /// and known for most text formats
+    #[error("{}{message}", position(*.line, *.column))]
+    Parse {
+        line: Option<usize>,
+        column: Option<usize>,
+        message: String,
+    },
+    /// The document can't be written in the output format
+    #[error("{0}")]
+    Serialize(String),
+    /// The formats or options asked for don't go together
+    #[error("{0}")]
+    Unsupported(String),
+    /// The document doesn't match the `--schema`
+    #[error("{0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+fn position(line: Option<usize>, column: Option<usize>) -> String {
+    match (line, column) {
+        (Some(line), Some(column)) => format!("{line}:{column}: "),
+        (Some(line), None) => format!("{line}: "),
+        _ => String::new(),
     }
 }
 
-fn transcode(input: &impl IO, output: &impl IO) -> anyhow::Result<String> {
-    match (input.format(), output.format()) {
-        // Output YAML
-        (FileFormat::Json, FileFormat::Yaml) => {
-            let value =
-                serde_json::from_str::<serde_yaml::Value>(&fs::read_to_string(input.path())?)?;
-            Ok(serde_yaml::to_string(&value).unwrap())
-        }
-        (FileFormat::Toml, FileFormat::Yaml) => {
-            let value = toml::from_str::<serde_yaml::Value>(&fs::read_to_string(input.path())?)?;
-            Ok(serde_yaml::to_string(&value).unwrap())
+impl TranscodeError {
+    fn exit(&self) -> Exit {
+        match self {
+            Self::Parse { .. } => Exit::Parse,
+            Self::Serialize(_) => Exit::Serialize,
+            Self::Unsupported(_) => Exit::Usage,
+            Self::Invalid(_) => Exit::Invalid,
+            Self::Io(_) => Exit::Io,
         }
+    }
 
-        // Output TOML
-        (FileFormat::Json, FileFormat::Toml) => {
-            let value = serde_json::from_str::<toml::Value>(&fs::read_to_string(input.path())?)?;
-            Ok(toml::to_string(&value).unwrap())
-        }
-        (FileFormat::Yaml, FileFormat::Toml) => {
-            let value = serde_yaml::from_str::<toml::Value>(&fs::read_to_string(input.path())?)?;
-            Ok(toml::to_string(&value).unwrap())
-        }
+    /// Classify an error from `render`, which is the document failing to
+    /// serialize unless it's already classified, as a `--schema` violation is
+    fn render(err: anyhow::Error) -> Self {
+        err.downcast::<Self>()
+            .unwrap_or_else(|err| Self::Serialize(format!("{err:#}")))
+    }
 
-        // Output JSON
-        (FileFormat::Yaml, FileFormat::Json) => {
-            let value =
-                serde_yaml::from_str::<serde_json::Value>(&fs::read_to_string(input.path())?)?;
-            Ok(serde_json::to_string(&value).unwrap())
+    /// A syntax error at 1-based `line`, and `column` if it's known
+    fn at(line: usize, column: Option<usize>, message: impl Display) -> Self {
+        Self::Parse {
+            line: Some(line),
+            column,
+            message: message.to_string(),
         }
-        (FileFormat::Toml, FileFormat::Json) => {
-            let value = toml::from_str::<serde_json::Value>(&fs::read_to_string(input.path())?)?;
-            Ok(serde_json::to_string(&value).unwrap())
+    }
+
+    /// A syntax error with no position to give
+    fn syntax(message: impl Display) -> Self {
+        Self::Parse {
+            line: None,
+            column: None,
+            message: message.to_string(),
         }
+    }
+
+    /// A syntax error at `line` and `column` from a crate that ends its
+    /// message with them too, which is dropped
+    fn placed(line: usize, column: usize, message: impl Display) -> Self {
+        let message =
+            message
+                .to_string()
+                .replacen(&format!(" at line {line} column {column}"), "", 1);
+        // serde_json puts an error at the end of a line past its last column,
+        // as column 0 of the next
+        Self::at(line, Some(column.max(1)), message)
+    }
+}
 
-        // Everything else
-        (_, FileFormat::Unknown) => Err(anyhow!("Output format is unknown")),
-        (FileFormat::Unknown, _) => Err(anyhow!("Input format is unknown")),
-        (_, _) => Err(anyhow!("Invalid formats")),
+impl From<serde_json::Error> for TranscodeError {
+    fn from(err: serde_json::Error) -> Self {
+        match err.classify() {
+            serde_json::error::Category::Io => Self::Io(err.into()),
+            _ if err.line() > 0 => Self::placed(err.line(), err.column(), err),
+            _ => Self::syntax(err),
+        }
     }
 }
+
+impl From<serde_yaml::Error> for TranscodeError {
+    fn from(err: serde_yaml::Error) -> Self {
+        match err.location() {
+            Some(at) => Self::placed(at.line(), at.column(), err),
+            None => Self::syntax(err),
+        }
+    }
+}
+
+impl From<toml::de::Error> for TranscodeError {
+    fn from(err: toml::de::Error) -> Self {
+        match err.line_col() {
+            Some((line, column)) => Self::placed(line + 1, column + 1, err),
+            None => Self::syntax(err),
+        }
+    }
+}
+
+impl From<json5::Error> for TranscodeError {
+    fn from(err: json5::Error) -> Self {
+        match err.position() {
+            Some(at) => Self::placed(at.line + 1, at.column + 1, err),
+            None => Self::syntax(err),
+        }
+    }
+}
+
+impl From<ron::error::SpannedError> for TranscodeError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        Self::at(err.span.start.line, Some(err.span.start.col), err.code)
+    }
+}
+
+impl From<hcl::Error> for TranscodeError {
+    fn from(err: hcl::Error) -> Self {
+        match err {
+            hcl::Error::Parse(err) => Self::at(
+                err.location().line(),
+                Some(err.location().column()),
+                err.message(),
+            ),
+            err => Self::syntax(err),
+        }
+    }
+}
+
+impl From<std::str::Utf8Error> for TranscodeError {
+    fn from(err: std::str::Utf8Error) -> Self {
+        Self::syntax(err)
+    }
+}
+
+/// Prefix a conversion error with the name of the input, joining it to the
+/// line and column of a syntax error as in `config.yaml:12:7: message`
+fn located(err: TranscodeError, name: &str) -> anyhow::Error {
+    let message = match err {
+        TranscodeError::Parse { line: Some(_), .. } => format!("{name}:{err}"),
+        ref err => format!("{name}: {err}"),
+    };
+    failure(err.exit(), message)
+}
+
+/// What the process exits with, telling kinds of failure apart. The codes are
+/// stable, for scripts to rely on
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Exit {
+    /// Anything not covered below, or --check or --diff finding differences
+    Failure = 1,
+    /// Bad arguments, or a format that's unknown or can't be converted to
+    Usage = 2,
+    /// An input that doesn't parse
+    Parse = 3,
+    /// A document the output format can't hold
+    Serialize = 4,
+    /// A file or URL that can't be read or written
+    Io = 5,
+    /// A document that doesn't match the --schema
+    Invalid = 6,
+}
+
+/// An error that exits with the code for its kind of failure
+#[derive(Debug)]
+struct Failure {
+    exit: Exit,
+    message: String,
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+fn failure(exit: Exit, message: impl Display) -> anyhow::Error {
+    Failure {
+        exit,
+        message: message.to_string(),
+    }
+    .into()
+}
+
+/// The kind of failure `err` is, going by what raised it
+fn exit_of(err: &anyhow::Error) -> Exit {
+    if let Some(failure) = err.downcast_ref::<Failure>() {
+        failure.exit
+    } else if let Some(err) = err.downcast_ref::<TranscodeError>() {
+        err.exit()
+    } else if err.is::<io::Error>() {
+        Exit::Io
+    } else {
+        Exit::Failure
+    }
+}
+
+/// The kind of failure all of `exits` share, or a plain failure if they differ
+fn shared_exit(exits: &[Exit]) -> Exit {
+    match exits {
+        [first, rest @ ..] if rest.iter().all(|exit| exit == first) => *first,
+        _ => Exit::Failure,
+    }
+}
+
+/// Raised by --check when an output differs from what converting would write,
+/// and by --diff when the documents differ
+#[derive(Debug)]
+struct Mismatch(String);
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+pub fn start() -> anyhow::Result<ExitCode> {
+    let cli = match config::parse() {
+        Ok(cli) => cli,
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            return Ok(ExitCode::from(exit_of(&err) as u8));
+        }
+    };
+    if let Some(shell) = cli.completions {
+        let mut command = Cli::command();
+        let name = command.get_name().to_string();
+        clap_complete::generate(shell, &mut command, name, &mut io::stdout());
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let deprecated =
+        |arg: &OsStr| arg == "--format" || arg.to_string_lossy().starts_with("--format=");
+    if !cli.quiet && std::env::args_os().any(|arg| deprecated(&arg)) {
+        eprintln!("warning: --format is deprecated; use --to or --output-format");
+    }
+    let check = cli.check || cli.diff.is_some();
+    let report = cli
+        .report
+        .map(|ReportFormat::Json| Arc::new(Report::default()));
+    let report_file = cli.report_file.clone();
+    let result = dispatch(cli, report.clone());
+
+    let exit_status = match &result {
+        Ok(()) => 0,
+        // --check and --diff keep 1 for differences, and exit 2 for anything
+        // else that stops them, such as a missing output or a broken input
+        Err(err) if check && !err.is::<Mismatch>() => Exit::Usage as u8,
+        Err(err) => exit_of(err) as u8,
+    };
+
+    if let Some(report) = report {
+        let report = report.to_json(exit_status);
+        match report_file {
+            Some(path) => fs::write(&path, report + "\n").map_err(|err| {
+                failure(
+                    Exit::Io,
+                    format!("Failed to write {}: {err}", path.display()),
+                )
+            })?,
+            None => println!("{report}"),
+        }
+    }
+
+    if let Err(err) = result {
+        eprintln!("Error: {err:?}");
+    }
+    Ok(ExitCode::from(exit_status))
+}
+
+fn dispatch(cli: Cli, report: Option<Arc<Report>>) -> anyhow::Result<()> {
+    let mut options = Options {
+        report,
+        ..Options::from(&cli)
+    };
+    for path in &cli.patch {
+        let (_, patch) = load(&Input::new(path.clone(), None), &options)
+            .map_err(|err| failure(exit_of(&err), format!("Invalid patch: {err:#}")))?;
+        options.patches.push(binary::to_text(patch));
+    }
+    if let Some(path) = &cli.schema {
+        let (input, schema) = load(&Input::new(path.clone(), None), &options)
+            .map_err(|err| failure(exit_of(&err), format!("Invalid schema: {err:#}")))?;
+        let schema = Schema::new(input.name(), &binary::to_text(schema))
+            .map_err(|err| failure(Exit::Usage, format!("Invalid schema: {err}")))?;
+        options.schema = Some(Arc::new(schema));
+    }
+    if let Some(path) = &cli.template {
+        options.template = Some(Arc::new(Template::new(path)?));
+    }
+
+    let mut formats = Vec::new();
+    for format in &cli.output_format {
+        if !formats.contains(format) {
+            formats.push(*format);
+        }
+    }
+    if formats.len() > 1 && !cli.keep_original {
+        return Err(failure(
+            Exit::Usage,
+            format_args!(
+                "--keep-original=false needs a single output format, not {}",
+                formats.len()
+            ),
+        ));
+    }
+
+    let batch = Batch {
+        formats: &formats,
+        out_dir: cli.out_dir.as_deref(),
+        ext_map: &cli.ext_map,
+        yaml_ext: cli.yaml_ext,
+        fail_fast: cli.fail_fast,
+        jobs: cli.jobs,
+    };
+
+    if cli.validate {
+        let inputs: Vec<_> = if cli.git_changed {
+            // Only the changed files that are config files
+            changed_files()?
+                .into_iter()
+                .map(|path| Input::new(path, None))
+                .filter(|input| input.format != FileFormat::Unknown)
+                .collect()
+        } else {
+            let paths = match &cli.files_from {
+                Some(list) => read_file_list(list)?,
+                None => expand_globs(&cli.paths)?,
+            };
+            paths
+                .into_iter()
+                .map(|path| Input::new(path, cli.input_format))
+                .collect()
+        };
+        return inspect_all(&inputs, "Validated", validate, &options);
+    }
+
+    if (cli.git_changed || cli.files_from.is_some()) && formats.is_empty() {
+        let flag = match cli.git_changed {
+            true => "--git-changed",
+            false => "--files-from",
+        };
+        return Err(failure(
+            Exit::Usage,
+            format_args!("{flag} needs --to, unless it's given --validate"),
+        ));
+    }
+
+    if cli.git_changed {
+        // Only pick up config files that aren't already in the target format
+        let (inputs, skipped) = changed_files()?
+            .into_iter()
+            .map(|path| Input::new(path, None))
+            .partition(|input| input.format != FileFormat::Unknown && formats != [input.format]);
+        return batch.run(inputs, skipped.len(), &options);
+    }
+
+    if let Some(other) = &cli.diff {
+        let [path] = &cli.paths[..] else {
+            return Err(failure(
+                Exit::Usage,
+                "--diff compares a single PATH with the file it's given",
+            ));
+        };
+        return diff(
+            &Input::new(path.clone(), cli.input_format),
+            &Input::new(other.clone(), cli.input_format),
+            &options,
+        );
+    }
+
+    if cli.stdout {
+        // A template makes the output whatever it writes
+        let format = match cli.template {
+            Some(_) => Some(FileFormat::Unknown),
+            None => formats.first().copied(),
+        };
+        let (Some(format), [path]) = (format, &cli.paths[..]) else {
+            return Err(failure(
+                Exit::Usage,
+                "--stdout needs a single input PATH, without an OUTPUT, and --to or --template",
+            ));
+        };
+        if formats.len() > 1 {
+            return Err(failure(
+                Exit::Usage,
+                "--stdout writes a single output format",
+            ));
+        }
+        return print(
+            &Input::new(path.clone(), cli.input_format),
+            format,
+            &options,
+        );
+    }
+
+    if cli.stats {
+        let inputs: Vec<_> = expand_globs(&cli.paths)?
+            .into_iter()
+            .map(|path| Input::new(path, cli.input_format))
+            .collect();
+        return inspect_all(&inputs, "Summarized", summarize, &options);
+    }
+
+    if cli.merge {
+        let [inputs @ .., output] = &cli.paths[..] else {
+            unreachable!("PATH is required without --git-changed or --files-from");
+        };
+        if inputs.is_empty() {
+            return Err(failure(
+                Exit::Usage,
+                "--merge needs inputs followed by the OUTPUT path",
+            ));
+        }
+        let format = match formats[..] {
+            [] => None,
+            [format] => Some(format),
+            _ => {
+                return Err(failure(
+                    Exit::Usage,
+                    "--merge writes a single output format",
+                ))
+            }
+        };
+        let inputs: Vec<_> = expand_globs(inputs)?
+            .into_iter()
+            .map(|path| Input::new(path, cli.input_format))
+            .collect();
+        return merge(&inputs, &Output::new(output.clone(), format), &options);
+    }
+
+    if let Some(list) = &cli.files_from {
+        let inputs = read_file_list(list)?
+            .into_iter()
+            .map(|path| Input::new(path, cli.input_format))
+            .collect();
+        return batch.run(inputs, 0, &options);
+    }
+
+    // With --to every PATH is an input, but a last one that isn't there is an
+    // OUTPUT, which a single path can't be in several formats
+    if let ([_, .., last], true) = (&cli.paths[..], formats.len() > 1) {
+        let pattern = last.to_string_lossy();
+        if !last.exists()
+            && url_path(last).is_none()
+            && last != Path::new("-")
+            && !pattern.contains(['*', '?', '['])
+        {
+            return Err(failure(
+                Exit::Usage,
+                format_args!(
+                    "An OUTPUT path can't be given with several --to formats, and {} doesn't \
+                     exist to be an input; leave it out to write each format beside its input",
+                    last.display()
+                ),
+            ));
+        }
+    }
+
+    if !formats.is_empty() || cli.out_dir.is_some() || cli.recursive {
+        let mut inputs = Vec::new();
+        let mut skipped = 0;
+        for path in expand_globs(&cli.paths)? {
+            if !path.is_dir() {
+                inputs.push(Input::new(path, cli.input_format));
+            } else if cli.recursive {
+                // Only pick up files in a known format that isn't already the target
+                for input in walk(&path)? {
+                    match input.format {
+                        FileFormat::Unknown => skipped += 1,
+                        format if formats == [format] => skipped += 1,
+                        _ => inputs.push(input),
+                    }
+                }
+            } else {
+                return Err(failure(
+                    Exit::Usage,
+                    format_args!(
+                        "{} is a directory; pass --recursive to convert the files in it",
+                        path.display()
+                    ),
+                ));
+            }
+        }
+        if cli.watch {
+            let jobs = inputs
+                .into_iter()
+                .map(|input| {
+                    batch
+                        .outputs(&input, &options)
+                        .map(|outputs| (input, outputs))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            return watch::watch(&jobs, &options);
+        }
+        return batch.run(inputs, skipped, &options);
+    }
+
+    match &cli.paths[..] {
+        [input, output] => {
+            let input = Input::new(input.clone(), cli.input_format);
+            let output = Output::new(output.clone(), None);
+            if cli.watch {
+                return watch::watch(&[(input, vec![output])], &options);
+            }
+            convert(&input, &[output], &options)
+        }
+        [input] if options.schema.is_some() => {
+            validate(&Input::new(input.clone(), cli.input_format), &options)
+        }
+        [input] if input.as_os_str() == "-" => Err(failure(
+            Exit::Usage,
+            "An OUTPUT path is required when reading from standard input",
+        )),
+        [_] => Err(failure(
+            Exit::Usage,
+            "Pass an OUTPUT path, --to or --out-dir",
+        )),
+        _ => Err(failure(
+            Exit::Usage,
+            "Converting several inputs needs --to or --out-dir",
+        )),
+    }
+}
+
+/// Where and how to write the outputs of several inputs
+struct Batch<'a> {
+    /// Output formats, or none to keep each input's own format
+    formats: &'a [FileFormat],
+    out_dir: Option<&'a Path>,
+    ext_map: &'a [ExtMapping],
+    yaml_ext: YamlExt,
+    fail_fast: bool,
+    /// Number of inputs to convert at once, or `None` for one per CPU
+    jobs: Option<NonZeroUsize>,
+}
+
+impl Batch<'_> {
+    /// Convert each input, reporting failures as they happen and carrying on
+    /// past them unless `fail_fast` is set
+    fn run(&self, inputs: Vec<Input>, skipped: usize, options: &Options) -> anyhow::Result<()> {
+        // A lone input needs no summary
+        if let ([input], 0) = (&inputs[..], skipped) {
+            return convert(input, &self.outputs(input, options)?, options);
+        }
+
+        // Each status line is printed whole, but in parallel runs the lines
+        // of different inputs come in the order they finish
+        let stop = AtomicBool::new(false);
+        let convert_one = |input: &Input| {
+            if stop.load(Ordering::Relaxed) {
+                return None;
+            }
+            let result = self
+                .outputs(input, options)
+                .inspect_err(|err| record(input, None, Duration::ZERO, Err(err), options))
+                .and_then(|outputs| convert(input, &outputs, options));
+            match &result {
+                Err(_) if self.fail_fast => stop.store(true, Ordering::Relaxed),
+                Err(err) => eprintln!("{err:#}"),
+                Ok(()) => {}
+            }
+            Some(result)
+        };
+
+        let results: Vec<_> = match self.jobs {
+            Some(jobs) if jobs.get() == 1 => inputs.iter().map(convert_one).collect(),
+            jobs => ThreadPoolBuilder::new()
+                .num_threads(jobs.map_or(0, NonZeroUsize::get))
+                .build()?
+                .install(|| inputs.par_iter().map(convert_one).collect()),
+        };
+
+        let mut converted = 0;
+        let mut failed = 0;
+        let mut mismatched = 0;
+        let mut exits = Vec::new();
+        for result in results {
+            match result {
+                Some(Ok(())) => converted += 1,
+                Some(Err(err)) if self.fail_fast => return Err(err),
+                Some(Err(err)) => {
+                    failed += 1;
+                    if err.is::<Mismatch>() {
+                        mismatched += 1;
+                    }
+                    exits.push(exit_of(&err));
+                }
+                None => {}
+            }
+        }
+
+        let summary = format!(
+            "{} {}, skipped {skipped}, {}",
+            if options.dry_run {
+                "Would convert"
+            } else if options.check {
+                "Checked"
+            } else {
+                "Converted"
+            },
+            plural(converted, "file"),
+            plural(failed, "error")
+        );
+        match failed {
+            0 => {
+                status(options, format_args!("{summary}"));
+                Ok(())
+            }
+            _ if failed == mismatched => Err(Mismatch(summary).into()),
+            _ => Err(failure(shared_exit(&exits), summary)),
+        }
+    }
+
+    /// Where to write `input` in each output format
+    fn outputs(&self, input: &Input, options: &Options) -> anyhow::Result<Vec<Output>> {
+        match self.formats {
+            [] => Ok(vec![self.output(input, input.format, options)?]),
+            formats => formats
+                .iter()
+                .map(|format| self.output(input, *format, options))
+                .collect(),
+        }
+    }
+
+    fn output(
+        &self,
+        input: &Input,
+        format: FileFormat,
+        options: &Options,
+    ) -> anyhow::Result<Output> {
+        if input.is_stdin() {
+            return Err(failure(
+                Exit::Usage,
+                "An OUTPUT path is required when reading from standard input",
+            ));
+        }
+        if input.is_url() {
+            return Err(failure(
+                Exit::Usage,
+                "An OUTPUT path is required when reading from a URL",
+            ));
+        }
+
+        let extension = format.default_extension(self.yaml_ext);
+        let path = sibling_path(&input.path, extension, self.ext_map);
+        let relative = match &input.root {
+            Some(root) => path.strip_prefix(root).ok(),
+            None => path.file_name().map(Path::new),
+        };
+        let path = match (self.out_dir, relative) {
+            (Some(dir), Some(relative)) => {
+                let path = dir.join(relative);
+                match path.parent() {
+                    Some(parent) if !options.dry_run && !options.check => {
+                        fs::create_dir_all(parent).map_err(|err| {
+                            failure(
+                                Exit::Io,
+                                format_args!("Failed to create {}: {err}", parent.display()),
+                            )
+                        })?
+                    }
+                    _ => {}
+                }
+                path
+            }
+            _ => path,
+        };
+        Ok(Output::new(path, Some(format)))
+    }
+}
+
+/// Every file below `dir`, without following symlinked directories
+fn walk(dir: &Path) -> anyhow::Result<Vec<Input>> {
+    let mut inputs = Vec::new();
+    for entry in walkdir::WalkDir::new(dir).sort_by_file_name() {
+        let path = entry?.into_path();
+        if path.is_file() {
+            let mut input = Input::new(path, None);
+            input.root = Some(dir.to_path_buf());
+            inputs.push(input);
+        }
+    }
+    Ok(inputs)
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    match count {
+        1 => format!("1 {noun}"),
+        _ => format!("{count} {noun}s"),
+    }
+}
+
+/// Expand paths that don't exist but look like glob patterns, for shells
+/// such as `cmd.exe` that leave that to the program
+fn expand_globs(paths: &[PathBuf]) -> anyhow::Result<Vec<PathBuf>> {
+    let mut expanded = Vec::new();
+    for path in paths {
+        let pattern = path.to_string_lossy();
+        if path.exists() || url_path(path).is_some() || !pattern.contains(['*', '?', '[']) {
+            expanded.push(path.clone());
+            continue;
+        }
+
+        let matches = glob::glob(&pattern)?.collect::<Result<Vec<_>, _>>()?;
+        if matches.is_empty() {
+            return Err(anyhow!("No files match {pattern}"));
+        }
+        expanded.extend(matches);
+    }
+    Ok(expanded)
+}
+
+/// Read newline separated paths from `list`, or from stdin when it is `-`
+fn read_file_list(list: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let lines = if list == Path::new("-") {
+        io::stdin().lock().lines().collect::<Result<Vec<_>, _>>()
+    } else {
+        fs::read_to_string(list).map(|text| text.lines().map(String::from).collect())
+    };
+    let lines = lines.map_err(|err| {
+        let name = if list == Path::new("-") {
+            "standard input".into()
+        } else {
+            list.display().to_string()
+        };
+        failure(Exit::Io, format_args!("Failed to read {name}: {err}"))
+    })?;
+
+    Ok(lines
+        .into_iter()
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// `path` with its extension swapped for `extension`, unless `ext_map` names
+/// a replacement for the current extension
+fn sibling_path(path: &Path, extension: &str, ext_map: &[ExtMapping]) -> PathBuf {
+    // A gzipped input's sibling is gzipped too, as `a.json.gz` to `a.yaml.gz`
+    if is_gzip(path) {
+        let mut path = sibling_path(&uncompressed_path(path), extension, ext_map).into_os_string();
+        path.push(".gz");
+        return path.into();
+    }
+
+    let mapped = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .and_then(|ext| ext_map.iter().find(|mapping| mapping.from == ext));
+
+    let mut path = path.to_path_buf();
+    match mapped {
+        Some(mapping) => path.set_extension(&mapping.to),
+        None => path.set_extension(extension),
+    };
+    path
+}
+
+/// Convert `input` to each of `outputs`, parsing it only once. With several
+/// outputs, one that would overwrite the input is skipped, and a failing
+/// output doesn't stop the others
+fn convert(input: &Input, outputs: &[Output], options: &Options) -> anyhow::Result<()> {
+    let started = Instant::now();
+    let overwrites_input = |output: &Output| input.is_written_by(output) && !options.in_place;
+    let outputs: Vec<&Output> = match outputs {
+        [output] if overwrites_input(output) => {
+            if options.exit_zero_on_noop {
+                record(
+                    input,
+                    Some(output),
+                    started.elapsed(),
+                    Ok(Status::Skipped),
+                    options,
+                );
+                return Ok(());
+            }
+            let err = if input.path == output.path {
+                anyhow!(
+                    "Refusing to overwrite the input file {}; pass --in-place",
+                    input.name()
+                )
+            } else {
+                anyhow!(
+                    "Refusing to overwrite the input file {}, which {} leads to; pass --in-place",
+                    input.name(),
+                    output.path.display()
+                )
+            };
+            record(input, Some(output), started.elapsed(), Err(&err), options);
+            return Err(err);
+        }
+        outputs => outputs
+            .iter()
+            .filter(|output| {
+                if overwrites_input(output) {
+                    record(
+                        input,
+                        Some(output),
+                        started.elapsed(),
+                        Ok(Status::Skipped),
+                        options,
+                    );
+                    status(
+                        options,
+                        format_args!(
+                            "Skipped {} to {}, its own format",
+                            input.name(),
+                            output.format
+                        ),
+                    );
+                }
+                !overwrites_input(output)
+            })
+            .collect(),
+    };
+
+    if let [output] = outputs[..] {
+        if stream::streams(input, output, options) {
+            let result = stream::convert(input, output, options);
+            let outcome = result.as_ref().map(|()| Status::Ok);
+            record(input, Some(output), started.elapsed(), outcome, options);
+            return result;
+        }
+    }
+
+    let (input, value) = match load(input, options) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            for output in &outputs {
+                record(input, Some(output), started.elapsed(), Err(&err), options);
+            }
+            return Err(err);
+        }
+    };
+    let input = &input;
+    let loaded = started.elapsed();
+
+    let mut errors = Vec::new();
+    for output in &outputs {
+        let started = Instant::now();
+        let result = if options.split {
+            split(value.clone(), input, output, options)
+        } else {
+            convert_value(value.clone(), input, output, options)
+        };
+        let outcome = result.as_ref().map(|()| Status::Ok);
+        record(
+            input,
+            Some(output),
+            loaded + started.elapsed(),
+            outcome,
+            options,
+        );
+        if let Err(err) = result {
+            errors.push(err);
+        }
+    }
+
+    match (errors.len(), outputs.len()) {
+        (0, _) => Ok(()),
+        (_, 1) => Err(errors.remove(0)),
+        (failed, total) => {
+            for err in &errors {
+                eprintln!("{err:#}");
+            }
+            let exits: Vec<_> = errors.iter().map(exit_of).collect();
+            Err(failure(
+                shared_exit(&exits),
+                format!("{}: {failed} of {total} outputs failed", input.name()),
+            ))
+        }
+    }
+}
+
+/// Add how converting `input` to `output` went to the --report, if there is one
+fn record(
+    input: &Input,
+    output: Option<&Output>,
+    duration: Duration,
+    outcome: Result<Status, &anyhow::Error>,
+    options: &Options,
+) {
+    if let Some(report) = &options.report {
+        report.record(conversion(input, output, duration, outcome, options));
+    }
+}
+
+/// The record of converting `input` to `output` for the --report
+fn conversion(
+    input: &Input,
+    output: Option<&Output>,
+    duration: Duration,
+    outcome: Result<Status, &anyhow::Error>,
+    options: &Options,
+) -> Conversion {
+    let size = |path: &Path| fs::metadata(path).ok().map(|metadata| metadata.len());
+    let known = |format: FileFormat| (format != FileFormat::Unknown).then(|| format.to_string());
+    let file = !input.is_stdin() && !input.is_url();
+    let wrote =
+        outcome.is_ok_and(|status| status == Status::Ok) && !options.dry_run && !options.check;
+    Conversion {
+        input: input.name(),
+        output: output.map(|output| output.path.display().to_string()),
+        input_format: known(input.format),
+        output_format: output.and_then(|output| known(output.format)),
+        bytes_read: file.then(|| size(&input.path)).flatten(),
+        bytes_written: output
+            .filter(|_| wrote)
+            .and_then(|output| size(&output.path)),
+        duration_ms: duration,
+        status: *outcome.as_ref().unwrap_or(&Status::Error),
+        error: outcome.err().map(|err| format!("{err:#}")),
+        stats: None,
+    }
+}
+
+/// Read and parse `input`, detecting its format from the content if need be.
+/// Returns the input with the format it was parsed as
+fn load(input: &Input, options: &Options) -> anyhow::Result<(Input, serde_json::Value)> {
+    let (input, content, _) = read(input, options)?;
+    let value = parse_read(&input, &content, options)?;
+    Ok((input, value))
+}
+
+/// Read `input`, decoded to UTF-8 if it's text, with the format it's in and the
+/// number of bytes it had
+fn read(input: &Input, options: &Options) -> anyhow::Result<(Input, Vec<u8>, usize)> {
+    if input.is_stdin() && input.format == FileFormat::Unknown && !options.sniff {
+        return Err(failure(
+            Exit::Usage,
+            "Cannot detect the format of standard input; pass --input-format (--from)",
+        ));
+    }
+
+    let (content, served) = if input.is_url() {
+        let (content, content_type) = fetch(input, options.timeout).map_err(|err| {
+            failure(
+                Exit::Io,
+                format_args!("Failed to fetch {}: {err:#}", input.name()),
+            )
+        })?;
+        (
+            content,
+            content_type.map_or(FileFormat::Unknown, |t| served_format(&t)),
+        )
+    } else {
+        (input.read()?, FileFormat::Unknown)
+    };
+    let size = content.len();
+    let known = match input.format {
+        FileFormat::Unknown => served,
+        format => format,
+    };
+    // A UTF-8 byte order mark is dropped by borrowing past it
+    let content = decode(&content, &known, options)
+        .map_err(|err| located(err, &input.name()))?
+        .into_owned();
+    let format = match input.format {
+        FileFormat::Unknown if served != FileFormat::Unknown => served,
+        FileFormat::Unknown if options.sniff => sniff(&content, options).map_err(|attempts| {
+            failure(
+                Exit::Usage,
+                format_args!(
+                    "{}: Couldn't detect the format; pass --input-format (--from). Tried {}",
+                    input.name(),
+                    attempts.join("; ")
+                ),
+            )
+        })?,
+        format => format,
+    };
+    let detected = match input.format {
+        FileFormat::Unknown if served != FileFormat::Unknown => ", from its Content-Type",
+        FileFormat::Unknown => ", detected from its content",
+        _ => "",
+    };
+    let input = Input {
+        path: input.path.clone(),
+        format,
+        root: input.root.clone(),
+    };
+    detail(
+        options,
+        1,
+        format_args!(
+            "Read {} ({}{detected}, {})",
+            input.name(),
+            input.format,
+            plural(size, "byte")
+        ),
+    );
+
+    check_input(&input)
+        .map_err(|err| located(TranscodeError::Unsupported(err.to_string()), &input.name()))?;
+    Ok((input, content, size))
+}
+
+/// Parse the `content` `read` from `input`
+fn parse_read(
+    input: &Input,
+    content: &[u8],
+    options: &Options,
+) -> anyhow::Result<serde_json::Value> {
+    let started = Instant::now();
+    let value =
+        parse(content, input.format(), options).map_err(|err| located(err, &input.name()))?;
+    detail(
+        options,
+        2,
+        format_args!("Parsed {} in {:?}", input.name(), started.elapsed()),
+    );
+    Ok(value)
+}
+
+/// Print where the documents in `before` and `after` differ, failing if they do
+fn diff(before: &Input, after: &Input, options: &Options) -> anyhow::Result<()> {
+    let (_, old) = load(before, options)?;
+    let (_, new) = load(after, options)?;
+    let differences = compare::differences(&binary::to_text(old), &binary::to_text(new));
+    // The differences are what --diff is for, so they alone go to stdout
+    if !options.quiet {
+        for difference in &differences {
+            println!("{difference}");
+        }
+    }
+    match differences.len() {
+        0 => Ok(()),
+        count => Err(Mismatch(format!(
+            "{} and {} differ at {}",
+            before.name(),
+            after.name(),
+            plural(count, "path")
+        ))
+        .into()),
+    }
+}
+
+/// Deep-merge `inputs` in order and write the result to `output`
+fn merge(inputs: &[Input], output: &Output, options: &Options) -> anyhow::Result<()> {
+    let started = Instant::now();
+    let mut merged = None;
+    for input in inputs {
+        let (input, value) = load(input, options).inspect_err(|err| {
+            record(input, Some(output), started.elapsed(), Err(err), options);
+        })?;
+        merged = Some(match merged {
+            None => (input, value),
+            Some((first, mut merged)) => {
+                let mut conflicts = Vec::new();
+                merge_into(
+                    &mut merged,
+                    value,
+                    options.append_arrays,
+                    &mut String::new(),
+                    &mut conflicts,
+                );
+                for conflict in conflicts {
+                    detail(options, 1, format_args!("{}: {conflict}", input.name()));
+                }
+                (first, merged)
+            }
+        });
+    }
+    let (first, merged) = merged.ok_or_else(|| anyhow!("--merge needs at least one input"))?;
+
+    // Messages name every input, as in `base.toml + prod.yaml`
+    let names: Vec<_> = inputs.iter().map(Input::name).collect();
+    let source = Input {
+        path: PathBuf::from(names.join(" + ")),
+        ..first
+    };
+    let result = convert_value(merged, &source, output, options);
+    let outcome = result.as_ref().map(|()| Status::Ok);
+    record(&source, Some(output), started.elapsed(), outcome, options);
+    result
+}
+
+/// Merge `layer` over `base`: maps merge key by key, and anything else in
+/// `layer` replaces what `base` has, except that arrays are concatenated when
+/// `append_arrays` is set. Notes each value replaced by one of another kind in
+/// `conflicts`, at the JSON Pointer `path`
+fn merge_into(
+    base: &mut serde_json::Value,
+    layer: serde_json::Value,
+    append_arrays: bool,
+    path: &mut String,
+    conflicts: &mut Vec<String>,
+) {
+    match (base, layer) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(layer)) => {
+            for (key, value) in layer {
+                let len = compare::push_segment(path, &key);
+                match base.get_mut(&key) {
+                    Some(existing) => merge_into(existing, value, append_arrays, path, conflicts),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+                path.truncate(len);
+            }
+        }
+        (serde_json::Value::Array(base), serde_json::Value::Array(layer)) if append_arrays => {
+            base.extend(layer);
+        }
+        (base, layer) => {
+            let (before, after) = (table::kind(base), table::kind(&layer));
+            if before != after {
+                let path = if path.is_empty() { "/" } else { path };
+                conflicts.push(format!("{path}: {before} replaced by {after}"));
+            }
+            *base = layer;
+        }
+    }
+}
+
+/// Write the parsed `value` of `input` to `output`
+fn convert_value(
+    value: serde_json::Value,
+    input: &Input,
+    output: &Output,
+    options: &Options,
+) -> anyhow::Result<()> {
+    check_formats(input, output, options)
+        .map_err(|err| TranscodeError::Unsupported(err.to_string()))
+        .and_then(|()| {
+            let started = Instant::now();
+            let content = render(value, input, output, options).map_err(TranscodeError::render)?;
+            detail(
+                options,
+                2,
+                format_args!("Serialized {} in {:?}", output.name(), started.elapsed()),
+            );
+            Ok(content)
+        })
+        .map_err(|err| located(err, &input.name()))
+        .and_then(|content| {
+            check_overwrite(input, output, options)?;
+            write(input, output, &content, options)?;
+
+            let writes = !options.dry_run && !options.check;
+            let file = !input.is_stdin() && !input.is_url();
+            if options.in_place && !options.keep_original && file && writes {
+                remove_original(input, output, &content, options)?;
+            }
+            Ok(())
+        })
+}
+
+/// Convert `input` to `format` and write it to stdout, with nothing else, for
+/// --stdout
+fn print(input: &Input, format: FileFormat, options: &Options) -> anyhow::Result<()> {
+    let started = Instant::now();
+    let output = Output::new(PathBuf::from("-"), Some(format));
+    let compress = options.compress == Some(true);
+    let binary = compress
+        || matches!(
+            format,
+            FileFormat::MsgPack | FileFormat::Cbor | FileFormat::Bson
+        );
+    if binary && io::stdout().is_terminal() && !options.force {
+        return Err(failure(
+            Exit::Usage,
+            format_args!(
+                "Refusing to print {} output to a terminal; pipe it elsewhere or pass --force",
+                if compress { "gzipped" } else { "binary" }
+            ),
+        ));
+    }
+
+    let result = load(input, options).and_then(|(input, value)| {
+        check_formats(&input, &output, options)
+            .map_err(|err| TranscodeError::Unsupported(err.to_string()))
+            .and_then(|()| render(value, &input, &output, options).map_err(TranscodeError::render))
+            .map_err(|err| located(err, &input.name()))
+    });
+    let written = result.and_then(|content| {
+        let mut stdout = io::stdout().lock();
+        if compress {
+            let mut encoder = GzEncoder::new(stdout, Compression::default());
+            encoder.write_all(&content)?;
+            encoder.finish()?.flush()?;
+        } else {
+            stdout.write_all(&content)?;
+            stdout.flush()?;
+        }
+        Ok(())
+    });
+    let outcome = written.as_ref().map(|()| Status::Ok);
+    record(input, Some(&output), started.elapsed(), outcome, options);
+    written
+}
+
+/// Read each of `inputs` with `inspect`, going on past those that fail, and
+/// sum up what was `done` with them
+fn inspect_all(
+    inputs: &[Input],
+    done: &str,
+    inspect: fn(&Input, &Options) -> anyhow::Result<()>,
+    options: &Options,
+) -> anyhow::Result<()> {
+    if let [input] = inputs {
+        return inspect(input, options);
+    }
+
+    let mut exits = Vec::new();
+    for input in inputs {
+        if let Err(err) = inspect(input, options) {
+            eprintln!("{err:#}");
+            exits.push(exit_of(&err));
+        }
+    }
+    let summary = format!(
+        "{done} {}, {}",
+        plural(inputs.len(), "file"),
+        plural(exits.len(), "error")
+    );
+    if exits.is_empty() {
+        status(options, format_args!("{summary}"));
+        Ok(())
+    } else {
+        Err(failure(shared_exit(&exits), summary))
+    }
+}
+
+/// Check that `input` parses, and that it matches the --schema if there is
+/// one, without converting it
+fn validate(input: &Input, options: &Options) -> anyhow::Result<()> {
+    let started = Instant::now();
+    let result = load(input, options).and_then(|(input, value)| {
+        let matches = match &options.schema {
+            Some(schema) => {
+                schema
+                    .check(&binary::to_text(value))
+                    .map_err(|err| located(err, &input.name()))?;
+                format!(" and matches {}", schema.name())
+            }
+            None => String::new(),
+        };
+        status(
+            options,
+            format_args!("{}: valid {}{matches}", input.name(), input.format),
+        );
+        Ok(())
+    });
+    let outcome = result.as_ref().map(|()| Status::Ok);
+    record(input, None, started.elapsed(), outcome, options);
+    result
+}
+
+/// Print the `Stats` of `input`, or add them to the --report
+fn summarize(input: &Input, options: &Options) -> anyhow::Result<()> {
+    let started = Instant::now();
+    let result = read(input, options).and_then(|(input, content, size)| {
+        let value = binary::to_text(parse_read(&input, &content, options)?);
+        let stats = match (input.format, &value) {
+            (FileFormat::Ndjson, serde_json::Value::Array(records)) => Stats::new(records, size),
+            (FileFormat::Yaml, serde_json::Value::Array(documents))
+                if serde_yaml::Deserializer::from_slice(&content).count() > 1 =>
+            {
+                Stats::new(documents, size)
+            }
+            (_, value) => Stats::new([value], size),
+        };
+        Ok((input, stats))
+    });
+
+    match (result, &options.report) {
+        (Ok((input, stats)), Some(report)) => {
+            let mut conversion =
+                conversion(&input, None, started.elapsed(), Ok(Status::Ok), options);
+            conversion.stats = Some(stats);
+            report.record(conversion);
+            Ok(())
+        }
+        (Ok((input, stats)), None) => {
+            print!("{} ({}):\n{}", input.name(), input.format, stats.to_text());
+            Ok(())
+        }
+        (Err(err), _) => {
+            record(input, None, started.elapsed(), Err(&err), options);
+            Err(err)
+        }
+    }
+}
+
+/// Refuse to replace a directory, or an existing file without --force
+fn check_overwrite(input: &Input, output: &Output, options: &Options) -> anyhow::Result<()> {
+    if output.path.is_dir() {
+        return Err(anyhow!("{} is a directory", output.path.display()));
+    }
+
+    // --check only reads what is there
+    if !output.path.exists() || options.check {
+        return Ok(());
+    }
+
+    // --in-place already allows reformatting a file over itself, and --backup
+    // keeps a copy of what gets replaced
+    let reformat = options.in_place && input.is_written_by(output);
+    if !options.force && !reformat && options.backup.is_none() {
+        return Err(anyhow!(
+            "Refusing to overwrite {}; pass --force",
+            output.path.display()
+        ));
+    }
+
+    if let Some(backup) = backup_path(output, options) {
+        if backup.exists() && !options.force {
+            return Err(anyhow!(
+                "Refusing to overwrite the backup {}; pass --force",
+                backup.display()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Where --backup copies the existing file at `output`
+fn backup_path(output: &Output, options: &Options) -> Option<PathBuf> {
+    let suffix = options.backup.as_ref()?;
+    let mut path = output.path.clone().into_os_string();
+    path.push(suffix);
+    Some(path.into())
+}
+
+/// Remove the input of an --in-place conversion, once the output is known to
+/// hold `content`
+fn remove_original(
+    input: &Input,
+    output: &Output,
+    content: &[u8],
+    options: &Options,
+) -> anyhow::Result<()> {
+    // Reformatting a file in place leaves nothing to remove
+    if input.is_written_by(output) {
+        return Ok(());
+    }
+
+    if read_file(&output.path, output.compressed(options))
+        .ok()
+        .as_deref()
+        != Some(content)
+    {
+        return Err(anyhow!(
+            "{} doesn't hold the converted output, so {} was kept",
+            output.path.display(),
+            input.name()
+        ));
+    }
+    fs::remove_file(&input.path)?;
+    status(options, format_args!("Removed {}", input.name()));
+    Ok(())
+}
+
+/// Write every document in `value` to its own file numbered after `output`
+fn split(
+    value: serde_json::Value,
+    input: &Input,
+    output: &Output,
+    options: &Options,
+) -> anyhow::Result<()> {
+    let located = |err| located(err, &input.name());
+    check_formats(input, output, options)
+        .map_err(|err| located(TranscodeError::Unsupported(err.to_string())))?;
+    let serde_json::Value::Array(documents) = value else {
+        return Err(anyhow!(
+            "{}: --split needs a multi-document YAML stream or an array at the root",
+            input.name()
+        ));
+    };
+
+    for (index, document) in documents.into_iter().enumerate() {
+        let output = Output {
+            path: numbered_path(&output.path, index),
+            format: output.format,
+        };
+        check_overwrite(input, &output, options)?;
+        let content = render(document, input, &output, options)
+            .map_err(|err| located(TranscodeError::render(err)))?;
+        write(input, &output, &content, options)?;
+    }
+    Ok(())
+}
+
+/// `path` with `-{index}` appended to its file stem
+fn numbered_path(path: &Path, index: usize) -> PathBuf {
+    if is_gzip(path) {
+        let mut path = numbered_path(&uncompressed_path(path), index).into_os_string();
+        path.push(".gz");
+        return path.into();
+    }
+
+    let mut name = path.file_stem().unwrap_or_default().to_os_string();
+    name.push(format!("-{index}"));
+    if let Some(extension) = path.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    path.with_file_name(name)
+}
+
+fn write(input: &Input, output: &Output, content: &[u8], options: &Options) -> anyhow::Result<()> {
+    if options.check {
+        let current = read_file(&output.path, output.compressed(options)).map_err(|err| {
+            failure(
+                Exit::Io,
+                format_args!("Failed to read {}: {err}", output.path.display()),
+            )
+        })?;
+        if current != content {
+            if let (true, Ok(current), Ok(content)) = (
+                options.verbose > 0 && !options.quiet,
+                std::str::from_utf8(&current),
+                std::str::from_utf8(content),
+            ) {
+                let path = output.path.display().to_string();
+                eprint!(
+                    "{}",
+                    similar::TextDiff::from_lines(current, content)
+                        .unified_diff()
+                        .header(&path, &format!("{path} (converted)"))
+                );
+            }
+            return Err(Mismatch(format!("{} is out of date", output.path.display())).into());
+        }
+        status(
+            options,
+            format_args!("{} is up to date", output.path.display()),
+        );
+        return Ok(());
+    }
+
+    if options.dry_run {
+        status(
+            options,
+            format_args!(
+                "Would write {} ({}) to {} ({}){}",
+                input.name(),
+                input.format,
+                output.path.display(),
+                output.format,
+                if output.path.exists() {
+                    ", overwriting it"
+                } else {
+                    ""
+                }
+            ),
+        );
+        return Ok(());
+    }
+
+    make_parent(&output.path, options)?;
+    back_up(output, options)?;
+    write_atomically(
+        &output.path,
+        output.compressed(options),
+        options.mode,
+        |file| file.write_all(content),
+    )
+    .map_err(|err| {
+        failure(
+            Exit::Io,
+            format_args!("Failed to write {}: {err}", output.path.display()),
+        )
+    })?;
+    wrote(input, output, options);
+    Ok(())
+}
+
+/// Report that `input` was written to `output`, with the size of the file at
+/// --verbose
+fn wrote(input: &Input, output: &Output, options: &Options) {
+    let size = fs::metadata(&output.path).map_or(0, |metadata| metadata.len());
+    match options.verbose {
+        0 => status(
+            options,
+            format_args!("Wrote {} to {}", input.name(), output.path.display()),
+        ),
+        _ => status(
+            options,
+            format_args!(
+                "Wrote {} to {} ({}, {})",
+                input.name(),
+                output.path.display(),
+                output.format,
+                plural(usize::try_from(size).unwrap_or(usize::MAX), "byte")
+            ),
+        ),
+    }
+}
+
+/// Report progress on stderr, unless --quiet
+fn status(options: &Options, message: fmt::Arguments) {
+    if !options.quiet {
+        eprintln!("{message}");
+    }
+}
+
+/// Report detail on stderr when --verbose is given at least `level` times
+fn detail(options: &Options, level: u8, message: fmt::Arguments) {
+    if !options.quiet && options.verbose >= level {
+        eprintln!("{message}");
+    }
+}
+
+/// Create the directory `path` goes in for --mkdir, or else fail naming it
+/// when it's missing, which writing would only report as a missing file
+fn make_parent(path: &Path, options: &Options) -> anyhow::Result<()> {
+    let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    else {
+        return Ok(());
+    };
+    if parent.is_dir() {
+        return Ok(());
+    }
+    if parent.exists() {
+        return Err(failure(
+            Exit::Io,
+            format_args!(
+                "Failed to write {}: {} isn't a directory",
+                path.display(),
+                parent.display()
+            ),
+        ));
+    }
+    if !options.mkdir {
+        return Err(failure(
+            Exit::Io,
+            format_args!(
+                "Failed to write {}: the directory {} doesn't exist; pass --mkdir to create it",
+                path.display(),
+                parent.display()
+            ),
+        ));
+    }
+    fs::create_dir_all(parent).map_err(|err| {
+        failure(
+            Exit::Io,
+            format_args!("Failed to create {}: {err}", parent.display()),
+        )
+    })
+}
+
+/// Copy an existing output aside before it's replaced, if --backup says to
+fn back_up(output: &Output, options: &Options) -> anyhow::Result<()> {
+    if let Some(backup) = backup_path(output, options).filter(|_| output.path.exists()) {
+        fs::copy(&output.path, &backup).map_err(|err| {
+            failure(
+                Exit::Io,
+                format_args!(
+                    "Failed to back up {} to {}: {err}",
+                    output.path.display(),
+                    backup.display()
+                ),
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Let `fill` write to a temporary file beside `path`, gzipping what it writes
+/// if `compress`, and rename it into place, so `path` never holds a partial
+/// write. The temporary file is removed if anything fails, and an existing
+/// `path` keeps its permissions
+fn write_atomically<E: From<io::Error>>(
+    path: &Path,
+    compress: bool,
+    mode: Option<u32>,
+    fill: impl FnOnce(&mut dyn Write) -> Result<(), E>,
+) -> Result<(), E> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    // The temporary file is only readable by its owner, which a new file
+    // shouldn't stay, though one replacing a file should until it has that
+    // file's permissions
+    let existing = fs::metadata(path).ok();
+    #[cfg_attr(not(unix), allow(unused_mut))]
+    let mut builder = tempfile::Builder::new();
+    #[cfg(unix)]
+    if existing.is_none() && mode.is_none() {
+        use std::os::unix::fs::PermissionsExt;
+        builder.permissions(fs::Permissions::from_mode(0o666));
+    }
+    // tempfile's errors name the temporary file, which means nothing to
+    // whoever asked for `path`, so only the kind of error is kept
+    let file = builder
+        .tempfile_in(dir)
+        .map_err(|err| io::Error::from(err.kind()))?;
+    let mut writer = io::BufWriter::new(file);
+    if compress {
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        fill(&mut encoder)?;
+        writer = encoder.finish()?;
+    } else {
+        fill(&mut writer)?;
+    }
+    let file = writer
+        .into_inner()
+        .map_err(io::IntoInnerError::into_error)?;
+    file.as_file().sync_all()?;
+    if let Some(metadata) = &existing {
+        keep_owner(file.as_file(), metadata);
+        fs::set_permissions(file.path(), metadata.permissions())?;
+    }
+    if let Some(mode) = mode {
+        let permissions = mode_permissions(mode, file.as_file().metadata()?.permissions());
+        fs::set_permissions(file.path(), permissions)?;
+    }
+    file.persist(path).map_err(io::Error::from)?;
+    Ok(())
+}
+
+/// Give `file` the owner and group of the file it replaces, or just the group
+/// if changing the owner isn't allowed, or neither
+#[cfg(unix)]
+fn keep_owner(file: &fs::File, metadata: &fs::Metadata) {
+    use std::os::unix::fs::{fchown, MetadataExt};
+    let _ = fchown(file, Some(metadata.uid()), Some(metadata.gid()))
+        .or_else(|_| fchown(file, None, Some(metadata.gid())));
+}
+
+#[cfg(not(unix))]
+fn keep_owner(_file: &fs::File, _metadata: &fs::Metadata) {}
+
+#[cfg(unix)]
+fn mode_permissions(mode: u32, _current: fs::Permissions) -> fs::Permissions {
+    use std::os::unix::fs::PermissionsExt;
+    fs::Permissions::from_mode(mode)
+}
+
+/// Windows files only have a read-only flag
+#[cfg(not(unix))]
+fn mode_permissions(mode: u32, mut current: fs::Permissions) -> fs::Permissions {
+    current.set_readonly(mode & 0o222 == 0);
+    current
+}
+
+/// Parse a `--mode` such as `600`, `0644` or `0o644`
+fn parse_mode(mode: &str) -> Result<u32, String> {
+    let digits = mode.strip_prefix("0o").unwrap_or(mode);
+    match u32::from_str_radix(digits, 8) {
+        Ok(mode) if mode <= 0o7777 => Ok(mode),
+        _ => Err(format!(
+            "`{mode}` isn't a mode; give up to four octal digits, such as 644"
+        )),
+    }
+}
+
+/// The body of `input`'s URL and its `Content-Type`, gunzipped if the URL
+/// ends in `.gz`
+#[cfg(feature = "http")]
+fn fetch(input: &Input, timeout: Duration) -> anyhow::Result<(Vec<u8>, Option<String>)> {
+    let url = input.path.to_string_lossy();
+    let (content, content_type) = http::fetch(&url, timeout)?;
+    let gzipped = url_path(&input.path).is_some_and(|path| is_gzip(Path::new(path)));
+    if gzipped {
+        return Ok((gunzip(&content)?, content_type));
+    }
+    Ok((content, content_type))
+}
+
+#[cfg(not(feature = "http"))]
+fn fetch(_input: &Input, _timeout: Duration) -> anyhow::Result<(Vec<u8>, Option<String>)> {
+    Err(anyhow!(
+        "reading URLs requires rscribe to be built with the `http` feature"
+    ))
+}
+
+#[cfg(feature = "git")]
+fn changed_files() -> anyhow::Result<Vec<PathBuf>> {
+    git::changed_files()
+}
+
+#[cfg(not(feature = "git"))]
+fn changed_files() -> anyhow::Result<Vec<PathBuf>> {
+    Err(anyhow!(
+        "--git-changed requires rscribe to be built with the `git` feature"
+    ))
+}
+
+pub fn transcode(
+    content: &[u8],
+    input: &impl IO,
+    output: &impl IO,
+    options: &Options,
+) -> Result<Vec<u8>, TranscodeError> {
+    check_formats(input, output, options)
+        .map_err(|err| TranscodeError::Unsupported(err.to_string()))?;
+    let content = decode(content, input.format(), options)?;
+    let value = parse(&content, input.format(), options)?;
+    render(value, input, output, options).map_err(TranscodeError::render)
+}
+
+/// Convert `content` from one text format to another without touching the
+/// filesystem. Binary output formats such as MessagePack are unsupported here;
+/// use [`transcode`] for those
+pub fn transcode_str(
+    content: &str,
+    from: FileFormat,
+    to: FileFormat,
+    options: &Options,
+) -> Result<String, TranscodeError> {
+    let input = Input::new(PathBuf::from("-"), Some(from));
+    let output = Output::new(PathBuf::from("-"), Some(to));
+    String::from_utf8(transcode(content.as_bytes(), &input, &output, options)?)
+        .map_err(|_| TranscodeError::Unsupported(format!("{to} output is binary, not text")))
+}
+
+/// Explain that `what`, the format of `io`, couldn't be told from its path
+fn undetected(io: &impl IO, what: &str, hint: &str) -> anyhow::Error {
+    match uncompressed_path(io.path()).extension() {
+        Some(ext) => anyhow!(
+            "Couldn't infer {what} from the extension `.{}`; {hint}",
+            ext.to_string_lossy()
+        ),
+        None => anyhow!("Couldn't infer {what} without an extension; {hint}"),
+    }
+}
+
+/// Reject an input whose format is unknown
+fn check_input(input: &impl IO) -> anyhow::Result<()> {
+    match input.format() {
+        FileFormat::Unknown => Err(undetected(
+            input,
+            "the format",
+            "pass --input-format (--from)",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Reject conversions and options that can't apply to this pair of formats
+fn check_formats(input: &impl IO, output: &impl IO, options: &Options) -> anyhow::Result<()> {
+    check_input(input)?;
+    // The template writes whatever the output is
+    if options.template.is_some() {
+        return Ok(());
+    }
+    match (input.format(), output.format()) {
+        // Errors here are prefixed with the input's name already
+        (_, FileFormat::Unknown) => {
+            return Err(undetected(
+                output,
+                &format!("the format of {}", output.name()),
+                &format!(
+                "give it one of the extensions {}, or leave it out and pass --output-format (--to)",
+                FileFormat::names()
+            ),
+            ))
+        }
+        (_, FileFormat::Json5) => return Err(anyhow!("JSON5 output is not supported")),
+        (_, FileFormat::Hcl) => return Err(anyhow!("HCL output is not supported")),
+        (_, FileFormat::Json) => {}
+        (_, format)
+            if (options.compact || options.indent.is_some()) && !options.layout_defaulted =>
+        {
+            return Err(anyhow!(
+                "--compact and --indent only apply to JSON output, not {format}"
+            ))
+        }
+        _ => {}
+    }
+
+    if options.multi_doc && !matches!(output.format(), FileFormat::Yaml | FileFormat::Bson) {
+        return Err(anyhow!(
+            "--multi-doc only applies to YAML and BSON output, not {}",
+            output.format()
+        ));
+    }
+    Ok(())
+}
+
+/// Turn a parsed document into the contents of `output`
+fn render(
+    value: serde_json::Value,
+    input: &impl IO,
+    output: &impl IO,
+    options: &Options,
+) -> anyhow::Result<Vec<u8>> {
+    let mut value = if options.sops_decrypt {
+        decrypt(value)?
+    } else {
+        value
+    };
+
+    // Before the edits, so values given in them stay as typed
+    if options.infer_types
+        && matches!(
+            input.format(),
+            FileFormat::Ini | FileFormat::Csv | FileFormat::Xml | FileFormat::Properties
+        )
+    {
+        infer_types(&mut value);
+    }
+
+    if options.toml_unwrap && *input.format() == FileFormat::Toml {
+        value = match value {
+            serde_json::Value::Object(table) if table.len() == 1 => table
+                .into_iter()
+                .next()
+                .map(|(_, value)| value)
+                .unwrap_or_default(),
+            serde_json::Value::Object(table) => {
+                return Err(anyhow!(
+                    "--toml-unwrap needs a single key at the root of the input, not {}",
+                    table.len()
+                ))
+            }
+            value => value,
+        };
+    }
+
+    // Bytes only stay bytes in a format that has them
+    let bson = *input.format() == FileFormat::Bson && *output.format() != FileFormat::Bson;
+    if !binary::holds_bytes(*output.format()) || options.template.is_some() {
+        value = if bson && options.bson_extended {
+            binary::to_extended(value)
+        } else {
+            binary::to_text(value)
+        };
+    }
+    if bson && !options.bson_extended {
+        value = binary::plain_bson(value);
+    }
+
+    if options.unflatten {
+        value = flatten::unflatten(value, &options.flatten_sep)?;
+    }
+
+    if let Some(pointer) = &options.query {
+        value = query(value, pointer)?;
+    }
+
+    for patch in &options.patches {
+        merge_patch(&mut value, patch);
+    }
+
+    for edit in &options.edits {
+        match edit {
+            Edit::Set(pointer, new) => edit::set_value(&mut value, pointer, new.clone())?,
+            Edit::Unset(pointer) => {
+                if !edit::unset_value(&mut value, pointer) {
+                    detail(
+                        options,
+                        1,
+                        format_args!(
+                            "warning: {}: --unset `{pointer}` leads nowhere, so nothing was removed",
+                            input.name()
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    if let Some(schema) = &options.schema {
+        schema.check(&binary::to_text(value.clone()))?;
+    }
+
+    if options.flatten {
+        value = flatten::flatten(value, &options.flatten_sep)?;
+    }
+
+    let toml = *output.format() == FileFormat::Toml && options.template.is_none();
+    if toml {
+        if let (Some(key), false) = (&options.toml_wrap, value.is_object()) {
+            value = serde_json::json!({ key.clone(): value });
+        }
+    }
+
+    // What --verify expects to read back, before the output format drops or
+    // replaces anything
+    let expected = options.verify.map(|_| value.clone());
+
+    if toml {
+        if options.null_as_empty_table {
+            null_to_empty_table(&mut value);
+        }
+        apply_null_policy(&mut value, options.null_policy, &mut String::new())?;
+    }
+
+    if options.sort_keys {
+        sort_keys(&mut value);
+    }
+
+    if let Some(template) = &options.template {
+        let content = newline::convert(template.render(&value)?.into_bytes(), options.newline);
+        return Ok(match options.output_bom {
+            true => [encoding::BOM, &content].concat(),
+            false => content,
+        });
+    }
+
+    let header = options.emit_header.then(|| {
+        format!(
+            "Generated by rscribe from {} at {}",
+            input.name(),
+            humantime::format_rfc3339_seconds(SystemTime::now())
+        )
+    });
+
+    if let (Some(header), Some(key), FileFormat::Json) =
+        (&header, &options.header_json_key, output.format())
+    {
+        if let Some(map) = value.as_object_mut() {
+            map.shift_insert(0, key.clone(), header.clone().into());
+        }
+    }
+
+    let content = serialize(&value, output.format(), options)?;
+
+    if let (Some(verify), Some(expected)) = (options.verify, &expected) {
+        // Output that doesn't read back is a failure to serialize
+        let mut read_back = parse(&content, output.format(), options)
+            .map_err(|err| TranscodeError::Serialize(err.to_string()))?;
+        // As `expected` already is
+        if !binary::holds_bytes(*output.format()) {
+            read_back = binary::to_text(read_back);
+        }
+        // The header isn't part of the input
+        if let (Some(_), Some(key), Some(map)) =
+            (&header, &options.header_json_key, read_back.as_object_mut())
+        {
+            map.shift_remove(key);
+        }
+        let differences = compare::differences(expected, &read_back);
+        if !differences.is_empty() {
+            let report = differences
+                .iter()
+                .map(|difference| format!("\n  {difference}"))
+                .collect::<String>();
+            if verify == Verify::Strict {
+                return Err(anyhow!("Output does not round-trip to the input:{report}"));
+            }
+            if !options.quiet {
+                eprintln!(
+                    "warning: {}: output does not round-trip to the input:{report}",
+                    output.name()
+                );
+            }
+        }
+    }
+
+    let content = match (header, output.format()) {
+        (Some(header), FileFormat::Yaml | FileFormat::Toml) => {
+            [format!("# {header}\n").into_bytes(), content].concat()
+        }
+        _ => content,
+    };
+    Ok(match output.format() {
+        FileFormat::MsgPack | FileFormat::Cbor | FileFormat::Bson => content,
+        _ if options.output_bom => {
+            [encoding::BOM, &newline::convert(content, options.newline)].concat()
+        }
+        _ => newline::convert(content, options.newline),
+    })
+}
+
+/// Detect the format of `content` by trying JSON, then TOML, then YAML, which
+/// comes last because nearly anything is valid YAML. Fails with how each
+/// attempt went wrong
+fn sniff(content: &[u8], options: &Options) -> Result<FileFormat, Vec<String>> {
+    let mut attempts = Vec::new();
+    if content.trim_ascii_start().starts_with(b"{") || content.trim_ascii_start().starts_with(b"[")
+    {
+        match parse(content, &FileFormat::Json, options) {
+            Ok(_) => return Ok(FileFormat::Json),
+            Err(err) => attempts.push(format!("JSON: {err}")),
+        }
+    } else {
+        attempts.push("JSON: doesn't start with `{` or `[`".to_string());
+    }
+
+    match parse(content, &FileFormat::Toml, options) {
+        Ok(_) => return Ok(FileFormat::Toml),
+        Err(err) => attempts.push(format!("TOML: {err}")),
+    }
+
+    // A lone scalar is as likely to be prose as YAML
+    match parse(content, &FileFormat::Yaml, options) {
+        Ok(value) if value.is_object() || value.is_array() => return Ok(FileFormat::Yaml),
+        Ok(_) => attempts.push("YAML: holds a plain value, not a map or a list".to_string()),
+        Err(err) => attempts.push(format!("YAML: {err}")),
+    }
+    Err(attempts)
+}
+
+/// Read the documents in a YAML stream, each with `read`. Several read as an
+/// array of them
+fn yaml_documents<'de, E>(
+    stream: serde_yaml::Deserializer<'de>,
+    read: impl FnMut(serde_yaml::Deserializer<'de>) -> Result<serde_json::Value, E>,
+) -> Result<serde_json::Value, E> {
+    let mut documents = stream.map(read).collect::<Result<Vec<_>, _>>()?;
+    match documents.len() {
+        0 => Ok(serde_json::Value::Null),
+        1 => Ok(documents.remove(0)),
+        _ => Ok(serde_json::Value::Array(documents)),
+    }
+}
+
+/// `content` as the UTF-8 the parser of `format` expects. Binary formats are
+/// left as they are
+fn decode<'a>(
+    content: &'a [u8],
+    format: &FileFormat,
+    options: &Options,
+) -> Result<Cow<'a, [u8]>, TranscodeError> {
+    match format {
+        FileFormat::MsgPack | FileFormat::Cbor | FileFormat::Bson | FileFormat::Plist => {
+            Ok(Cow::Borrowed(content))
+        }
+        _ => encoding::decode(content, options.input_encoding).map_err(TranscodeError::syntax),
+    }
+}
+
+/// Parse `content` into the `Value` every conversion pivots through
+fn parse(
+    content: &[u8],
+    format: &FileFormat,
+    options: &Options,
+) -> Result<serde_json::Value, TranscodeError> {
+    let value = match format {
+        FileFormat::Json => value::from_json(
+            serde_json::Deserializer::from_slice(content),
+            options.duplicate_keys,
+        ),
+        FileFormat::Yaml => {
+            let text = std::str::from_utf8(content).ok();
+            if let Some(text) = text {
+                yaml::check_aliases(text, options.aliases)?;
+            }
+            let numbers = text.map(yaml::Numbers::scan);
+            let reader = value::Reader {
+                duplicates: options.duplicate_keys,
+                yaml: Some(content),
+                yaml_1_1: options.yaml_compat == YamlCompat::V1_1,
+                numbers: numbers.as_ref(),
+            };
+            let stream = serde_yaml::Deserializer::from_slice(content);
+            let mut value = yaml_documents(stream, |document| reader.read(document))?;
+            yaml::merge_keys(&mut value, &mut String::new())?;
+            Ok(value)
+        }
+        FileFormat::Toml => toml_value::from_toml(std::str::from_utf8(content)?, options.datetime),
+        FileFormat::MsgPack => binary::from_msgpack(content),
+        FileFormat::Cbor => binary::from_cbor(content),
+        FileFormat::Bson => binary::from_bson(content),
+        FileFormat::Ron => {
+            let content = std::str::from_utf8(content)?;
+            ron_names::check(content)?;
+            Ok(ron::from_str(content)?)
+        }
+        FileFormat::Xml => xml::from_xml(std::str::from_utf8(content)?),
+        FileFormat::Csv => table::from_csv(content),
+        FileFormat::Ini => ini::from_ini(std::str::from_utf8(content)?),
+        FileFormat::Json5 => Ok(json5::from_str(std::str::from_utf8(content)?)?),
+        FileFormat::Ndjson => ndjson::from_ndjson(content, options.duplicate_keys),
+        FileFormat::Plist => property_list::from_plist(content),
+        FileFormat::Env => dotenv::from_env(std::str::from_utf8(content)?),
+        FileFormat::Properties => properties::from_properties(std::str::from_utf8(content)?),
+        FileFormat::Hcl => Ok(hcl::from_slice(content)?),
+        FileFormat::Unknown => Err(TranscodeError::Unsupported(
+            "Input format is unknown".to_string(),
+        )),
+    }?;
+    // The binary readers escape their maps as they make byte strings
+    Ok(if binary::holds_bytes(*format) {
+        value
+    } else {
+        binary::escape(value)
+    })
+}
+
+/// Write `value` as JSON, indented as `--indent` and `--compact` say
+fn write_json(
+    value: &impl Serialize,
+    writer: impl Write,
+    options: &Options,
+) -> serde_json::Result<()> {
+    let indent = if options.compact {
+        0
+    } else {
+        options.indent.unwrap_or(2)
+    };
+    if indent == 0 {
+        return serde_json::to_writer(writer, value);
+    }
+
+    let indent = " ".repeat(indent.into());
+    let formatter = PrettyFormatter::with_indent(indent.as_bytes());
+    value.serialize(&mut serde_json::Serializer::with_formatter(
+        writer, formatter,
+    ))
+}
+
+fn serialize(
+    value: &serde_json::Value,
+    format: &FileFormat,
+    options: &Options,
+) -> anyhow::Result<Vec<u8>> {
+    match format {
+        FileFormat::Json => {
+            let mut buffer = Vec::new();
+            write_json(value, &mut buffer, options)?;
+            Ok(buffer)
+        }
+        FileFormat::Yaml if options.multi_doc => {
+            let serde_json::Value::Array(documents) = value else {
+                return Err(anyhow!("--multi-doc needs an array at the root"));
+            };
+            let documents = documents
+                .iter()
+                .map(|document| yaml::to_string(document, options.yaml_style, options.yaml_quote))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(documents.join("---\n").into_bytes())
+        }
+        FileFormat::Yaml => {
+            Ok(yaml::to_string(value, options.yaml_style, options.yaml_quote)?.into_bytes())
+        }
+        FileFormat::Toml => Ok(toml_value::to_toml(value, options.datetime)?.into_bytes()),
+        FileFormat::MsgPack => {
+            number::check(value, "MessagePack", &mut String::new())?;
+            Ok(rmp_serde::to_vec(&number::Native(value))?)
+        }
+        FileFormat::Cbor => {
+            number::check(value, "CBOR", &mut String::new())?;
+            let mut buffer = Vec::new();
+            ciborium::into_writer(&number::Native(value), &mut buffer)?;
+            Ok(buffer)
+        }
+        FileFormat::Bson => binary::to_bson(value, options.multi_doc),
+        FileFormat::Ron => {
+            number::check(value, "RON", &mut String::new())?;
+            let ron = ron::ser::to_string_pretty(&number::Native(value), PrettyConfig::default())?;
+            Ok(ron.into_bytes())
+        }
+        FileFormat::Xml => Ok(xml::to_xml(value)?.into_bytes()),
+        FileFormat::Csv => table::to_csv(value),
+        FileFormat::Ini => Ok(ini::to_ini(value)?.into_bytes()),
+        FileFormat::Json5 => Err(anyhow!("JSON5 output is not supported")),
+        FileFormat::Hcl => Err(anyhow!("HCL output is not supported")),
+        FileFormat::Ndjson => ndjson::to_ndjson(value),
+        FileFormat::Plist => {
+            number::check(value, "property list", &mut String::new())?;
+            property_list::to_plist(value)
+        }
+        FileFormat::Env => {
+            let style = dotenv::EnvStyle {
+                separator: &options.env_separator,
+                join_arrays: options.env_join_arrays,
+            };
+            Ok(dotenv::to_env(value, &style)?.into_bytes())
+        }
+        FileFormat::Properties => {
+            Ok(properties::to_properties(value, &options.flatten_sep)?.into_bytes())
+        }
+        FileFormat::Unknown => Err(anyhow!("Output format is unknown")),
+    }
+}
+
+/// Turn strings that read as numbers or booleans into numbers and booleans
+fn infer_types(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::String(string) => {
+            if let Some(inferred) = infer_type(string) {
+                *value = inferred;
+            }
+        }
+        serde_json::Value::Object(map) => map.values_mut().for_each(infer_types),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(infer_types),
+        _ => {}
+    }
+}
+
+fn infer_type(string: &str) -> Option<serde_json::Value> {
+    let digits = string.trim_start_matches(['-', '+']);
+    // Leading zeros usually mean an identifier such as a zip code, not a number
+    if digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.") {
+        return None;
+    }
+
+    match string {
+        "true" => Some(true.into()),
+        "false" => Some(false.into()),
+        _ if !digits.starts_with(|c: char| c.is_ascii_digit()) => None,
+        _ => string
+            .parse::<i64>()
+            .map(Into::into)
+            .or_else(|_| string.parse::<u64>().map(Into::into))
+            .ok()
+            // Keeping the digits a float would round
+            .or_else(|| string.parse().ok().map(serde_json::Value::Number))
+            .or_else(|| {
+                let float = string.parse::<f64>().ok()?;
+                serde_json::Number::from_f64(float).map(serde_json::Value::Number)
+            }),
+    }
+}
+
+/// The part of `value` the JSON Pointer `pointer` leads to
+fn query(mut value: serde_json::Value, pointer: &str) -> anyhow::Result<serde_json::Value> {
+    if pointer.is_empty() {
+        return Ok(value);
+    }
+    let Some(segments) = pointer.strip_prefix('/') else {
+        return Err(anyhow!("--query `{pointer}` must start with `/`"));
+    };
+
+    let mut path = String::new();
+    for segment in segments.split('/') {
+        let key = segment.replace("~1", "/").replace("~0", "~");
+        let found = match &mut value {
+            serde_json::Value::Object(map) => map.swap_remove(&key),
+            // RFC 6901 indices have no leading zeros
+            serde_json::Value::Array(items) if key == "0" || !key.starts_with('0') => key
+                .parse::<usize>()
+                .ok()
+                .filter(|index| *index < items.len())
+                .map(|index| items.swap_remove(index)),
+            _ => None,
+        };
+        let parent = if path.is_empty() { "/" } else { &path };
+        value = match found {
+            Some(found) => found,
+            None if value.is_array() => {
+                return Err(anyhow!(
+                    "--query `{pointer}`: {parent} has no item `{segment}`"
+                ))
+            }
+            None if value.is_object() => {
+                return Err(anyhow!(
+                    "--query `{pointer}`: {parent} has no key `{segment}`"
+                ))
+            }
+            None => {
+                return Err(anyhow!(
+                    "--query `{pointer}`: {parent} is {}, which has no `{segment}`",
+                    table::kind(&value)
+                ))
+            }
+        };
+        path.push('/');
+        path.push_str(segment);
+    }
+    Ok(value)
+}
+
+/// Apply a JSON Merge Patch: maps merge key by key, a null removes the key it
+/// sits under, and anything else replaces the target wholesale
+fn merge_patch(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    let serde_json::Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = serde_json::Value::Object(serde_json::Map::new());
+    }
+    let serde_json::Value::Object(map) = target else {
+        return;
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            map.shift_remove(key);
+        } else {
+            merge_patch(
+                map.entry(key.clone()).or_insert(serde_json::Value::Null),
+                value,
+            );
+        }
+    }
+}
+
+fn sort_keys(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.sort_keys();
+            map.values_mut().for_each(sort_keys);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(sort_keys),
+        _ => {}
+    }
+}
+
+/// Rid `value` of nulls as `policy` says, with `path` naming it in errors
+fn apply_null_policy(
+    value: &mut serde_json::Value,
+    policy: NullPolicy,
+    path: &mut String,
+) -> anyhow::Result<()> {
+    match value {
+        serde_json::Value::Null => match policy {
+            NullPolicy::Error => {
+                let path = if path.is_empty() { "the root" } else { path };
+                return Err(anyhow!(
+                    "null at `{path}`, which TOML can't represent; pass --null-policy omit or string"
+                ));
+            }
+            NullPolicy::String => *value = "null".into(),
+            // Nulls are dropped by their parent, so only a null root gets here
+            NullPolicy::Omit => *value = serde_json::Value::Object(serde_json::Map::new()),
+        },
+        serde_json::Value::Object(map) => {
+            if policy == NullPolicy::Omit {
+                map.retain(|_, child| !child.is_null());
+            }
+            for (key, child) in map.iter_mut() {
+                let len = path.len();
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+                apply_null_policy(child, policy, path)?;
+                path.truncate(len);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            if policy == NullPolicy::Omit {
+                items.retain(|item| !item.is_null());
+            }
+            for (index, item) in items.iter_mut().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("[{index}]"));
+                apply_null_policy(item, policy, path)?;
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// TOML has no null, so keep the key around as an empty table instead
+fn null_to_empty_table(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for child in map.values_mut() {
+                if child.is_null() {
+                    *child = serde_json::Value::Object(serde_json::Map::new());
+                } else {
+                    null_to_empty_table(child);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(null_to_empty_table),
+        _ => {}
+    }
+}
+
+/// Decrypt a SOPS document. One that can't be decrypted or verified is an
+/// input that can't be read, as one that doesn't parse is
+#[cfg(feature = "sops")]
+fn decrypt(value: serde_json::Value) -> anyhow::Result<serde_json::Value> {
+    if sops::is_encrypted(&value) {
+        sops::decrypt(value).map_err(|err| {
+            TranscodeError::Parse {
+                line: None,
+                column: None,
+                message: format!("{err:#}"),
+            }
+            .into()
+        })
+    } else {
+        Ok(value)
+    }
+}
+
+#[cfg(not(feature = "sops"))]
+fn decrypt(_value: serde_json::Value) -> anyhow::Result<serde_json::Value> {
+    Err(TranscodeError::Unsupported(
+        "--sops-decrypt requires rscribe to be built with the `sops` feature".to_string(),
+    )
+    .into())
+}
@@ -1,4 +1,5 @@
-fn main() -> anyhow::Result<()> {
-    scribe::start()?;
-    Ok(())
+use std::process::ExitCode;
+
+fn main() -> anyhow::Result<ExitCode> {
+    scribe::start()
 }
@@ -0,0 +1,86 @@
+//! Newline-delimited JSON, where each line holds one document and the whole
+//! file maps to an array of them.
+
+use std::io::{BufRead, Write};
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use super::{value::Reader, DuplicateKeys, TranscodeError};
+
+pub fn from_ndjson(content: &[u8], duplicates: DuplicateKeys) -> Result<Value, TranscodeError> {
+    Ok(Value::Array(
+        records(content, duplicates).collect::<Result<_, _>>()?,
+    ))
+}
+
+/// The documents in `reader`, parsed one line at a time
+pub fn records(
+    reader: impl BufRead,
+    duplicates: DuplicateKeys,
+) -> impl Iterator<Item = Result<Value, TranscodeError>> {
+    reader
+        .lines()
+        .enumerate()
+        .filter_map(move |(index, line)| match line {
+            Ok(line) if line.trim().is_empty() => None,
+            Ok(line) => Some(record(&line, duplicates).map_err(|(err, duplicate)| {
+                // Swap serde_json's position, always line 1, for the line in the file
+                let message = match duplicate {
+                    Some(pointer) => format!("duplicate key {pointer}"),
+                    None => err.to_string(),
+                };
+                let message = message
+                    .rsplit_once(" at line ")
+                    .map_or(&*message, |(m, _)| m);
+                TranscodeError::at(index + 1, Some(err.column().max(1)), message)
+            })),
+            Err(err) => Some(Err(err.into())),
+        })
+}
+
+/// The document on one line, or why it isn't one, with the JSON Pointer to
+/// the key if it's a refused duplicate
+fn record(
+    line: &str,
+    duplicates: DuplicateKeys,
+) -> Result<Value, (serde_json::Error, Option<String>)> {
+    let reader = Reader {
+        duplicates,
+        yaml: None,
+        yaml_1_1: false,
+        numbers: None,
+    };
+    let mut deserializer = serde_json::Deserializer::from_str(line);
+    let value = reader
+        .read(&mut deserializer)
+        .map_err(|failure| (failure.error, failure.duplicate.map(|d| d.pointer)))?;
+    deserializer.end().map_err(|err| (err, None))?;
+    Ok(value)
+}
+
+pub fn to_ndjson(value: &Value) -> anyhow::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    write_ndjson(value, &mut buffer)?;
+    Ok(buffer)
+}
+
+pub fn write_ndjson(value: &Value, mut writer: impl Write) -> anyhow::Result<()> {
+    let Value::Array(items) = value else {
+        return Err(anyhow!(
+            "NDJSON output needs an array at the root, with one line written per item"
+        ));
+    };
+
+    for item in items {
+        write_record(item, &mut writer)?;
+    }
+    Ok(())
+}
+
+/// Write `item` as one line
+pub fn write_record(item: &Value, mut writer: impl Write) -> anyhow::Result<()> {
+    serde_json::to_writer(&mut writer, item)?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
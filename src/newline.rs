@@ -0,0 +1,87 @@
+//! The line breaks of text output, whichever the serializers wrote.
+//!
+//! Only the line break bytes of the output change. Those inside strings are
+//! escaped in every format but YAML block scalars, CSV and TOML multi-line
+//! strings, whose parsers read either kind of break as a newline anyway.
+
+use std::io::{self, Write};
+
+use super::Newline;
+
+/// `content` with its line breaks as `newline` says, ending with one
+pub fn convert(content: Vec<u8>, newline: Newline) -> Vec<u8> {
+    let mut writer = Writer::new(Vec::with_capacity(content.len()), newline);
+    // Writing to a Vec can't fail
+    let _ = writer.write_all(&content);
+    writer.finish().unwrap_or_default()
+}
+
+/// Rewrites line breaks on their way to `inner`, and ends what was written
+/// with one when finished
+pub struct Writer<W> {
+    inner: W,
+    crlf: bool,
+    /// A `\r` that may turn out to start a `\r\n`
+    carriage_return: bool,
+    /// Whether the last byte written ended a line, or nothing was written
+    line_ended: bool,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(inner: W, newline: Newline) -> Self {
+        Self {
+            inner,
+            crlf: match newline {
+                Newline::Lf => false,
+                Newline::Crlf => true,
+                Newline::Native => cfg!(windows),
+            },
+            carriage_return: false,
+            line_ended: true,
+        }
+    }
+
+    fn line_break(&self) -> &'static [u8] {
+        if self.crlf {
+            b"\r\n"
+        } else {
+            b"\n"
+        }
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.carriage_return {
+            self.inner.write_all(b"\r")?;
+        }
+        if !self.line_ended {
+            self.inner.write_all(self.line_break())?;
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut converted = Vec::with_capacity(buf.len() + buf.len() / 32);
+        for &byte in buf {
+            if std::mem::take(&mut self.carriage_return) && byte != b'\n' {
+                converted.push(b'\r');
+            }
+            match byte {
+                b'\n' => converted.extend_from_slice(self.line_break()),
+                b'\r' => self.carriage_return = true,
+                byte => converted.push(byte),
+            }
+        }
+        if let Some(last) = buf.last() {
+            self.line_ended = *last == b'\n';
+        }
+        self.inner.write_all(&converted)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,162 @@
+//! Numbers, which serde_json's `arbitrary_precision` keeps as the text they
+//! were read from, so integers past 64 bits and decimals with more digits than
+//! a float holds survive converting between formats that can hold them.
+//!
+//! Other serializers would see such a `Number` as a map with a private key, so
+//! `Native` writes numbers as the integers and floats they know, once `check`
+//! has made sure each fits.
+
+use anyhow::anyhow;
+use serde::{
+    ser::{SerializeMap, SerializeSeq},
+    Serialize, Serializer,
+};
+use serde_json::{Number, Value};
+
+use super::{binary, compare};
+
+/// The key serde_json reads a number from under `arbitrary_precision`, in the
+/// map it hands visitors in its place
+pub const TOKEN: &str = "$serde_json::private::Number";
+
+/// Why a number can't be written as a 64-bit integer or float
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Unfit {
+    /// An integer outside `i64` and `u64`
+    Integer,
+    /// A decimal no `f64` rounds back to
+    Float,
+}
+
+impl Unfit {
+    pub fn explain(self, format: &str) -> String {
+        match self {
+            Unfit::Integer => format!("is too large for a {format} integer"),
+            Unfit::Float => format!("has more digits than a {format} float holds"),
+        }
+    }
+}
+
+/// Why `number` can't be written as a 64-bit integer or float, if it can't
+pub fn unfit(number: &Number) -> Option<Unfit> {
+    if number.is_i64() || number.is_u64() {
+        return None;
+    }
+    let text = number.to_string();
+    if !text.contains(['.', 'e', 'E']) {
+        return Some(Unfit::Integer);
+    }
+    match number.as_f64().and_then(Number::from_f64) {
+        Some(float) if equal(&float, number) => None,
+        _ => Some(Unfit::Float),
+    }
+}
+
+/// Whether two numbers have the same value, however they're written, so `1`
+/// equals `1.0` and `1e2` equals `100`
+pub fn equal(a: &Number, b: &Number) -> bool {
+    match (decimal(&a.to_string()), decimal(&b.to_string())) {
+        (Some(a), Some(b)) => a == b,
+        _ => a.as_f64() == b.as_f64(),
+    }
+}
+
+/// `text` as its sign, its significant digits and the power of ten after the
+/// last of them, with zero as no digits
+fn decimal(text: &str) -> Option<(bool, String, i64)> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+        None => (text, 0),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{whole}{fraction}");
+    if !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+
+    let significant = digits.trim_start_matches('0');
+    let trimmed = significant.trim_end_matches('0');
+    if trimmed.is_empty() {
+        return Some((false, String::new(), 0));
+    }
+    let zeros = i64::try_from(significant.len() - trimmed.len()).ok()?;
+    let places = i64::try_from(fraction.len()).ok()?;
+    Some((negative, trimmed.to_string(), exponent - places + zeros))
+}
+
+/// Fail on the first number in `value` that `format` can't hold, which only
+/// has 64-bit integers and floats
+pub fn check(value: &Value, format: &str, path: &mut String) -> anyhow::Result<()> {
+    match value {
+        Value::Number(number) => match unfit(number) {
+            Some(unfit) => {
+                let at = if path.is_empty() { "the root" } else { path };
+                Err(anyhow!("{number} at `{at}` {}", unfit.explain(format)))
+            }
+            None => Ok(()),
+        },
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                let len = compare::push_segment(path, &index.to_string());
+                check(item, format, path)?;
+                path.truncate(len);
+            }
+            Ok(())
+        }
+        Value::Object(entries) => {
+            for (key, value) in entries {
+                let len = compare::push_segment(path, key);
+                check(value, format, path)?;
+                path.truncate(len);
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Write `number` as the 64-bit integer or float it holds
+pub fn serialize<S: Serializer>(number: &Number, serializer: S) -> Result<S::Ok, S::Error> {
+    if let Some(integer) = number.as_i64() {
+        serializer.serialize_i64(integer)
+    } else if let Some(integer) = number.as_u64() {
+        serializer.serialize_u64(integer)
+    } else {
+        serializer.serialize_f64(number.as_f64().unwrap_or_default())
+    }
+}
+
+/// `value` with its numbers serialized by `serialize`, for serializers other
+/// than serde_json's
+pub struct Native<'a>(pub &'a Value);
+
+impl Serialize for Native<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Some(bytes) = binary::decoded(self.0) {
+            return serializer.serialize_bytes(&bytes);
+        }
+        match self.0 {
+            Value::Number(number) => serialize(number, serializer),
+            Value::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(&Native(item))?;
+                }
+                seq.end()
+            }
+            Value::Object(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    let key = binary::written_key(key, entries.len() == 1);
+                    map.serialize_entry(key, &Native(value))?;
+                }
+                map.end()
+            }
+            value => value.serialize(serializer),
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Java `.properties` files of `key=value` lines.
+//!
+//! Reading follows `java.util.Properties`: keys end at the first unescaped
+//! `=`, `:` or space, a line ending in a backslash carries on to the next, and
+//! lines starting with `#` or `!` are comments. The result is a flat map of
+//! strings; `--unflatten` nests it on its dotted keys. Writing joins the keys of
+//! nested maps and arrays to dotted ones, in order, leaving keys that are
+//! dotted already as they are, and escapes anything past ASCII as `\uXXXX` so
+//! the file reads back under Java's default ISO 8859-1.
+
+use std::{iter::Peekable, str::Chars};
+
+use anyhow::anyhow;
+use serde_json::{Map, Value};
+
+use super::{flatten, TranscodeError};
+
+pub fn from_properties(content: &str) -> Result<Value, TranscodeError> {
+    let mut entries = Map::new();
+    let mut logical = String::new();
+    let mut start = 0;
+    let mut continued = false;
+
+    for (index, line) in content.split('\n').enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let line = line.trim_start_matches([' ', '\t', '\x0c']);
+        if !continued {
+            if line.is_empty() || line.starts_with(['#', '!']) {
+                continue;
+            }
+            start = index + 1;
+        }
+
+        let backslashes = line.len() - line.trim_end_matches('\\').len();
+        continued = backslashes % 2 == 1;
+        if continued {
+            logical.push_str(&line[..line.len() - 1]);
+            continue;
+        }
+        logical.push_str(line);
+        let (key, value) = entry(&logical).map_err(|err| TranscodeError::at(start, None, err))?;
+        entries.insert(key, Value::String(value));
+        logical.clear();
+    }
+    // The last line may end in a backslash with nothing after it
+    if continued {
+        let (key, value) = entry(&logical).map_err(|err| TranscodeError::at(start, None, err))?;
+        entries.insert(key, Value::String(value));
+    }
+    Ok(Value::Object(entries))
+}
+
+/// The key and value of a logical line, with the continuations joined
+fn entry(line: &str) -> anyhow::Result<(String, String)> {
+    let mut chars = line.chars().peekable();
+    let mut key = String::new();
+    while let Some(&char) = chars.peek() {
+        match char {
+            '=' | ':' | ' ' | '\t' | '\x0c' => break,
+            '\\' => {
+                chars.next();
+                unescape(&mut chars, &mut key)?;
+            }
+            char => {
+                chars.next();
+                key.push(char);
+            }
+        }
+    }
+
+    skip_space(&mut chars);
+    if chars.next_if(|char| matches!(char, '=' | ':')).is_some() {
+        skip_space(&mut chars);
+    }
+
+    let mut value = String::new();
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => unescape(&mut chars, &mut value)?,
+            char => value.push(char),
+        }
+    }
+    Ok((key, value))
+}
+
+fn skip_space(chars: &mut Peekable<Chars>) {
+    while chars
+        .next_if(|char| matches!(char, ' ' | '\t' | '\x0c'))
+        .is_some()
+    {}
+}
+
+/// Push what the escape after a backslash stands for. Anything but the
+/// escapes Java knows stands for itself, and a lone trailing one for nothing
+fn unescape(chars: &mut Peekable<Chars>, out: &mut String) -> anyhow::Result<()> {
+    match chars.next() {
+        Some('t') => out.push('\t'),
+        Some('n') => out.push('\n'),
+        Some('r') => out.push('\r'),
+        Some('f') => out.push('\x0c'),
+        Some('u') => {
+            let unit = code_unit(chars)?;
+            // A character past the Basic Multilingual Plane is escaped as a
+            // UTF-16 surrogate pair
+            let units = if (0xD800..0xDC00).contains(&unit) {
+                if chars.next() != Some('\\') || chars.next() != Some('u') {
+                    return Err(anyhow!("\\u{unit:04X} is half of a surrogate pair"));
+                }
+                vec![unit, code_unit(chars)?]
+            } else {
+                vec![unit]
+            };
+            for char in char::decode_utf16(units) {
+                out.push(char.map_err(|err| {
+                    anyhow!(
+                        "\\u{:04X} is half of a surrogate pair",
+                        err.unpaired_surrogate()
+                    )
+                })?);
+            }
+        }
+        Some(char) => out.push(char),
+        None => {}
+    }
+    Ok(())
+}
+
+/// The four hex digits of a `\uXXXX` escape
+fn code_unit(chars: &mut Peekable<Chars>) -> anyhow::Result<u16> {
+    let digits: String = chars.take(4).collect();
+    if digits.len() != 4 {
+        return Err(anyhow!("\\u{digits} needs four hex digits"));
+    }
+    u16::from_str_radix(&digits, 16).map_err(|_| anyhow!("\\u{digits} needs four hex digits"))
+}
+
+/// `value` as properties lines, the keys of its nested maps and arrays joined
+/// with `separator`, as `--flatten` joins them
+pub fn to_properties(value: &Value, separator: &str) -> anyhow::Result<String> {
+    let Value::Object(root) = value else {
+        return Err(anyhow!("properties output needs a map at the root"));
+    };
+    if root.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut lines = String::new();
+    for (key, value) in flatten::join_keys(value.clone(), separator, true)? {
+        let text = match value {
+            Value::Null => String::new(),
+            Value::String(string) => string,
+            Value::Object(_) => {
+                return Err(anyhow!(
+                    "`{key}` is an empty map, which has no place in properties"
+                ))
+            }
+            Value::Array(_) => {
+                return Err(anyhow!(
+                    "`{key}` is an empty array, which has no place in properties"
+                ))
+            }
+            value => value.to_string(),
+        };
+        lines.push_str(&escape(&key, true));
+        lines.push('=');
+        lines.push_str(&escape(&text, false));
+        lines.push('\n');
+    }
+    Ok(lines)
+}
+
+/// `text` escaped to read back the same as a key, or as a value, which only
+/// needs its leading space kept
+fn escape(text: &str, key: bool) -> String {
+    let mut escaped = String::new();
+    for (index, char) in text.chars().enumerate() {
+        match char {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\x0c' => escaped.push_str("\\f"),
+            ' ' if key || index == 0 => escaped.push_str("\\ "),
+            '=' | ':' | '#' | '!' if key => {
+                escaped.push('\\');
+                escaped.push(char);
+            }
+            ' '..='~' => escaped.push(char),
+            char => {
+                for unit in char.encode_utf16(&mut [0; 2]) {
+                    escaped.push_str(&format!("\\u{unit:04X}"));
+                }
+            }
+        }
+    }
+    escaped
+}
@@ -0,0 +1,93 @@
+//! Apple property lists, read as XML or binary and written as XML. `Data` is a
+//! byte string and `Date` an RFC 3339 string.
+
+use std::io::Cursor;
+
+use anyhow::anyhow;
+use plist::{Date, Dictionary};
+use serde_json::{Map, Number, Value};
+
+use super::{binary, TranscodeError};
+
+pub fn from_plist(content: &[u8]) -> Result<Value, TranscodeError> {
+    match plist::Value::from_reader(Cursor::new(content)) {
+        Ok(value) => Ok(to_json(value)),
+        Err(err) => Err(TranscodeError::syntax(err)),
+    }
+}
+
+fn to_json(value: plist::Value) -> Value {
+    match value {
+        plist::Value::Array(items) => Value::Array(items.into_iter().map(to_json).collect()),
+        plist::Value::Dictionary(entries) => binary::escaped(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key, to_json(value)))
+                .collect::<Map<_, _>>(),
+        ),
+        plist::Value::Boolean(bool) => Value::Bool(bool),
+        plist::Value::Data(bytes) => binary::bytes(&bytes),
+        plist::Value::Date(date) => Value::String(date.to_xml_format()),
+        // NaN and the infinities become null, as they do when parsing JSON
+        plist::Value::Real(real) => Number::from_f64(real).map_or(Value::Null, Value::Number),
+        plist::Value::Integer(integer) => match integer.as_signed() {
+            Some(signed) => Value::from(signed),
+            None => Value::from(integer.as_unsigned()),
+        },
+        plist::Value::String(string) => Value::String(string),
+        plist::Value::Uid(uid) => Value::from(uid.get()),
+        _ => Value::Null,
+    }
+}
+
+/// `value` as an XML plist. Strings in the exact form dates are written in
+/// become dates again, but base64 strings stay strings, since any short word
+/// is valid base64
+pub fn to_plist(value: &Value) -> anyhow::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    from_json(value, &mut String::new())?.to_writer_xml(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn from_json(value: &Value, path: &mut String) -> anyhow::Result<plist::Value> {
+    if let Some(bytes) = binary::decoded(value) {
+        return Ok(plist::Value::Data(bytes));
+    }
+    Ok(match value {
+        Value::Null => {
+            let path = if path.is_empty() { "/" } else { path };
+            return Err(anyhow!("{path}: null has no property list equivalent"));
+        }
+        Value::Bool(bool) => plist::Value::Boolean(*bool),
+        Value::Number(number) => match (number.as_i64(), number.as_u64()) {
+            (Some(signed), _) => plist::Value::Integer(signed.into()),
+            (_, Some(unsigned)) => plist::Value::Integer(unsigned.into()),
+            _ => plist::Value::Real(number.as_f64().unwrap_or_default()),
+        },
+        Value::String(string) => match Date::from_xml_format(string) {
+            Ok(date) => plist::Value::Date(date),
+            Err(_) => plist::Value::String(string.clone()),
+        },
+        Value::Array(items) => {
+            let mut converted = Vec::with_capacity(items.len());
+            for (index, item) in items.iter().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("/{index}"));
+                converted.push(from_json(item, path)?);
+                path.truncate(len);
+            }
+            plist::Value::Array(converted)
+        }
+        Value::Object(entries) => {
+            let mut converted = Dictionary::new();
+            for (key, value) in entries {
+                let len = path.len();
+                path.push_str(&format!("/{}", key.replace('~', "~0").replace('/', "~1")));
+                let written = binary::written_key(key, entries.len() == 1);
+                converted.insert(written.to_string(), from_json(value, path)?);
+                path.truncate(len);
+            }
+            plist::Value::Dictionary(converted)
+        }
+    })
+}
@@ -0,0 +1,106 @@
+//! The `--report json` account of a run, for build systems to read rather than
+//! scraping status messages.
+//!
+//! The document is an object with `conversions`, one per input and output
+//! pair in the order they finished, and a `summary` of them:
+//!
+//! ```json
+//! {
+//!   "conversions": [
+//!     {
+//!       "input": "a.toml", "output": "a.json",
+//!       "input_format": "toml", "output_format": "json",
+//!       "bytes_read": 18, "bytes_written": 22, "duration_ms": 0.41,
+//!       "status": "ok", "error": null, "stats": null
+//!     }
+//!   ],
+//!   "summary": { "total": 1, "ok": 1, "skipped": 0, "error": 0, "exit_status": 0 }
+//! }
+//! ```
+
+use std::{
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+use serde::Serialize;
+use serde_json::json;
+
+use super::stats::Stats;
+
+#[derive(Default)]
+pub struct Report {
+    conversions: Mutex<Vec<Conversion>>,
+}
+
+/// What happened converting one input to one output. Fields that don't apply
+/// or aren't known, such as the output of an input that couldn't be read, are
+/// `null`
+#[derive(Serialize)]
+pub struct Conversion {
+    pub input: String,
+    pub output: Option<String>,
+    pub input_format: Option<String>,
+    pub output_format: Option<String>,
+    pub bytes_read: Option<u64>,
+    pub bytes_written: Option<u64>,
+    #[serde(serialize_with = "milliseconds")]
+    pub duration_ms: Duration,
+    pub status: Status,
+    pub error: Option<String>,
+    /// The shape of the input, under --stats
+    pub stats: Option<Stats>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    /// Not converted, such as an output in the input's own format
+    Skipped,
+    Error,
+}
+
+fn milliseconds<S: serde::Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    // Microseconds are as fine as the timing is worth
+    serializer.serialize_f64(duration.as_micros() as f64 / 1000.0)
+}
+
+impl Report {
+    pub fn record(&self, conversion: Conversion) {
+        self.conversions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(conversion);
+    }
+
+    /// The report as pretty-printed JSON, for a run exiting with `exit_status`
+    pub fn to_json(&self, exit_status: u8) -> String {
+        let conversions = self
+            .conversions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let count = |status| {
+            conversions
+                .iter()
+                .filter(|conversion| conversion.status == status)
+                .count()
+        };
+
+        let report = json!({
+            "conversions": *conversions,
+            "summary": {
+                "total": conversions.len(),
+                "ok": count(Status::Ok),
+                "skipped": count(Status::Skipped),
+                "error": count(Status::Error),
+                "exit_status": exit_status,
+            },
+        });
+        // A report of strings and numbers always serializes
+        serde_json::to_string_pretty(&report).unwrap_or_default()
+    }
+}
@@ -0,0 +1,218 @@
+//! Checks for RON values that have no plain JSON equivalent.
+//!
+//! The `ron` deserializer drops struct and enum names when reading into a
+//! generic value, so `Unit` quietly turns into `null` and `Point(1, 2)` into
+//! `[1, 2]`. Named structs with fields still map cleanly onto a map (only the
+//! name is lost), but unit, newtype and tuple variants or structs are rejected
+//! here before they can be flattened.
+
+use super::TranscodeError;
+
+/// Identifiers RON treats as literals rather than names
+const LITERALS: &[&str] = &[
+    "true", "false", "Some", "None", "inf", "inff32", "inff64", "NaN", "NaNf32", "NaNf64",
+];
+
+pub fn check(content: &str) -> Result<(), TranscodeError> {
+    let bytes = content.as_bytes();
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        i = skip_trivia(bytes, i, &mut line);
+        let Some(&byte) = bytes.get(i) else {
+            break;
+        };
+
+        match byte {
+            b'"' => i = skip_string(bytes, i + 1, &mut line),
+            b'\'' => i = skip_char(bytes, i + 1),
+            b'b' if matches!(bytes.get(i + 1), Some(b'"')) => {
+                i = skip_string(bytes, i + 2, &mut line)
+            }
+            b'b' if matches!(bytes.get(i + 1), Some(b'\'')) => i = skip_char(bytes, i + 2),
+            b'r' if raw_string_hashes(bytes, i + 1).is_some() => {
+                i = skip_raw_string(bytes, i + 1, &mut line)
+            }
+            b'0'..=b'9' | b'+' | b'-' | b'.' => i = skip_number(bytes, i),
+            // Attributes such as `#![enable(implicit_some)]`
+            b'#' => {
+                while bytes.get(i).is_some_and(|byte| *byte != b']') {
+                    i += 1;
+                }
+            }
+            byte if is_ident_start(byte) => {
+                let start = i;
+                i = skip_ident(bytes, i);
+                let ident = &content[start..i];
+                let ident = ident.strip_prefix("r#").unwrap_or(ident);
+                let at = line;
+
+                let next = skip_trivia(bytes, i, &mut line);
+                match bytes.get(next) {
+                    // A struct field name
+                    Some(b':') => {}
+                    _ if LITERALS.contains(&ident) => {}
+                    Some(b'(') if has_fields(bytes, next + 1, line) => {}
+                    Some(b'(') => {
+                        return Err(TranscodeError::at(
+                            at,
+                            None,
+                            format_args!(
+                                "`{ident}(..)` is a tuple struct or enum variant, \
+                                 which can't be converted without losing its name"
+                            ),
+                        ))
+                    }
+                    _ => {
+                        return Err(TranscodeError::at(
+                            at,
+                            None,
+                            format_args!(
+                                "`{ident}` is a unit struct or enum variant, \
+                                 which can't be converted without losing it"
+                            ),
+                        ))
+                    }
+                }
+            }
+            _ => i += 1,
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the parenthesised body starting at `i` opens with `name:`
+fn has_fields(bytes: &[u8], i: usize, mut line: usize) -> bool {
+    let i = skip_trivia(bytes, i, &mut line);
+    if !bytes.get(i).copied().is_some_and(is_ident_start) {
+        return false;
+    }
+    let i = skip_ident(bytes, i);
+    let i = skip_trivia(bytes, i, &mut line);
+    bytes.get(i) == Some(&b':')
+}
+
+fn is_ident_start(byte: u8) -> bool {
+    byte.is_ascii_alphabetic() || byte == b'_'
+}
+
+fn skip_ident(bytes: &[u8], mut i: usize) -> usize {
+    if bytes[i..].starts_with(b"r#") {
+        i += 2;
+    }
+    while bytes
+        .get(i)
+        .is_some_and(|byte| byte.is_ascii_alphanumeric() || *byte == b'_')
+    {
+        i += 1;
+    }
+    i
+}
+
+/// Skip a number, including exponents, suffixes and `0x`-style prefixes
+fn skip_number(bytes: &[u8], mut i: usize) -> usize {
+    i += 1;
+    while let Some(&byte) = bytes.get(i) {
+        let exponent_sign = matches!(byte, b'+' | b'-') && matches!(bytes[i - 1], b'e' | b'E');
+        if byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'.' || exponent_sign {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+    i
+}
+
+/// Skip whitespace and comments, counting newlines
+fn skip_trivia(bytes: &[u8], mut i: usize, line: &mut usize) -> usize {
+    loop {
+        match (bytes.get(i), bytes.get(i + 1)) {
+            (Some(b'\n'), _) => {
+                *line += 1;
+                i += 1;
+            }
+            (Some(byte), _) if byte.is_ascii_whitespace() => i += 1,
+            (Some(b'/'), Some(b'/')) => {
+                while bytes.get(i).is_some_and(|byte| *byte != b'\n') {
+                    i += 1;
+                }
+            }
+            // Block comments nest in RON
+            (Some(b'/'), Some(b'*')) => {
+                let mut depth = 0;
+                while i < bytes.len() {
+                    if bytes[i..].starts_with(b"/*") {
+                        depth += 1;
+                        i += 2;
+                    } else if bytes[i..].starts_with(b"*/") {
+                        depth -= 1;
+                        i += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else {
+                        if bytes[i] == b'\n' {
+                            *line += 1;
+                        }
+                        i += 1;
+                    }
+                }
+            }
+            _ => return i,
+        }
+    }
+}
+
+/// Skip the rest of a string whose opening quote ends just before `i`
+fn skip_string(bytes: &[u8], mut i: usize, line: &mut usize) -> usize {
+    while let Some(&byte) = bytes.get(i) {
+        match byte {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            b'\n' => {
+                *line += 1;
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    i
+}
+
+/// Skip the rest of a character literal whose opening quote ends just before `i`
+fn skip_char(bytes: &[u8], mut i: usize) -> usize {
+    while let Some(&byte) = bytes.get(i) {
+        match byte {
+            b'\\' => i += 2,
+            b'\'' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    i
+}
+
+/// The number of `#`s if a raw string's opening delimiter starts at `i`
+fn raw_string_hashes(bytes: &[u8], i: usize) -> Option<usize> {
+    let hashes = bytes[i..].iter().take_while(|byte| **byte == b'#').count();
+    (bytes.get(i + hashes) == Some(&b'"')).then_some(hashes)
+}
+
+fn skip_raw_string(bytes: &[u8], i: usize, line: &mut usize) -> usize {
+    let hashes = raw_string_hashes(bytes, i).unwrap_or(0);
+    let mut closing = vec![b'"'];
+    closing.extend(std::iter::repeat_n(b'#', hashes));
+
+    let mut i = i + hashes + 1;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(&closing) {
+            return i + closing.len();
+        }
+        if bytes[i] == b'\n' {
+            *line += 1;
+        }
+        i += 1;
+    }
+    i
+}
@@ -0,0 +1,56 @@
+//! `--schema`: checking the document against a JSON Schema, draft 2020-12,
+//! before it's written.
+//!
+//! The schema is read like any other input, so one kept as YAML or TOML works
+//! as well as JSON. Every violation is reported, not just the first, each with
+//! where in the document it is and the keyword that failed.
+
+use anyhow::anyhow;
+use jsonschema::Validator;
+use serde_json::Value;
+
+use super::TranscodeError;
+
+/// A compiled schema, with the name of the file it came from for messages
+pub struct Schema {
+    name: String,
+    validator: Validator,
+}
+
+impl Schema {
+    /// Compile `schema`, read from `name`, failing on one that isn't valid
+    /// under draft 2020-12
+    pub fn new(name: String, schema: &Value) -> anyhow::Result<Self> {
+        let validator = jsonschema::draft202012::new(schema)
+            .map_err(|err| anyhow!("{name}: {err} at `{}`", err.schema_path()))?;
+        Ok(Self { name, validator })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Fail listing every way `value` doesn't match the schema
+    pub fn check(&self, value: &Value) -> Result<(), TranscodeError> {
+        let violations = self
+            .validator
+            .iter_errors(value)
+            .map(|err| {
+                let path = err.instance_path().to_string();
+                let at = if path.is_empty() {
+                    "the root".to_string()
+                } else {
+                    format!("`{path}`")
+                };
+                format!("\n  {at}: {err} ({})", err.kind().keyword())
+            })
+            .collect::<String>();
+        if violations.is_empty() {
+            return Ok(());
+        }
+        Err(TranscodeError::Invalid(format!(
+            "doesn't match the schema in {}:{violations}",
+            self.name
+        )))
+    }
+}
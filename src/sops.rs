@@ -0,0 +1,217 @@
+//! Decryption of documents encrypted with [SOPS](https://github.com/getsops/sops).
+//!
+//! SOPS keeps the structure of a JSON/YAML document intact and replaces each
+//! leaf with an `ENC[AES256_GCM,data:...,iv:...,tag:...,type:...]` string. The
+//! AES data key is stored, wrapped for each recipient, in the top level `sops`
+//! metadata block. Only age recipients are supported; the identity is read from
+//! `SOPS_AGE_KEY`, `SOPS_AGE_KEY_FILE` or `$XDG_CONFIG_HOME/sops/age/keys.txt`,
+//! the same places the `sops` binary looks. A document whose MAC doesn't match
+//! its decrypted values, or that has none, is refused.
+
+use std::{env, fs, io::Read, path::PathBuf};
+
+use aes_gcm::{
+    aead::{consts::U32, Aead, KeyInit, Payload},
+    aes::Aes256,
+    AesGcm, Nonce,
+};
+use anyhow::{anyhow, Context};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::Value;
+use sha2::{Digest, Sha512};
+
+/// SOPS uses a 256 bit IV rather than the usual 96 bit GCM nonce
+type Cipher = AesGcm<Aes256, U32>;
+
+/// Whether the document carries a SOPS metadata block
+pub fn is_encrypted(value: &Value) -> bool {
+    value.get("sops").is_some_and(Value::is_object)
+}
+
+/// Strip the SOPS metadata from `value` and decrypt every encrypted leaf
+pub fn decrypt(mut value: Value) -> anyhow::Result<Value> {
+    let metadata = value
+        .as_object_mut()
+        .and_then(|map| map.remove("sops"))
+        .ok_or_else(|| anyhow!("Document has no sops metadata"))?;
+
+    let data_key = data_key(&metadata)?;
+    let cipher =
+        Cipher::new_from_slice(&data_key).map_err(|_| anyhow!("Invalid sops data key length"))?;
+
+    let mac = metadata.get("mac").and_then(Value::as_str).ok_or_else(|| {
+        anyhow!("The sops metadata has no `mac`, so the document can't be verified")
+    })?;
+    let last_modified = metadata
+        .get("lastmodified")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("The sops metadata has no `lastmodified`"))?;
+    let mut hash = Mac {
+        hasher: Sha512::new(),
+        only_encrypted: metadata.get("mac_only_encrypted") == Some(&Value::Bool(true)),
+    };
+
+    let mut path = Vec::new();
+    decrypt_value(&mut value, &cipher, &mut path, &mut hash)?;
+
+    // The MAC is the hash of every value in order, encrypted with the time
+    // the file was last changed as its additional data
+    let expected =
+        decrypt_leaf(mac, &cipher, last_modified).context("Failed to decrypt the sops MAC")?;
+    let computed: String = hash
+        .hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect();
+    if !expected
+        .as_str()
+        .is_some_and(|expected| expected.eq_ignore_ascii_case(&computed))
+    {
+        return Err(anyhow!(
+            "The sops MAC doesn't match the document, which may have been tampered with"
+        ));
+    }
+    Ok(value)
+}
+
+/// The running hash of a document's values that SOPS checks its MAC against
+struct Mac {
+    hasher: Sha512,
+    /// Whether only the encrypted values count, as `mac_only_encrypted` says
+    only_encrypted: bool,
+}
+
+impl Mac {
+    /// Add `value` in the form the `sops` binary hashes it in
+    fn add(&mut self, value: &Value) {
+        let bytes = match value {
+            Value::String(string) => string.clone(),
+            Value::Bool(true) => "True".to_string(),
+            Value::Bool(false) => "False".to_string(),
+            Value::Number(number) => match number.as_i64() {
+                Some(integer) => integer.to_string(),
+                None => number.as_f64().unwrap_or_default().to_string(),
+            },
+            Value::Null | Value::Array(_) | Value::Object(_) => return,
+        };
+        self.hasher.update(bytes.as_bytes());
+    }
+}
+
+/// Unwrap the data key with the first age identity that matches a recipient
+fn data_key(metadata: &Value) -> anyhow::Result<Vec<u8>> {
+    let recipients = metadata
+        .get("age")
+        .and_then(Value::as_array)
+        .filter(|recipients| !recipients.is_empty())
+        .ok_or_else(|| anyhow!("Only age encrypted sops files are supported"))?;
+
+    let identities = identities()?;
+
+    for recipient in recipients {
+        let Some(enc) = recipient.get("enc").and_then(Value::as_str) else {
+            continue;
+        };
+        let armored = age::armor::ArmoredReader::new(enc.as_bytes());
+        let decryptor = age::Decryptor::new(armored)?;
+        let Ok(mut reader) = decryptor.decrypt(identities.iter().map(|i| i.as_ref() as _)) else {
+            continue;
+        };
+        let mut key = Vec::new();
+        reader.read_to_end(&mut key)?;
+        return Ok(key);
+    }
+
+    Err(anyhow!(
+        "None of the available age identities can decrypt this file"
+    ))
+}
+
+fn identities() -> anyhow::Result<Vec<Box<dyn age::Identity + Send + Sync>>> {
+    let keys = if let Ok(keys) = env::var("SOPS_AGE_KEY") {
+        keys
+    } else {
+        let path = match env::var_os("SOPS_AGE_KEY_FILE") {
+            Some(path) => PathBuf::from(path),
+            None => env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+                .ok_or_else(|| anyhow!("No age key found; set SOPS_AGE_KEY or SOPS_AGE_KEY_FILE"))?
+                .join("sops/age/keys.txt"),
+        };
+        fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read age key file {}", path.display()))?
+    };
+
+    Ok(age::IdentityFile::from_buffer(keys.as_bytes())?.into_identities()?)
+}
+
+fn decrypt_value(
+    value: &mut Value,
+    cipher: &Cipher,
+    path: &mut Vec<String>,
+    mac: &mut Mac,
+) -> anyhow::Result<()> {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                path.push(key.clone());
+                decrypt_value(child, cipher, path, mac)?;
+                path.pop();
+            }
+        }
+        // SOPS does not include list indices in the authenticated path
+        Value::Array(items) => {
+            for item in items {
+                decrypt_value(item, cipher, path, mac)?;
+            }
+        }
+        Value::String(s) if s.starts_with("ENC[") => {
+            let aad = path.iter().map(|key| format!("{key}:")).collect::<String>();
+            *value = decrypt_leaf(s, cipher, &aad)
+                .with_context(|| format!("Failed to decrypt `{}`", path.join(".")))?;
+            mac.add(value);
+        }
+        value if !mac.only_encrypted => mac.add(value),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn decrypt_leaf(leaf: &str, cipher: &Cipher, aad: &str) -> anyhow::Result<Value> {
+    let fields = leaf
+        .strip_prefix("ENC[AES256_GCM,")
+        .and_then(|rest| rest.strip_suffix(']'))
+        .ok_or_else(|| anyhow!("Unsupported sops cipher"))?;
+
+    let field = |name: &str| {
+        fields
+            .split(',')
+            .find_map(|field| field.strip_prefix(name)?.strip_prefix(':'))
+            .ok_or_else(|| anyhow!("Encrypted value is missing `{name}`"))
+    };
+
+    let mut data = STANDARD.decode(field("data")?)?;
+    data.extend(STANDARD.decode(field("tag")?)?);
+    let iv = STANDARD.decode(field("iv")?)?;
+    let nonce = Nonce::<U32>::try_from(iv.as_slice()).map_err(|_| anyhow!("Invalid IV length"))?;
+
+    let plaintext = cipher
+        .decrypt(
+            &nonce,
+            Payload {
+                msg: &data,
+                aad: aad.as_bytes(),
+            },
+        )
+        .map_err(|_| anyhow!("Authentication failed"))?;
+    let plaintext = String::from_utf8(plaintext)?;
+
+    Ok(match field("type")? {
+        "int" => Value::from(plaintext.parse::<i64>()?),
+        "float" => Value::from(plaintext.parse::<f64>()?),
+        "bool" => Value::Bool(plaintext.eq_ignore_ascii_case("true")),
+        _ => Value::String(plaintext),
+    })
+}
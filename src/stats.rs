@@ -0,0 +1,92 @@
+//! `--stats`: a summary of the shape of a document, the same whatever format
+//! it was read from.
+//!
+//! A multi-document YAML stream or NDJSON input counts each of its documents
+//! on its own, so their depth is measured from each document's root and the
+//! array holding them isn't counted.
+
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Default, Serialize)]
+pub struct Stats {
+    pub bytes: usize,
+    pub documents: usize,
+    /// Keys across every map
+    pub keys: usize,
+    /// The most maps and arrays any value is nested in, counting its own
+    pub depth: usize,
+    pub strings: usize,
+    pub numbers: usize,
+    pub booleans: usize,
+    pub nulls: usize,
+    pub arrays: usize,
+    pub maps: usize,
+    pub longest_array: usize,
+    /// In characters
+    pub longest_string: usize,
+}
+
+impl Stats {
+    /// The stats of `documents`, read from `bytes` bytes of input
+    pub fn new<'a>(documents: impl IntoIterator<Item = &'a Value>, bytes: usize) -> Self {
+        let mut stats = Self {
+            bytes,
+            ..Self::default()
+        };
+        for document in documents {
+            stats.documents += 1;
+            stats.walk(document, 0);
+        }
+        stats
+    }
+
+    fn walk(&mut self, value: &Value, depth: usize) {
+        match value {
+            Value::Null => self.nulls += 1,
+            Value::Bool(_) => self.booleans += 1,
+            Value::Number(_) => self.numbers += 1,
+            Value::String(string) => {
+                self.strings += 1;
+                self.longest_string = self.longest_string.max(string.chars().count());
+            }
+            Value::Array(items) => {
+                self.arrays += 1;
+                self.depth = self.depth.max(depth + 1);
+                self.longest_array = self.longest_array.max(items.len());
+                for item in items {
+                    self.walk(item, depth + 1);
+                }
+            }
+            Value::Object(entries) => {
+                self.maps += 1;
+                self.depth = self.depth.max(depth + 1);
+                self.keys += entries.len();
+                for value in entries.values() {
+                    self.walk(value, depth + 1);
+                }
+            }
+        }
+    }
+
+    /// The stats as aligned lines of text, each indented by two spaces
+    pub fn to_text(&self) -> String {
+        [
+            ("bytes", self.bytes),
+            ("documents", self.documents),
+            ("keys", self.keys),
+            ("depth", self.depth),
+            ("strings", self.strings),
+            ("numbers", self.numbers),
+            ("booleans", self.booleans),
+            ("nulls", self.nulls),
+            ("arrays", self.arrays),
+            ("maps", self.maps),
+            ("longest array", self.longest_array),
+            ("longest string", self.longest_string),
+        ]
+        .iter()
+        .map(|(name, count)| format!("  {name:<15}{count}\n"))
+        .collect()
+    }
+}
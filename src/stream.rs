@@ -0,0 +1,313 @@
+//! Converting between JSON, YAML and NDJSON files without reading them into
+//! memory first.
+//!
+//! The input is parsed from a buffered reader over the file, and the output
+//! serialized straight into the temporary file that replaces the output path.
+//! NDJSON input is read one line at a time, and JSON with an array at its root
+//! one item at a time, so converting either only ever holds one item. Other
+//! JSON is read whole into a `Value`, and YAML input is read whole before
+//! parsing it. Everything that needs the whole document, such as `--query`,
+//! `--verify` or `--check`, goes through `convert_value` instead, as do TOML
+//! and the other formats.
+
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    fs,
+    io::{self, BufRead, Read, Write},
+    time::Instant,
+};
+
+use serde::{
+    ser::{Error as _, SerializeSeq},
+    Serialize, Serializer,
+};
+use serde_json::Value;
+
+use super::{
+    back_up, binary, check_formats, check_overwrite, detail, encoding, failure, located,
+    make_parent, ndjson, newline, parse, plural, value, write_atomically, write_json, wrote, yaml,
+    DuplicateKeys, Exit, FileFormat, Input, Options, Output, TranscodeError, YamlQuote, YamlStyle,
+    IO,
+};
+
+/// Whether converting `input` to `output` can stream
+pub fn streams(input: &Input, output: &Output, options: &Options) -> bool {
+    let plain = |format| {
+        matches!(
+            format,
+            FileFormat::Json | FileFormat::Yaml | FileFormat::Ndjson
+        )
+    };
+    let whole_document = options.check
+        || options.dry_run
+        || options.verify.is_some()
+        || options.split
+        || options.multi_doc
+        || options.emit_header
+        || options.sort_keys
+        // serde_yaml only writes block style
+        || (output.format == FileFormat::Yaml && options.yaml_style == YamlStyle::Flow)
+        || options.flatten
+        || options.unflatten
+        || options.sops_decrypt
+        || options.input_encoding.is_some()
+        || options.query.is_some()
+        || options.schema.is_some()
+        || options.template.is_some()
+        || !options.patches.is_empty()
+        || !options.edits.is_empty()
+        // Removing the original compares it with what was written
+        || (options.in_place && !options.keep_original);
+
+    !input.is_stdin()
+        && !input.is_url()
+        && plain(input.format)
+        && plain(output.format)
+        && !whole_document
+        && !marked(input)
+        // Read an input that's being replaced whole before writing it
+        && !input.is_written_by(output)
+}
+
+/// Whether `input` starts with a byte order mark or isn't plain UTF-8, and
+/// needs decoding as a whole. One that can't be read is left to fail later
+fn marked(input: &Input) -> bool {
+    input
+        .open()
+        .and_then(|mut reader| Ok(encoding::marked(reader.fill_buf()?)))
+        .unwrap_or(false)
+}
+
+pub fn convert(input: &Input, output: &Output, options: &Options) -> anyhow::Result<()> {
+    check_formats(input, output, options)
+        .map_err(|err| located(TranscodeError::Unsupported(err.to_string()), &input.name()))?;
+    let reader = input.open()?;
+
+    if let Ok(metadata) = fs::metadata(&input.path) {
+        let size = usize::try_from(metadata.len()).unwrap_or(usize::MAX);
+        detail(
+            options,
+            1,
+            format_args!(
+                "Read {} ({}, {})",
+                input.name(),
+                input.format,
+                plural(size, "byte")
+            ),
+        );
+    }
+
+    check_overwrite(input, output, options)?;
+    make_parent(&output.path, options)?;
+    back_up(output, options)?;
+    let started = Instant::now();
+    write_atomically(
+        &output.path,
+        output.compressed(options),
+        options.mode,
+        |writer| {
+            if options.output_bom {
+                writer.write_all(encoding::BOM)?;
+            }
+            let mut writer = newline::Writer::new(writer, options.newline);
+            transcode(reader, &mut writer, input, output, options)?;
+            writer.finish()?;
+            anyhow::Ok(())
+        },
+    )
+    .map_err(|err| match err.downcast::<io::Error>() {
+        // Conversion failures already say what went wrong
+        Ok(err) => failure(
+            Exit::Io,
+            format_args!("Failed to write {}: {err}", output.path.display()),
+        ),
+        Err(err) => err,
+    })?;
+    // Parsing and serializing take turns, so they're timed together
+    detail(
+        options,
+        2,
+        format_args!("Converted {} in {:?}", input.name(), started.elapsed()),
+    );
+    wrote(input, output, options);
+    Ok(())
+}
+
+fn transcode(
+    mut reader: Box<dyn BufRead>,
+    writer: &mut dyn Write,
+    input: &Input,
+    output: &Output,
+    options: &Options,
+) -> anyhow::Result<()> {
+    let name = input.name();
+    let parse_error = |err| located(err, &name);
+    let write_error =
+        |err: anyhow::Error| located(TranscodeError::Serialize(format!("{err:#}")), &name);
+    // Such as a gzipped input that turns out not to be gzip
+    let read_error = |err| failure(Exit::Io, format_args!("Failed to read {name}: {err}"));
+
+    if input.format == FileFormat::Ndjson {
+        let mut records = ndjson::records(reader, options.duplicate_keys);
+        if output.format == FileFormat::Ndjson {
+            return records.try_for_each(|record| {
+                ndjson::write_record(&record.map_err(parse_error)?, &mut *writer)
+                    .map_err(write_error)
+            });
+        }
+
+        let records = Records {
+            records: RefCell::new(records),
+            failure: RefCell::new(None),
+            yaml: (output.format == FileFormat::Yaml).then_some(options.yaml_quote),
+        };
+        let written = write(&records, writer, output.format, options);
+        if let Some(err) = records.failure.take() {
+            return Err(parse_error(err));
+        }
+        return written.map_err(write_error);
+    }
+
+    if input.format == FileFormat::Json && starts_array(&mut reader).map_err(read_error)? {
+        let deserializer = serde_json::Deserializer::from_reader(reader);
+        if output.format == FileFormat::Ndjson {
+            return value::json_items(deserializer, options.duplicate_keys, |item| {
+                ndjson::write_record(&item, &mut *writer)
+            })
+            .map_err(parse_error)?
+            .map_err(write_error);
+        }
+
+        let items = Items {
+            deserializer: RefCell::new(Some(deserializer)),
+            duplicates: options.duplicate_keys,
+            failure: RefCell::new(None),
+            yaml: (output.format == FileFormat::Yaml).then_some(options.yaml_quote),
+        };
+        let written = write(&items, writer, output.format, options);
+        if let Some(err) = items.failure.take() {
+            return Err(parse_error(err));
+        }
+        return written.map_err(write_error);
+    }
+
+    let value = match input.format {
+        FileFormat::Json => value::from_json(
+            serde_json::Deserializer::from_reader(reader),
+            options.duplicate_keys,
+        ),
+        // serde_yaml would read it all before parsing anyway, and checking
+        // aliases or telling quoted scalars from plain ones needs the text
+        _ => {
+            let mut content = Vec::new();
+            reader.read_to_end(&mut content).map_err(read_error)?;
+            encoding::decode(&content, None)
+                .map_err(TranscodeError::syntax)
+                .and_then(|content: Cow<[u8]>| parse(&content, &FileFormat::Yaml, options))
+                .map(binary::to_text)
+        }
+    }
+    .map_err(parse_error)?;
+
+    match output.format {
+        FileFormat::Ndjson => ndjson::write_ndjson(&value, writer),
+        FileFormat::Yaml => write(
+            &yaml::Quoted(&value, options.yaml_quote),
+            writer,
+            output.format,
+            options,
+        ),
+        format => write(&value, writer, format, options),
+    }
+    .map_err(write_error)
+}
+
+/// Whether the JSON `reader` holds starts with an array, looking past the
+/// whitespace before it without taking it, which would throw the line numbers
+/// of errors off. Whitespace filling the whole buffer counts as no array
+fn starts_array(reader: &mut dyn BufRead) -> io::Result<bool> {
+    let buffer = reader.fill_buf()?;
+    Ok(buffer
+        .iter()
+        .find(|byte| !matches!(byte, b' ' | b'\t' | b'\n' | b'\r'))
+        == Some(&b'['))
+}
+
+/// Write `value` as JSON or YAML. Values written as YAML serialize their
+/// strings through `yaml::Quoted`
+fn write(
+    value: &impl Serialize,
+    writer: &mut dyn Write,
+    format: FileFormat,
+    options: &Options,
+) -> anyhow::Result<()> {
+    match format {
+        FileFormat::Json => write_json(value, writer, options)?,
+        _ => yaml::to_writer(writer, value)?,
+    }
+    Ok(())
+}
+
+/// NDJSON records serialized as an array as they're read. A record that fails
+/// to parse stops serialization, and is kept in `failure` to report
+struct Records<I> {
+    records: RefCell<I>,
+    failure: RefCell<Option<TranscodeError>>,
+    /// How to quote the records' strings if they're written as YAML, which
+    /// needs `yaml::Quoted`
+    yaml: Option<YamlQuote>,
+}
+
+/// The items of a JSON array serialized as an array as they're read, from
+/// `deserializer` the one time it's serialized. An item that fails to parse
+/// stops serialization, and is kept in `failure` to report
+struct Items<R> {
+    deserializer: RefCell<Option<serde_json::Deserializer<R>>>,
+    duplicates: DuplicateKeys,
+    failure: RefCell<Option<TranscodeError>>,
+    /// How to quote the items' strings if they're written as YAML
+    yaml: Option<YamlQuote>,
+}
+
+impl<R: io::Read> Serialize for Items<serde_json::de::IoRead<R>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Some(deserializer) = self.deserializer.take() else {
+            return Err(S::Error::custom("the array was already read"));
+        };
+        let mut seq = serializer.serialize_seq(None)?;
+        let read = value::json_items(deserializer, self.duplicates, |item| match self.yaml {
+            Some(quote) => seq.serialize_element(&yaml::Quoted(&item, quote)),
+            None => seq.serialize_element(&item),
+        });
+        match read {
+            Ok(Ok(())) => seq.end(),
+            Ok(Err(err)) => Err(err),
+            Err(err) => {
+                let message = err.to_string();
+                self.failure.replace(Some(err));
+                Err(S::Error::custom(message))
+            }
+        }
+    }
+}
+
+impl<I: Iterator<Item = Result<Value, TranscodeError>>> Serialize for Records<I> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(None)?;
+        for record in &mut *self.records.borrow_mut() {
+            match record {
+                Ok(record) => match self.yaml {
+                    Some(quote) => seq.serialize_element(&yaml::Quoted(&record, quote))?,
+                    None => seq.serialize_element(&record)?,
+                },
+                Err(err) => {
+                    let message = err.to_string();
+                    self.failure.replace(Some(err));
+                    return Err(S::Error::custom(message));
+                }
+            }
+        }
+        seq.end()
+    }
+}
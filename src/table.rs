@@ -0,0 +1,77 @@
+//! Mapping between CSV files and arrays of flat objects.
+//!
+//! The header row supplies the keys of every object read from CSV, and every
+//! value read is a string. Writing takes the union of keys across all objects
+//! as the header, in the order they are first seen, and leaves a cell empty
+//! where an object lacks that key.
+
+use anyhow::anyhow;
+use serde_json::{Map, Value};
+
+use super::TranscodeError;
+
+pub fn from_csv(content: &[u8]) -> Result<Value, TranscodeError> {
+    let mut reader = csv::Reader::from_reader(content);
+    let headers = reader.headers().map_err(TranscodeError::syntax)?.clone();
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let row = headers
+            .iter()
+            .zip(record.map_err(TranscodeError::syntax)?.iter())
+            .map(|(key, cell)| (key.to_string(), Value::String(cell.to_string())))
+            .collect::<Map<_, _>>();
+        rows.push(Value::Object(row));
+    }
+    Ok(Value::Array(rows))
+}
+
+pub fn to_csv(value: &Value) -> anyhow::Result<Vec<u8>> {
+    let Value::Array(items) = value else {
+        return Err(anyhow!(
+            "CSV output needs an array of objects, not {}",
+            kind(value)
+        ));
+    };
+
+    let mut rows = Vec::with_capacity(items.len());
+    let mut headers: Vec<&str> = Vec::new();
+    for (index, item) in items.iter().enumerate() {
+        let row = index + 1;
+        let Value::Object(entries) = item else {
+            return Err(anyhow!("row {row} is {}, not an object", kind(item)));
+        };
+        for (key, value) in entries {
+            if value.is_array() || value.is_object() {
+                return Err(anyhow!("nested value at row {row}, key '{key}'"));
+            }
+            if !headers.contains(&key.as_str()) {
+                headers.push(key);
+            }
+        }
+        rows.push(entries);
+    }
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(&headers)?;
+    for entries in rows {
+        writer.write_record(headers.iter().map(|key| match entries.get(*key) {
+            None | Some(Value::Null) => String::new(),
+            Some(Value::String(string)) => string.clone(),
+            Some(value) => value.to_string(),
+        }))?;
+    }
+    Ok(writer.into_inner()?)
+}
+
+/// How `value` is described in error messages
+pub fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
@@ -0,0 +1,71 @@
+//! `--template`: writing the document through a Handlebars template instead
+//! of serializing it, for text shapes no format covers, such as a config
+//! snippet or a markdown table.
+//!
+//! The document is the template's root context, so `{{name}}` is its `name`
+//! key and `{{#each this}}` walks a document that's an array. Output is plain
+//! text, so nothing is HTML-escaped. Templates render in strict mode, where a
+//! missing key is an error naming the line it's on rather than an empty string.
+
+use std::{fs, path::Path};
+
+use anyhow::anyhow;
+use handlebars::{Handlebars, RenderErrorReason, TemplateErrorReason};
+use serde_json::Value;
+
+use super::{failure, Exit};
+
+pub struct Template {
+    name: String,
+    registry: Handlebars<'static>,
+}
+
+impl Template {
+    /// Read and compile the template at `path`
+    pub fn new(path: &Path) -> anyhow::Result<Self> {
+        let name = path.display().to_string();
+        let source = fs::read_to_string(path)
+            .map_err(|err| failure(Exit::Io, format_args!("Failed to read {name}: {err}")))?;
+        let mut registry = Handlebars::new();
+        registry.set_strict_mode(true);
+        registry.register_escape_fn(handlebars::no_escape);
+        registry
+            .register_template_string(&name, source)
+            .map_err(|err| {
+                // The syntax error lists every rule of the grammar it expected
+                let reason = match err.reason() {
+                    TemplateErrorReason::InvalidSyntax(_) => {
+                        "invalid Handlebars syntax".to_string()
+                    }
+                    reason => reason.to_string(),
+                };
+                let at = located(&name, err.pos());
+                failure(
+                    Exit::Usage,
+                    format_args!("Invalid template: {at}: {reason}"),
+                )
+            })?;
+        Ok(Self { name, registry })
+    }
+
+    pub fn render(&self, value: &Value) -> anyhow::Result<String> {
+        self.registry.render(&self.name, value).map_err(|err| {
+            let reason = match err.reason() {
+                RenderErrorReason::MissingVariable(Some(name)) => {
+                    format!("`{name}` isn't in the document")
+                }
+                reason => reason.to_string(),
+            };
+            let at = located(&self.name, err.line_no.zip(err.column_no));
+            anyhow!("{at}: {reason}")
+        })
+    }
+}
+
+/// `name`, followed by the line and column in it if there are any
+fn located(name: &str, position: Option<(usize, usize)>) -> String {
+    match position {
+        Some((line, column)) => format!("{name}:{line}:{column}"),
+        None => name.to_string(),
+    }
+}
@@ -0,0 +1,279 @@
+//! TOML documents, whose datetimes are RFC 3339 strings or, with `--datetime
+//! epoch`, Unix timestamps.
+
+use anyhow::anyhow;
+use serde_json::{Map, Number, Value};
+use toml::value::{Date, Datetime, Offset, Time};
+
+use super::{
+    compare,
+    number::{self, Unfit},
+    DatetimeMode, TranscodeError,
+};
+
+pub fn from_toml(content: &str, mode: DatetimeMode) -> Result<Value, TranscodeError> {
+    let value = content
+        .parse()
+        .map_err(|err| duplicate(content, &err).unwrap_or_else(|| err.into()))?;
+    Ok(to_json(value, mode))
+}
+
+/// The toml crate's error for a key or table defined twice, reworded to say
+/// `duplicate key` and give the JSON Pointer to it, as for JSON and YAML, and
+/// placed at the second definition rather than the table holding it
+fn duplicate(content: &str, err: &toml::de::Error) -> Option<TranscodeError> {
+    let message = err.to_string();
+    let message = message
+        .rsplit_once(" at line ")
+        .map_or(&*message, |(m, _)| m);
+    let quoted = |text: &str| text.strip_prefix('`')?.strip_suffix('`').map(String::from);
+
+    let mut segments = Vec::new();
+    let mut position = err.line_col();
+    if let Some(rest) = message.strip_prefix("duplicate key: ") {
+        let (key, table) = match rest.split_once(" for key ") {
+            Some((key, table)) => (quoted(key)?, Some(quoted(table)?)),
+            None => (quoted(rest)?, None),
+        };
+        segments.extend(
+            table
+                .iter()
+                .flat_map(|table| table.split('.').map(String::from)),
+        );
+        segments.push(key);
+        position = second_definition(content, &segments).or(position);
+    } else if let Some(rest) = message.strip_prefix("redefinition of table ") {
+        let (table, _) = rest.split_once(" for key ")?;
+        segments.extend(quoted(table)?.split('.').map(String::from));
+    } else {
+        return None;
+    }
+
+    let mut pointer = String::new();
+    for segment in &segments {
+        compare::push_segment(&mut pointer, segment);
+    }
+    let message = format!("duplicate key {pointer}");
+    Some(match position {
+        Some((line, column)) => TranscodeError::at(line + 1, Some(column + 1), message),
+        None => TranscodeError::syntax(message),
+    })
+}
+
+/// The 0-based line and column of the second `key = value` line defining
+/// `path` in the same table. Lines are read much as the toml crate reads them,
+/// skipping comments and multi-line strings, closely enough to place an error
+fn second_definition(content: &str, path: &[String]) -> Option<(usize, usize)> {
+    let mut table = Vec::new();
+    let mut seen = false;
+    let mut lines = content.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let trimmed = line.trim_start();
+        let column = line.len() - trimmed.len();
+        if let Some(header) = trimmed.strip_prefix('[') {
+            let array = header.starts_with('[');
+            let (keys, _) = dotted_key(header.trim_start_matches('['))?;
+            // Each table of an array of tables may define the key again
+            if array && path.starts_with(&keys) {
+                seen = false;
+            }
+            table = keys;
+            continue;
+        }
+        let Some((keys, rest)) = dotted_key(trimmed) else {
+            continue;
+        };
+        let Some(value) = rest.strip_prefix('=') else {
+            continue;
+        };
+        if table.len() + keys.len() == path.len()
+            && path.starts_with(&table)
+            && path.ends_with(&keys)
+        {
+            if seen {
+                return Some((index, column));
+            }
+            seen = true;
+        }
+        // Skip past the lines of a multi-line string
+        let value = value.trim_start();
+        for quotes in ["\"\"\"", "'''"] {
+            if value.starts_with(quotes) && !value[3..].contains(quotes) {
+                lines.find(|(_, line)| line.contains(quotes));
+            }
+        }
+    }
+    None
+}
+
+/// The keys of the dotted key at the start of `text`, bare or quoted, and the
+/// text after it
+fn dotted_key(text: &str) -> Option<(Vec<String>, &str)> {
+    let mut keys = Vec::new();
+    let mut rest = text;
+    loop {
+        rest = rest.trim_start();
+        let (key, after) = match rest.chars().next()? {
+            // Escapes in quoted keys are too rare to be worth decoding here
+            quote @ ('"' | '\'') => {
+                let end = rest[1..].find(quote)? + 1;
+                (rest[1..end].to_string(), &rest[end + 1..])
+            }
+            _ => {
+                let end = rest
+                    .find(|char: char| !(char.is_ascii_alphanumeric() || matches!(char, '_' | '-')))
+                    .unwrap_or(rest.len());
+                if end == 0 {
+                    return None;
+                }
+                (rest[..end].to_string(), &rest[end..])
+            }
+        };
+        keys.push(key);
+        rest = after.trim_start();
+        match rest.strip_prefix('.') {
+            Some(after) => rest = after,
+            None => return Some((keys, rest)),
+        }
+    }
+}
+
+fn to_json(value: toml::Value, mode: DatetimeMode) -> Value {
+    match value {
+        toml::Value::String(string) => Value::String(string),
+        toml::Value::Integer(integer) => Value::from(integer),
+        // NaN and the infinities become null, as they do when parsing JSON
+        toml::Value::Float(float) => Number::from_f64(float).map_or(Value::Null, Value::Number),
+        toml::Value::Boolean(bool) => Value::Bool(bool),
+        toml::Value::Datetime(datetime) => match (mode, unix_time(&datetime)) {
+            (DatetimeMode::Epoch, Some(seconds)) => Value::from(seconds),
+            _ => Value::String(datetime.to_string()),
+        },
+        toml::Value::Array(items) => {
+            Value::Array(items.into_iter().map(|item| to_json(item, mode)).collect())
+        }
+        toml::Value::Table(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key, to_json(value, mode)))
+                .collect::<Map<_, _>>(),
+        ),
+    }
+}
+
+/// Seconds since the Unix epoch, dropping any fraction, for a datetime with
+/// an offset. Local dates and times name no instant, so have none
+fn unix_time(datetime: &Datetime) -> Option<i64> {
+    let (Some(Date { year, month, day }), Some(time), Some(offset)) =
+        (&datetime.date, &datetime.time, &datetime.offset)
+    else {
+        return None;
+    };
+    let Time {
+        hour,
+        minute,
+        second,
+        ..
+    } = time;
+
+    // Howard Hinnant's days_from_civil
+    let (year, month, day) = (i64::from(*year), i64::from(*month), i64::from(*day));
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let offset = match offset {
+        Offset::Z => 0,
+        Offset::Custom { hours, minutes } => {
+            let minutes = i64::from(*hours) * 60 + i64::from(*minutes) * i64::from(hours.signum());
+            minutes * 60
+        }
+    };
+    Some(
+        days * 86_400 + i64::from(*hour) * 3600 + i64::from(*minute) * 60 + i64::from(*second)
+            - offset,
+    )
+}
+
+/// `value` as TOML. With `--datetime detect`, strings in one of TOML's
+/// datetime forms become datetimes
+pub fn to_toml(value: &Value, mode: DatetimeMode) -> anyhow::Result<String> {
+    if !value.is_object() {
+        return Err(anyhow!(
+            "TOML needs a table at the root, not {}; pass --toml-wrap KEY to put it under KEY",
+            super::table::kind(value)
+        ));
+    }
+    let value = from_json(value, mode, &mut String::new())?;
+    // The serializer writes tables after plain keys, as TOML requires
+    Ok(toml::to_string(&value)?)
+}
+
+fn from_json(value: &Value, mode: DatetimeMode, path: &mut String) -> anyhow::Result<toml::Value> {
+    Ok(match value {
+        Value::Null => {
+            let path = if path.is_empty() { "the root" } else { path };
+            return Err(anyhow!("null at `{path}`, which TOML can't represent"));
+        }
+        Value::Bool(bool) => toml::Value::Boolean(*bool),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) => toml::Value::Integer(integer),
+            None => match number::unfit(number).or(number.is_u64().then_some(Unfit::Integer)) {
+                Some(unfit) => {
+                    return Err(anyhow!(
+                        "`{path}` is {number}, which {}",
+                        unfit.explain("TOML")
+                    ))
+                }
+                None => toml::Value::Float(number.as_f64().unwrap_or_default()),
+            },
+        },
+        Value::String(string) => match string.parse::<Datetime>() {
+            Ok(datetime) if mode == DatetimeMode::Detect => toml::Value::Datetime(datetime),
+            _ => toml::Value::String(string.clone()),
+        },
+        Value::Array(items) => {
+            let mut converted = Vec::with_capacity(items.len());
+            for (index, item) in items.iter().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("[{index}]"));
+                converted.push(from_json(item, mode, path)?);
+                path.truncate(len);
+            }
+            // The serializer writes an array holding tables as an array of
+            // tables, which can't have anything else in it or be in an array
+            let tables =
+                |items: &[toml::Value]| items.iter().filter(|item| item.is_table()).count();
+            if (1..converted.len()).contains(&tables(&converted)) {
+                return Err(anyhow!(
+                    "`{path}` mixes tables with other values, which TOML can't represent"
+                ));
+            }
+            if converted
+                .iter()
+                .any(|item| item.as_array().is_some_and(|items| tables(items) > 0))
+            {
+                return Err(anyhow!(
+                    "`{path}` has tables in an array inside it, which TOML can't represent"
+                ));
+            }
+            toml::Value::Array(converted)
+        }
+        Value::Object(entries) => {
+            let mut converted = toml::map::Map::new();
+            for (key, value) in entries {
+                let len = path.len();
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+                converted.insert(key.clone(), from_json(value, mode, path)?);
+                path.truncate(len);
+            }
+            toml::Value::Table(converted)
+        }
+    })
+}
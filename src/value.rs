@@ -0,0 +1,392 @@
+//! Reading JSON and YAML into a `Value`. `Value`'s own `Deserialize` quietly
+//! keeps the last of two equal keys in a map; `Reader` does as
+//! `--duplicate-keys` says instead, and under `--yaml-compat 1.1` resolves plain
+//! YAML scalars as YAML 1.1 does.
+
+use std::{cell::RefCell, fmt};
+
+use serde::{
+    de::{self, DeserializeSeed, IgnoredAny, IntoDeserializer, MapAccess, SeqAccess, Visitor},
+    Deserialize,
+};
+use serde_json::{Map, Value};
+
+use super::{compare, number, yaml, DuplicateKeys, TranscodeError};
+
+#[derive(Clone, Copy)]
+pub struct Reader<'a> {
+    pub duplicates: DuplicateKeys,
+    /// The whole input, for YAML. serde_yaml borrows plain and quoted scalars
+    /// alike straight from it, which tells where a key is, and whether a
+    /// scalar is quoted: only a quoted one follows a quote
+    pub yaml: Option<&'a [u8]>,
+    /// Whether plain YAML scalars resolve as YAML 1.1 does
+    pub yaml_1_1: bool,
+    /// The plain YAML scalars serde_yaml reads as floats that can't hold them
+    pub numbers: Option<&'a yaml::Numbers>,
+}
+
+/// Why reading failed, with the duplicate key if `DuplicateKeys::Error`
+/// refused one
+pub struct Failure<E> {
+    pub error: E,
+    pub duplicate: Option<Duplicate>,
+}
+
+#[derive(Default)]
+pub struct Duplicate {
+    /// The JSON Pointer to the key's second occurrence
+    pub pointer: String,
+    /// Its 1-based line and column, where the deserializer's error doesn't say
+    pub at: Option<(usize, usize)>,
+    /// The keys and indexes leading to it, innermost first
+    segments: Vec<String>,
+}
+
+impl Reader<'_> {
+    pub fn read<'de, D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Value, Failure<D::Error>> {
+        let duplicate = RefCell::new(Duplicate::default());
+        let seed = Seed {
+            reader: self,
+            duplicate: &duplicate,
+        };
+        seed.deserialize(deserializer)
+            .map_err(|error| failed(error, &duplicate))
+    }
+}
+
+/// `error`, with the duplicate key `duplicate` was left holding if reading
+/// failed on one
+fn failed<E>(error: E, duplicate: &RefCell<Duplicate>) -> Failure<E> {
+    let mut duplicate = duplicate.take();
+    if duplicate.segments.is_empty() {
+        return Failure {
+            error,
+            duplicate: None,
+        };
+    }
+    for segment in duplicate.segments.iter().rev() {
+        compare::push_segment(&mut duplicate.pointer, segment);
+    }
+    Failure {
+        error,
+        duplicate: Some(duplicate),
+    }
+}
+
+/// Read a JSON document, all of what `deserializer` holds
+pub fn from_json<'de, R: serde_json::de::Read<'de>>(
+    mut deserializer: serde_json::Deserializer<R>,
+    duplicates: DuplicateKeys,
+) -> Result<Value, TranscodeError> {
+    let reader = Reader {
+        duplicates,
+        yaml: None,
+        yaml_1_1: false,
+        numbers: None,
+    };
+    let value = reader.read(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Read a JSON document with an array at its root, handing each item to `item`
+/// as it's read rather than keeping them all. An error from `item` stops
+/// reading, and is returned as it is
+pub fn json_items<'de, R: serde_json::de::Read<'de>, E>(
+    mut deserializer: serde_json::Deserializer<R>,
+    duplicates: DuplicateKeys,
+    item: impl FnMut(Value) -> Result<(), E>,
+) -> Result<Result<(), E>, TranscodeError> {
+    let reader = Reader {
+        duplicates,
+        yaml: None,
+        yaml_1_1: false,
+        numbers: None,
+    };
+    let duplicate = RefCell::new(Duplicate::default());
+    let stopped = RefCell::new(None);
+    let items = Items {
+        seed: Seed {
+            reader,
+            duplicate: &duplicate,
+        },
+        item,
+        stopped: &stopped,
+    };
+    let read = de::Deserializer::deserialize_seq(&mut deserializer, items);
+    if let Some(err) = stopped.take() {
+        return Ok(Err(err));
+    }
+    read.map_err(|error| failed(error, &duplicate))?;
+    deserializer.end()?;
+    Ok(Ok(()))
+}
+
+impl From<Failure<serde_json::Error>> for TranscodeError {
+    fn from(failure: Failure<serde_json::Error>) -> Self {
+        match failure.duplicate {
+            Some(duplicate) => Self::at(
+                failure.error.line(),
+                Some(failure.error.column()),
+                format_args!("duplicate key {}", duplicate.pointer),
+            ),
+            None => failure.error.into(),
+        }
+    }
+}
+
+impl From<Failure<serde_yaml::Error>> for TranscodeError {
+    fn from(failure: Failure<serde_yaml::Error>) -> Self {
+        let Some(duplicate) = failure.duplicate else {
+            return failure.error.into();
+        };
+        // serde_yaml places errors at the start of the map
+        let at = duplicate.at.or_else(|| {
+            let location = failure.error.location()?;
+            Some((location.line(), location.column()))
+        });
+        let message = format!("duplicate key {}", duplicate.pointer);
+        match at {
+            Some((line, column)) => Self::at(line, Some(column), message),
+            None => Self::syntax(message),
+        }
+    }
+}
+
+/// A `Reader` partway through a document. A duplicate key refused deep in it
+/// stops reading, and each map and array it's in adds its key or index to
+/// `duplicate` on the way out, starting from the duplicate itself
+#[derive(Clone, Copy)]
+struct Seed<'a> {
+    reader: Reader<'a>,
+    duplicate: &'a RefCell<Duplicate>,
+}
+
+impl Seed<'_> {
+    /// Pass on `err` from reading the value at `segment`
+    fn within<E>(self, segment: impl ToString, err: E) -> E {
+        let mut duplicate = self.duplicate.borrow_mut();
+        if !duplicate.segments.is_empty() {
+            duplicate.segments.push(segment.to_string());
+        }
+        err
+    }
+
+    /// Where `text` starts in the YAML input, if it was borrowed from it
+    fn offset(self, text: &str) -> Option<usize> {
+        let content = self.reader.yaml?;
+        let start = (text.as_ptr() as usize).wrapping_sub(content.as_ptr() as usize);
+        (start <= content.len()).then_some(start)
+    }
+
+    /// Where the scalar holding `text` starts in the YAML input, counting its
+    /// opening quote
+    fn scalar_offset(self, text: &str) -> Option<usize> {
+        let start = self.offset(text)?;
+        let content = self.reader.yaml?;
+        match start.checked_sub(1).map(|before| content[before]) {
+            Some(b'\'' | b'"') => Some(start - 1),
+            _ => Some(start),
+        }
+    }
+
+    /// The line and column of `offset` in the YAML input
+    fn position(self, offset: usize) -> Option<(usize, usize)> {
+        let before = self.reader.yaml?.get(..offset)?;
+        let line_start = before
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+            .map_or(0, |i| i + 1);
+        let column = String::from_utf8_lossy(&before[line_start..])
+            .chars()
+            .count()
+            + 1;
+        let line = before.iter().filter(|byte| **byte == b'\n').count() + 1;
+        Some((line, column))
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for Seed<'_> {
+    type Value = Value;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Seed<'_> {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any valid JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    // Numbers go through `Value`'s own `Deserialize`, for its handling of
+    // those that don't fit
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+        Value::deserialize(v.into_deserializer())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+        Value::deserialize(v.into_deserializer())
+    }
+
+    fn visit_i128<E: de::Error>(self, v: i128) -> Result<Value, E> {
+        Value::deserialize(v.into_deserializer())
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<Value, E> {
+        Value::deserialize(v.into_deserializer())
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Value, E> {
+        match self.reader.numbers.and_then(|numbers| numbers.get(v)) {
+            Some(number) => Ok(Value::Number(number.clone())),
+            None => Value::deserialize(v.into_deserializer()),
+        }
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Value, E> {
+        let (Some(content), Some(start), true) =
+            (self.reader.yaml, self.offset(v), self.reader.yaml_1_1)
+        else {
+            return Ok(v.into());
+        };
+        let quoted = start
+            .checked_sub(1)
+            .is_some_and(|before| matches!(content[before], b'\'' | b'"'));
+        if quoted {
+            return Ok(v.into());
+        }
+        Ok(yaml::resolve(v).unwrap_or_else(|| v.into()))
+    }
+
+    // Strings serde_yaml can't borrow are quoted with escapes, block scalars
+    // or plain scalars folded over several lines, none of which resolve
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq
+            .next_element_seed(self)
+            .map_err(|err| self.within(items.len(), err))?
+        {
+            items.push(item);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut entries = Map::new();
+        while let Some((key, text)) = map.next_key_seed(Key)? {
+            // serde_json hands over a number as a map holding its text
+            if key == number::TOKEN && entries.is_empty() {
+                let text: String = map.next_value()?;
+                return text.parse().map(Value::Number).map_err(de::Error::custom);
+            }
+            if entries.contains_key(&key) {
+                match self.reader.duplicates {
+                    DuplicateKeys::Error => {
+                        let err = de::Error::custom(format_args!("duplicate key `{key}`"));
+                        let mut duplicate = self.duplicate.borrow_mut();
+                        duplicate.at = text
+                            .and_then(|text| self.scalar_offset(text))
+                            .and_then(|offset| self.position(offset));
+                        duplicate.segments.push(key);
+                        return Err(err);
+                    }
+                    DuplicateKeys::First => {
+                        map.next_value::<IgnoredAny>()?;
+                        continue;
+                    }
+                    DuplicateKeys::Last => {}
+                }
+            }
+            let value = map
+                .next_value_seed(self)
+                .map_err(|err| self.within(&key, err))?;
+            entries.insert(key, value);
+        }
+        Ok(Value::Object(entries))
+    }
+}
+
+/// The items of an array, each read by `seed` and handed to `item`. The error
+/// of an `item` that fails is kept in `stopped`
+struct Items<'a, F, E> {
+    seed: Seed<'a>,
+    item: F,
+    stopped: &'a RefCell<Option<E>>,
+}
+
+impl<'de, F: FnMut(Value) -> Result<(), E>, E> Visitor<'de> for Items<'_, F, E> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<(), A::Error> {
+        let mut index = 0;
+        while let Some(item) = seq
+            .next_element_seed(self.seed)
+            .map_err(|err| self.seed.within(index, err))?
+        {
+            if let Err(err) = (self.item)(item) {
+                self.stopped.replace(Some(err));
+                return Err(de::Error::custom("stopped"));
+            }
+            index += 1;
+        }
+        Ok(())
+    }
+}
+
+/// A map key, read as a string as `String`'s `Deserialize` reads it, with the
+/// text it was borrowed from if it was
+struct Key;
+
+impl<'de> DeserializeSeed<'de> for Key {
+    type Value = (String, Option<&'de str>);
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Key {
+    type Value = (String, Option<&'de str>);
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string")
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
+        Ok((v.to_string(), Some(v)))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok((v.to_string(), None))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok((v, None))
+    }
+}
@@ -0,0 +1,104 @@
+//! Re-running conversions whenever their inputs change.
+//!
+//! The directories holding the inputs are watched rather than the files, so
+//! editors that save by renaming a new file into place keep being picked up.
+
+use std::{
+    path::Path,
+    sync::mpsc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::anyhow;
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use super::{convert, status, Input, Options, Output, IO};
+
+/// How long to wait for the rest of a burst of events before rebuilding
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Convert every job, then convert each again whenever its input changes.
+/// Runs until interrupted, reporting failed conversions and carrying on
+pub fn watch(jobs: &[(Input, Vec<Output>)], options: &Options) -> anyhow::Result<()> {
+    ctrlc::set_handler(|| std::process::exit(0))?;
+
+    let (sender, events) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+
+    let mut paths = Vec::new();
+    let mut dirs = Vec::new();
+    for (input, _) in jobs {
+        if input.is_stdin() {
+            return Err(anyhow!("--watch needs input files, not standard input"));
+        }
+        if input.is_url() {
+            return Err(anyhow!("--watch needs input files, not URLs"));
+        }
+        let path = input.path.canonicalize()?;
+        let dir = path
+            .parent()
+            .ok_or_else(|| anyhow!("Can't watch {}", input.name()))?
+            .to_path_buf();
+        if !dirs.contains(&dir) {
+            watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+            dirs.push(dir);
+        }
+        paths.push(path);
+    }
+
+    // Outputs are rewritten on every change, so replacing them is expected
+    let options = Options {
+        force: true,
+        ..options.clone()
+    };
+
+    for (input, outputs) in jobs {
+        if let Err(err) = convert(input, outputs, &options) {
+            eprintln!("{err:#}");
+        }
+    }
+
+    loop {
+        let mut changed = Vec::new();
+        // Only changes hold off the rebuild, as something reading the files
+        // all the time would otherwise keep it waiting forever
+        let mut quiet_at = None::<Instant>;
+        loop {
+            let event = match quiet_at {
+                None => events.recv()?,
+                Some(at) => {
+                    let wait = at.saturating_duration_since(Instant::now());
+                    match events.recv_timeout(wait) {
+                        Ok(event) => event,
+                        Err(_) => break,
+                    }
+                }
+            };
+            if let Ok(event) = event {
+                if !matches!(event.kind, EventKind::Access(_)) {
+                    changed.extend(event.paths);
+                    quiet_at = Some(Instant::now() + DEBOUNCE);
+                }
+            }
+        }
+
+        for ((input, outputs), path) in jobs.iter().zip(&paths) {
+            if changed.iter().any(|changed| same_file(changed, path)) && path.exists() {
+                rebuild(input, outputs, &options);
+            }
+        }
+    }
+}
+
+fn rebuild(input: &Input, outputs: &[Output], options: &Options) {
+    let now = humantime::format_rfc3339_seconds(SystemTime::now());
+    status(options, format_args!("[{now}] {} changed", input.name()));
+    if let Err(err) = convert(input, outputs, options) {
+        eprintln!("[{now}] {err:#}");
+    }
+}
+
+/// Whether an event path names `path`, which is canonical
+fn same_file(event: &Path, path: &Path) -> bool {
+    event == path || event.canonicalize().is_ok_and(|event| event == path)
+}
@@ -0,0 +1,300 @@
+//! XML documents, as a map holding the root element. Attributes are keys
+//! prefixed with `@`, and text next to them is under `#text`.
+
+use anyhow::anyhow;
+use quick_xml::{
+    escape::{escape, resolve_predefined_entity},
+    events::Event,
+    Reader, XmlVersion,
+};
+use serde_json::{Map, Value};
+
+use super::TranscodeError;
+
+const TEXT_KEY: &str = "#text";
+const ATTRIBUTE_PREFIX: char = '@';
+
+/// An element whose end tag hasn't been read yet
+struct Element {
+    name: String,
+    entries: Map<String, Value>,
+    text: String,
+    has_children: bool,
+}
+
+impl Element {
+    fn new(name: String, entries: Map<String, Value>) -> Self {
+        Element {
+            name,
+            entries,
+            text: String::new(),
+            has_children: false,
+        }
+    }
+
+    fn add_child(&mut self, name: String, value: Value) {
+        self.has_children = true;
+        match self.entries.get_mut(&name) {
+            // Element values are never arrays, so an array here holds siblings
+            Some(Value::Array(siblings)) => siblings.push(value),
+            Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+            None => {
+                self.entries.insert(name, value);
+            }
+        }
+    }
+
+    fn finish(mut self, line: usize) -> Result<(String, Value), TranscodeError> {
+        let text = self.text.trim();
+        if self.has_children && !text.is_empty() {
+            return Err(TranscodeError::at(
+                line,
+                None,
+                format_args!(
+                    "<{}> mixes text with child elements, which can't be converted",
+                    self.name
+                ),
+            ));
+        }
+
+        let value = match (self.entries.is_empty(), text.is_empty()) {
+            (true, true) => Value::Null,
+            (true, false) => Value::String(text.to_string()),
+            (false, true) => Value::Object(self.entries),
+            (false, false) => {
+                self.entries
+                    .insert(TEXT_KEY.to_string(), Value::String(text.to_string()));
+                Value::Object(self.entries)
+            }
+        };
+        Ok((self.name, value))
+    }
+}
+
+/// Child elements become keys named after them, repeated siblings an array,
+/// an element with only text a string and an empty one `null`. Text mixed in
+/// with child elements is an error rather than something to drop
+pub fn from_xml(content: &str) -> Result<Value, TranscodeError> {
+    let mut reader = Reader::from_str(content);
+    let line = |position: u64| content[..position as usize].matches('\n').count() + 1;
+
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root = None;
+
+    loop {
+        let position = reader.buffer_position();
+        let event = reader
+            .read_event()
+            .map_err(|err| TranscodeError::at(line(reader.error_position()), None, err))?;
+
+        let finished = match &event {
+            Event::Start(start) | Event::Empty(start) => {
+                let name = start.name().as_ref().to_string();
+                let mut entries = Map::new();
+                let invalid = |err| TranscodeError::at(line(position), None, err);
+                for attribute in start.attributes() {
+                    let attribute = attribute.map_err(|err| invalid(err.to_string()))?;
+                    let key = attribute.key.as_ref();
+                    entries.insert(
+                        format!("{ATTRIBUTE_PREFIX}{key}"),
+                        Value::String(
+                            attribute
+                                .normalized_value(XmlVersion::Implicit1_0)
+                                .map_err(|err| invalid(err.to_string()))?
+                                .into_owned(),
+                        ),
+                    );
+                }
+
+                let element = Element::new(name, entries);
+                if matches!(event, Event::Start(_)) {
+                    stack.push(element);
+                    continue;
+                }
+                Some(element.finish(line(position))?)
+            }
+            Event::End(_) => match stack.pop() {
+                Some(element) => Some(element.finish(line(position))?),
+                None => None,
+            },
+            Event::Text(text) => {
+                push_text(&mut stack, &text.xml10_content(), line(position))?;
+                None
+            }
+            Event::CData(data) => {
+                push_text(&mut stack, &data.xml10_content(), line(position))?;
+                None
+            }
+            Event::GeneralRef(reference) => {
+                let text = match reference
+                    .resolve_char_ref()
+                    .map_err(|err| TranscodeError::at(line(position), None, err))?
+                {
+                    Some(char) => char.to_string(),
+                    None => {
+                        let name = reference.xml10_content();
+                        resolve_predefined_entity(&name)
+                            .ok_or_else(|| {
+                                TranscodeError::at(
+                                    line(position),
+                                    None,
+                                    format_args!("unknown entity &{name};"),
+                                )
+                            })?
+                            .to_string()
+                    }
+                };
+                push_text(&mut stack, &text, line(position))?;
+                None
+            }
+            Event::Eof => break,
+            Event::Comment(_) | Event::Decl(_) | Event::PI(_) | Event::DocType(_) => None,
+        };
+
+        if let Some((name, value)) = finished {
+            match stack.last_mut() {
+                Some(parent) => parent.add_child(name, value),
+                None if root.is_none() => root = Some((name, value)),
+                None => {
+                    return Err(TranscodeError::at(
+                        line(position),
+                        None,
+                        "document has more than one root element",
+                    ))
+                }
+            }
+        }
+    }
+
+    let (name, value) =
+        root.ok_or_else(|| TranscodeError::syntax("document has no root element"))?;
+    Ok(Value::Object(Map::from_iter([(name, value)])))
+}
+
+fn push_text(stack: &mut [Element], text: &str, line: usize) -> Result<(), TranscodeError> {
+    match stack.last_mut() {
+        Some(element) => element.text.push_str(text),
+        None if text.trim().is_empty() => {}
+        None => {
+            return Err(TranscodeError::at(
+                line,
+                None,
+                "text outside the root element",
+            ))
+        }
+    }
+    Ok(())
+}
+
+pub fn to_xml(value: &Value) -> anyhow::Result<String> {
+    let root = match value.as_object() {
+        Some(map) if map.len() == 1 => map.iter().next(),
+        _ => None,
+    };
+    let Some((name, value)) = root.filter(|(_, value)| !value.is_array()) else {
+        return Err(anyhow!(
+            "XML output needs a map with a single key to use as the root element"
+        ));
+    };
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    write_element(&mut xml, name, value, 0)?;
+    Ok(xml)
+}
+
+fn write_element(xml: &mut String, name: &str, value: &Value, depth: usize) -> anyhow::Result<()> {
+    check_name(name)?;
+    let indent = "  ".repeat(depth);
+
+    let entries = match value {
+        Value::Array(items) => {
+            for item in items {
+                if item.is_array() {
+                    return Err(anyhow!(
+                        "<{name}> holds an array in an array, which XML can't express"
+                    ));
+                }
+                write_element(xml, name, item, depth)?;
+            }
+            return Ok(());
+        }
+        Value::Object(entries) => entries,
+        Value::Null => {
+            xml.push_str(&format!("{indent}<{name}/>\n"));
+            return Ok(());
+        }
+        scalar => {
+            let text = escape(plain_text(scalar));
+            xml.push_str(&format!("{indent}<{name}>{text}</{name}>\n"));
+            return Ok(());
+        }
+    };
+
+    xml.push_str(&format!("{indent}<{name}"));
+    let mut text = None;
+    let mut children = Vec::new();
+    for (key, value) in entries {
+        if key == TEXT_KEY {
+            text = Some(scalar_text(name, key, value)?);
+        } else if let Some(attribute) = key.strip_prefix(ATTRIBUTE_PREFIX) {
+            check_name(attribute)?;
+            let value = escape(scalar_text(name, key, value)?);
+            xml.push_str(&format!(" {attribute}=\"{value}\""));
+        } else {
+            children.push((key, value));
+        }
+    }
+
+    match (text, children.is_empty()) {
+        (Some(_), false) => Err(anyhow!(
+            "<{name}> has both `{TEXT_KEY}` and child elements, which can't be converted"
+        )),
+        (Some(text), true) => {
+            xml.push_str(&format!(">{}</{name}>\n", escape(text)));
+            Ok(())
+        }
+        (None, true) => {
+            xml.push_str("/>\n");
+            Ok(())
+        }
+        (None, false) => {
+            xml.push_str(">\n");
+            for (key, value) in children {
+                write_element(xml, key, value, depth + 1)?;
+            }
+            xml.push_str(&format!("{indent}</{name}>\n"));
+            Ok(())
+        }
+    }
+}
+
+/// The text of an attribute or `#text` value, which must not be a map or array
+fn scalar_text(element: &str, key: &str, value: &Value) -> anyhow::Result<String> {
+    match value {
+        Value::Array(_) | Value::Object(_) => Err(anyhow!(
+            "`{key}` of <{element}> must be a plain value to be written as XML"
+        )),
+        value => Ok(plain_text(value)),
+    }
+}
+
+fn plain_text(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+fn check_name(name: &str) -> anyhow::Result<()> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|char| char.is_alphabetic() || char == '_' || char == ':')
+        && chars.all(|char| char.is_alphanumeric() || matches!(char, '_' | ':' | '-' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(anyhow!("`{name}` is not a valid XML name"))
+    }
+}
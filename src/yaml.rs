@@ -0,0 +1,476 @@
+//! YAML specifics: anchors, aliases and merge keys, and YAML 1.1
+//! compatibility.
+//!
+//! serde_yaml expands aliases by replaying the node they refer to, but takes
+//! `<<` for an ordinary key, and only notices an alias inside its own anchor
+//! once it runs out of recursion. `check_aliases` looks for those first, and
+//! `merge_keys` applies the merges.
+//!
+//! serde_yaml follows YAML 1.2, where only `true` and `false` are booleans.
+//! Parsers written for 1.1 also read plain `yes`, `no`, `on`, `off`, `y` and
+//! `n` as booleans, `0755` as octal and `22:22` as a base 60 integer. With
+//! `--yaml-compat 1.1`, `value::Reader` resolves the plain scalars of a YAML
+//! input that way too, while quoted ones stay strings. Whichever spec the
+//! input follows, strings a 1.1 parser would misread are quoted in YAML output.
+//!
+//! serde_yaml also reads every number past 64 bits as a float, which rounds
+//! those with more digits than a float holds. `Numbers` finds them first, so
+//! they keep their digits, and `Quoted` writes them as they were read.
+//!
+//! serde_yaml only writes block style, so `--yaml-style flow` writes the
+//! collections itself, and has serde_yaml write each scalar in them.
+
+use std::{
+    borrow::Cow,
+    collections::{hash_map::RandomState, HashMap},
+    hash::{BuildHasher, Hasher},
+    io::{self, Write},
+    sync::OnceLock,
+};
+
+use serde::{
+    ser::{SerializeMap, SerializeSeq},
+    Serialize, Serializer,
+};
+use serde_json::{Map, Number, Value};
+use yaml_rust2::{parser::Parser, scanner::TScalarStyle, Event};
+
+use super::{compare, number, Aliases, TranscodeError, YamlQuote, YamlStyle};
+
+/// Fail on an alias inside the node its anchor names, which would expand
+/// forever, or on any alias at all under `Aliases::Error`
+pub fn check_aliases(content: &str, policy: Aliases) -> Result<(), TranscodeError> {
+    if !content.contains('*') {
+        return Ok(());
+    }
+
+    // The anchors of the collections being read, 0 for those without one
+    let mut open = Vec::new();
+    let mut parser = Parser::new_from_str(content);
+    loop {
+        // serde_yaml reports syntax errors, with its own positions
+        let Ok((event, marker)) = parser.next_token() else {
+            return Ok(());
+        };
+        let problem = match event {
+            Event::StreamEnd => return Ok(()),
+            Event::SequenceStart(anchor, _) | Event::MappingStart(anchor, _) => {
+                open.push(anchor);
+                continue;
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                open.pop();
+                continue;
+            }
+            Event::Alias(_) if policy == Aliases::Error => "isn't allowed with --aliases error",
+            Event::Alias(anchor) if open.contains(&anchor) => {
+                "is inside the node it refers to, so it would never finish expanding"
+            }
+            _ => continue,
+        };
+
+        let name: String = content
+            .chars()
+            .skip(marker.index() + 1)
+            .take_while(|c| !c.is_whitespace() && !",[]{}".contains(*c))
+            .collect();
+        return Err(TranscodeError::at(
+            marker.line(),
+            Some(marker.col() + 1),
+            format_args!("alias `*{name}` {problem}"),
+        ));
+    }
+}
+
+/// Merge the maps under each `<<` key into the map holding it. Keys the map
+/// has itself win, and so do maps earlier in a list of them
+pub fn merge_keys(value: &mut Value, path: &mut String) -> Result<(), TranscodeError> {
+    match value {
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                let len = compare::push_segment(path, &index.to_string());
+                merge_keys(item, path)?;
+                path.truncate(len);
+            }
+        }
+        Value::Object(entries) => {
+            for (key, value) in entries.iter_mut() {
+                let len = compare::push_segment(path, key);
+                merge_keys(value, path)?;
+                path.truncate(len);
+            }
+            if !entries.contains_key("<<") {
+                return Ok(());
+            }
+
+            let own: Vec<String> = entries.keys().filter(|key| *key != "<<").cloned().collect();
+            let mut merged = Map::new();
+            for (key, value) in std::mem::take(entries) {
+                if key != "<<" {
+                    merged.insert(key, value);
+                    continue;
+                }
+                let maps = match value {
+                    Value::Array(items) => items,
+                    map => vec![map],
+                };
+                for map in maps {
+                    let Value::Object(map) = map else {
+                        let len = compare::push_segment(path, "<<");
+                        let err = TranscodeError::syntax(format_args!(
+                            "{path} must be a map or a list of maps to merge"
+                        ));
+                        path.truncate(len);
+                        return Err(err);
+                    };
+                    for (key, value) in map {
+                        if !merged.contains_key(&key) && !own.contains(&key) {
+                            merged.insert(key, value);
+                        }
+                    }
+                }
+            }
+            *entries = merged;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// The plain scalars of a YAML input that serde_yaml reads as floats which
+/// can't hold them, by the bits of the float each reads as
+#[derive(Default)]
+pub struct Numbers(HashMap<u64, Option<Number>>);
+
+impl Numbers {
+    pub fn scan(content: &str) -> Self {
+        let mut numbers = Numbers::default();
+        // Nothing shorter than 16 digits and points has more than a float holds
+        let longest = content
+            .split(|c: char| !c.is_ascii_digit() && c != '.')
+            .map(str::len)
+            .max();
+        if longest < Some(16) {
+            return numbers;
+        }
+
+        let mut parser = Parser::new_from_str(content);
+        while let Ok((event, _)) = parser.next_token() {
+            let text = match event {
+                Event::StreamEnd => break,
+                Event::Scalar(text, TScalarStyle::Plain, _, None) => text,
+                _ => continue,
+            };
+            let Ok(number) = text.parse::<Number>() else {
+                continue;
+            };
+            let Some(float) = number.as_f64() else {
+                continue;
+            };
+            // Of two numbers rounding to the same float, neither can be told
+            // from the other, so both read as the float
+            let exact = number::unfit(&number).is_none();
+            numbers
+                .0
+                .entry(float.to_bits())
+                .and_modify(|known| {
+                    if exact
+                        || known
+                            .as_ref()
+                            .is_some_and(|known| !number::equal(known, &number))
+                    {
+                        *known = None;
+                    }
+                })
+                .or_insert((!exact).then_some(number));
+        }
+        numbers
+    }
+
+    /// The number serde_yaml read as `float`, if it rounded one
+    pub fn get(&self, float: f64) -> Option<&Number> {
+        self.0.get(&float.to_bits())?.as_ref()
+    }
+}
+
+/// What a plain YAML 1.1 scalar resolves to, if it isn't a string
+pub fn resolve(scalar: &str) -> Option<Value> {
+    match scalar {
+        "y" | "Y" | "yes" | "Yes" | "YES" | "on" | "On" | "ON" | "true" | "True" | "TRUE" => {
+            Some(Value::Bool(true))
+        }
+        "n" | "N" | "no" | "No" | "NO" | "off" | "Off" | "OFF" | "false" | "False" | "FALSE" => {
+            Some(Value::Bool(false))
+        }
+        _ => integer(scalar).map(Value::from),
+    }
+}
+
+/// A YAML 1.1 integer, which may be binary, octal, hexadecimal or base 60, and
+/// have `_` between its digits
+fn integer(scalar: &str) -> Option<i64> {
+    let (negative, unsigned) = match scalar.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, scalar.strip_prefix('+').unwrap_or(scalar)),
+    };
+    if !unsigned.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let digits = |text: &str, radix| {
+        let text = text.replace('_', "");
+        if text.is_empty() || !text.chars().all(|c| c.is_digit(radix)) {
+            return None;
+        }
+        i64::from_str_radix(&text, radix).ok()
+    };
+
+    let magnitude = if let Some(binary) = unsigned.strip_prefix("0b") {
+        digits(binary, 2)?
+    } else if let Some(hex) = unsigned.strip_prefix("0x") {
+        digits(hex, 16)?
+    } else if unsigned == "0" {
+        0
+    } else if let Some(octal) = unsigned.strip_prefix('0') {
+        digits(octal, 8)?
+    } else if unsigned.contains(':') {
+        let mut parts = unsigned.split(':');
+        let mut total = digits(parts.next()?, 10)?;
+        for part in parts {
+            // Each later part is a base 60 digit, written with one or two digits
+            let sixties = (1..=2)
+                .contains(&part.len())
+                .then(|| part.parse::<i64>().ok())
+                .flatten()
+                .filter(|sixties| *sixties < 60)?;
+            total = total.checked_mul(60)?.checked_add(sixties)?;
+        }
+        total
+    } else {
+        digits(unsigned, 10)?
+    };
+
+    Some(if negative { -magnitude } else { magnitude })
+}
+
+/// `value` serializing the strings a YAML 1.1 parser would misread as markers,
+/// which `to_writer` turns into quoted strings. serde_yaml only quotes what YAML
+/// 1.2 would misread, and can't be asked to quote anything else. Under
+/// `YamlQuote::Always` every string that isn't a key is a marker, and under
+/// `YamlQuote::NeverUnsafe` none are. Numbers past a 64-bit integer or float
+/// are markers too, written out as they are
+pub struct Quoted<'a>(pub &'a Value, pub YamlQuote);
+
+impl Serialize for Quoted<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mark_key = |key| match self.1 {
+            YamlQuote::NeverUnsafe => Cow::Borrowed(key),
+            _ => mark(key),
+        };
+        match self.0 {
+            Value::String(string) => match self.1 {
+                YamlQuote::Auto => serializer.serialize_str(&mark(string)),
+                YamlQuote::Always => serializer.serialize_str(&double_quoted(string)),
+                YamlQuote::NeverUnsafe => serializer.serialize_str(string),
+            },
+            Value::Number(number) if number::unfit(number).is_some() => {
+                serializer.serialize_str(&format!("{}[{number}]", marker()))
+            }
+            Value::Number(number) => number::serialize(number, serializer),
+            Value::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(&Quoted(item, self.1))?;
+                }
+                seq.end()
+            }
+            Value::Object(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(&mark_key(key), &Quoted(value, self.1))?;
+                }
+                map.end()
+            }
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+/// What a string to quote is serialized as instead: a plain scalar no document
+/// will hold, since it has a prefix drawn at random for each run
+fn marker() -> &'static str {
+    static MARKER: OnceLock<String> = OnceLock::new();
+    MARKER.get_or_init(|| {
+        let nonce = RandomState::new().build_hasher().finish();
+        format!("rscribe-quote-{nonce:016x}")
+    })
+}
+
+fn mark(string: &str) -> Cow<'_, str> {
+    if resolve(string).is_some() {
+        format!("{}({string})", marker()).into()
+    } else if string.starts_with('?') {
+        // serde_yaml leaves these plain, which reads back in block style, but
+        // not in flow style or by every parser
+        double_quoted(string).into()
+    } else {
+        string.into()
+    }
+}
+
+/// The marker for `string` in double quotes, which holds any string, spelled
+/// out in hexadecimal so it has no spaces or quotes of its own
+fn double_quoted(string: &str) -> String {
+    let hex: String = string.bytes().map(|byte| format!("{byte:02x}")).collect();
+    format!("{}<{hex}>", marker())
+}
+
+/// Write `value`, which serializes its strings through `Quoted`, as YAML
+pub fn to_writer(writer: impl Write, value: &impl Serialize) -> anyhow::Result<()> {
+    let mut writer = Unmark {
+        inner: writer,
+        line: Vec::new(),
+    };
+    serde_yaml::to_writer(&mut writer, value)?;
+    writer.finish()?;
+    Ok(())
+}
+
+pub fn to_string(value: &Value, style: YamlStyle, quote: YamlQuote) -> anyhow::Result<String> {
+    if style == YamlStyle::Flow {
+        let mut flow = String::new();
+        write_flow(value, quote, &mut flow)?;
+        return Ok(flow + "\n");
+    }
+    let mut buffer = Vec::new();
+    to_writer(&mut buffer, &Quoted(value, quote))?;
+    Ok(String::from_utf8(buffer)?)
+}
+
+/// Append `value` to `flow` in flow style, all on one line
+fn write_flow(value: &Value, quote: YamlQuote, flow: &mut String) -> anyhow::Result<()> {
+    match value {
+        Value::Array(items) => {
+            flow.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    flow.push_str(", ");
+                }
+                write_flow(item, quote, flow)?;
+            }
+            flow.push(']');
+        }
+        Value::Object(entries) => {
+            // Keys are quoted as they would be anyway
+            let key_quote = match quote {
+                YamlQuote::Always => YamlQuote::Auto,
+                quote => quote,
+            };
+            flow.push('{');
+            for (index, (key, value)) in entries.iter().enumerate() {
+                if index > 0 {
+                    flow.push_str(", ");
+                }
+                flow.push_str(&scalar(&Value::String(key.clone()), key_quote)?);
+                flow.push_str(": ");
+                write_flow(value, quote, flow)?;
+            }
+            flow.push('}');
+        }
+        value => flow.push_str(&scalar(value, quote)?),
+    }
+    Ok(())
+}
+
+/// `value`, which isn't a collection, as serde_yaml writes it, or as a double
+/// quoted string where that wouldn't hold together in flow style, such as a
+/// literal block or a plain scalar with a comma
+fn scalar(value: &Value, quote: YamlQuote) -> anyhow::Result<String> {
+    let mut buffer = Vec::new();
+    to_writer(&mut buffer, &Quoted(value, quote))?;
+    let text = String::from_utf8(buffer)?;
+    let text = text.strip_suffix('\n').unwrap_or(&text);
+    let quoted = text.starts_with(['\'', '"']);
+    let flow_indicator = text.starts_with('?') || text.contains([',', '[', ']', '{', '}']);
+    if text.contains('\n') || (!quoted && flow_indicator) {
+        return Ok(serde_json::to_string(value)?);
+    }
+    Ok(text.to_string())
+}
+
+/// Replaces markers with the strings they stand for in single or double
+/// quotes, or the numbers they stand for, a line at a time. Markers have no
+/// spaces, so serde_yaml never folds one over two lines
+struct Unmark<W> {
+    inner: W,
+    line: Vec<u8>,
+}
+
+impl<W: Write> Unmark<W> {
+    fn flush_lines(&mut self, end: usize) -> io::Result<()> {
+        let lines: Vec<u8> = self.line.drain(..end).collect();
+        let text = String::from_utf8_lossy(&lines);
+        let marker = marker();
+        if !text.contains(marker) {
+            return self.inner.write_all(&lines);
+        }
+
+        let mut rest = &*text;
+        while let Some(start) = rest.find(marker) {
+            let marked = &rest[start + marker.len()..];
+            let (replacement, after) = if let Some(marked) = marked.strip_prefix('(') {
+                let Some((string, after)) = marked.split_once(')') else {
+                    break;
+                };
+                (format!("'{string}'"), after)
+            } else if let Some(marked) = marked.strip_prefix('[') {
+                let Some((number, after)) = marked.split_once(']') else {
+                    break;
+                };
+                (number.to_string(), after)
+            } else if let Some(marked) = marked.strip_prefix('<') {
+                let Some((hex, after)) = marked.split_once('>') else {
+                    break;
+                };
+                let Some(string) = unhex(hex) else {
+                    break;
+                };
+                // A JSON string is a double-quoted YAML one, escapes and all
+                (Value::String(string).to_string(), after)
+            } else {
+                break;
+            };
+            self.inner.write_all(&rest.as_bytes()[..start])?;
+            self.inner.write_all(replacement.as_bytes())?;
+            rest = after;
+        }
+        self.inner.write_all(rest.as_bytes())
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        self.flush_lines(self.line.len())?;
+        self.inner.flush()
+    }
+}
+
+/// The string whose UTF-8 bytes `hex` spells out two digits at a time
+fn unhex(hex: &str) -> Option<String> {
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|start| u8::from_str_radix(hex.get(start..start + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+impl<W: Write> Write for Unmark<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.line.extend_from_slice(buf);
+        if let Some(newline) = self.line.iter().rposition(|byte| *byte == b'\n') {
+            self.flush_lines(newline + 1)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
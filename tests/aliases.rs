@@ -0,0 +1,88 @@
+mod common;
+
+use common::Dir;
+use serde_json::json;
+
+const ANCHORED: &str = "base: &base
+  host: localhost
+  port: 80
+extra: &extra
+  port: 8080
+  tls: true
+web:
+  <<: [*base, *extra]
+  name: web
+  host: example.com
+copy: *base
+";
+
+#[test]
+fn expands_aliases_and_merge_keys_by_default() {
+    let dir = Dir::new();
+    dir.write("a.yaml", ANCHORED);
+    let run = dir.run(&["a.yaml", "--stdout", "-f", "json"]).success();
+    let expanded = json!({
+        "base": {"host": "localhost", "port": 80},
+        "extra": {"port": 8080, "tls": true},
+        // Its own keys win, then those of the earlier merged map
+        "web": {"port": 80, "tls": true, "name": "web", "host": "example.com"},
+        "copy": {"host": "localhost", "port": 80},
+    });
+    assert_eq!(common::json(&run.stdout), expanded);
+
+    let run = dir
+        .run(&["a.yaml", "--stdout", "-f", "json", "--aliases", "expand"])
+        .success();
+    assert_eq!(common::json(&run.stdout), expanded);
+}
+
+#[test]
+fn names_the_first_alias_under_aliases_error() {
+    let dir = Dir::new();
+    dir.write("a.yaml", ANCHORED);
+    let run = dir
+        .run(&["a.yaml", "-f", "json", "--aliases", "error"])
+        .failure(3);
+    assert_eq!(
+        run.stderr,
+        "Error: a.yaml:8:8: alias `*base` isn't allowed with --aliases error\n"
+    );
+    assert!(!dir.exists("a.json"));
+
+    dir.write("plain.yaml", "a: 1\n");
+    dir.run(&["plain.yaml", "-f", "json", "--aliases", "error"])
+        .success();
+}
+
+#[test]
+fn refuses_an_alias_inside_its_own_anchor() {
+    let dir = Dir::new();
+    dir.write("list.yaml", "a: &x [1, *x]\n");
+    let run = dir.run(&["list.yaml", "-f", "json"]).failure(3);
+    assert_eq!(
+        run.stderr,
+        "Error: list.yaml:1:11: alias `*x` is inside the node it refers to, so it would never finish expanding\n"
+    );
+
+    dir.write("map.yaml", "a: &x\n  b:\n    c: *x\n");
+    let run = dir.run(&["map.yaml", "-f", "toml"]).failure(3);
+    assert!(
+        run.stderr
+            .starts_with("Error: map.yaml:3:8: alias `*x` is inside"),
+        "{}",
+        run.stderr
+    );
+}
+
+#[test]
+fn refuses_merging_something_other_than_maps() {
+    let dir = Dir::new();
+    dir.write("m.yaml", "s: &s 1\nm:\n  <<: *s\n");
+    let run = dir.run(&["m.yaml", "-f", "json"]).failure(3);
+    assert!(
+        run.stderr
+            .contains("/m/<< must be a map or a list of maps to merge"),
+        "{}",
+        run.stderr
+    );
+}
@@ -0,0 +1,145 @@
+use scribe::{
+    transcode, transcode_str, DuplicateKeys, FileFormat, Input, NullPolicy, Options, Output,
+    TranscodeError,
+};
+
+#[test]
+fn converts_a_string_in_memory() {
+    let yaml = transcode_str(
+        r#"{"name": "web", "ports": [80, 443]}"#,
+        FileFormat::Json,
+        FileFormat::Yaml,
+        &Options::default(),
+    )
+    .unwrap();
+    assert_eq!(yaml, "name: web\nports:\n- 80\n- 443\n");
+}
+
+#[test]
+fn builds_options() {
+    let options = Options::default().indent(4).sort_keys(true);
+    let json = transcode_str(
+        "b = 1\na = 2\n",
+        FileFormat::Toml,
+        FileFormat::Json,
+        &options,
+    );
+    assert_eq!(json.unwrap(), "{\n    \"a\": 2,\n    \"b\": 1\n}\n");
+
+    let options = Options::default().compact(true).query("/a");
+    let json = transcode_str(
+        "a:\n  b: [1, 2]\n",
+        FileFormat::Yaml,
+        FileFormat::Json,
+        &options,
+    );
+    assert_eq!(json.unwrap(), "{\"b\":[1,2]}\n");
+}
+
+#[test]
+fn places_a_parse_error() {
+    let err = transcode_str(
+        "{\n  \"a\": 1,\n  \"b\" 2\n}",
+        FileFormat::Json,
+        FileFormat::Yaml,
+        &Options::default(),
+    )
+    .unwrap_err();
+    match err {
+        TranscodeError::Parse {
+            line,
+            column,
+            message,
+        } => {
+            assert_eq!((line, column), (Some(3), Some(7)));
+            assert_eq!(message, "expected `:`");
+        }
+        err => panic!("expected a parse error, not {err:?}"),
+    }
+}
+
+#[test]
+fn places_errors_from_the_crates_own_readers() {
+    let err = transcode_str(
+        "[a]\nx = 1\nnot a pair\n",
+        FileFormat::Ini,
+        FileFormat::Json,
+        &Options::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        TranscodeError::Parse {
+            line: Some(3),
+            column: None,
+            ..
+        }
+    ));
+
+    let err = transcode_str(
+        "a: 1\nb: 2\na: 3\n",
+        FileFormat::Yaml,
+        FileFormat::Json,
+        &Options::default(),
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "3:1: duplicate key /a");
+}
+
+#[test]
+fn keeps_a_duplicate_key_as_told() {
+    let options = Options::default().duplicate_keys(DuplicateKeys::First);
+    let json = transcode_str(
+        "a: 1\na: 2\n",
+        FileFormat::Yaml,
+        FileFormat::Json,
+        &options.compact(true),
+    );
+    assert_eq!(json.unwrap(), "{\"a\":1}\n");
+}
+
+#[test]
+fn fails_to_serialize_what_the_output_cant_hold() {
+    let err = transcode_str(
+        r#"{"a": null, "b": 1}"#,
+        FileFormat::Json,
+        FileFormat::Toml,
+        &Options::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, TranscodeError::Serialize(_)), "{err:?}");
+
+    let options = Options::default().null_policy(NullPolicy::Omit);
+    let toml = transcode_str(
+        r#"{"a": null, "b": 1}"#,
+        FileFormat::Json,
+        FileFormat::Toml,
+        &options,
+    );
+    assert_eq!(toml.unwrap(), "b = 1\n");
+}
+
+#[test]
+fn refuses_binary_output_as_a_string() {
+    let err = transcode_str(
+        "a = 1",
+        FileFormat::Toml,
+        FileFormat::MsgPack,
+        &Options::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, TranscodeError::Unsupported(_)), "{err:?}");
+}
+
+#[test]
+fn converts_to_binary_formats_as_bytes() {
+    let input = Input::new("-".into(), Some(FileFormat::Json));
+    let output = Output::new("-".into(), Some(FileFormat::MsgPack));
+    let bytes = transcode(br#"{"a":1}"#, &input, &output, &Options::default()).unwrap();
+    assert_eq!(bytes, [0x81, 0xa1, b'a', 0x01]);
+
+    let input = Input::new("-".into(), Some(FileFormat::MsgPack));
+    let output = Output::new("-".into(), Some(FileFormat::Json));
+    let json = transcode(&bytes, &input, &output, &Options::default().compact(true));
+    assert_eq!(json.unwrap(), b"{\"a\":1}\n");
+}
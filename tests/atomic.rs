@@ -0,0 +1,72 @@
+mod common;
+
+use std::fs;
+
+use common::Dir;
+
+/// The names of the files in `dir`, sorted
+fn listing(dir: &Dir) -> Vec<String> {
+    let mut names: Vec<_> = fs::read_dir(dir.path("."))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn leaves_the_destination_untouched_when_serializing_fails() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: null\n");
+    dir.write("config.toml", "old = 1\n");
+    dir.run(&["config.yaml", "-f", "toml", "--force"])
+        .failure(4);
+    assert_eq!(dir.read("config.toml"), "old = 1\n");
+    assert_eq!(listing(&dir), ["config.toml", "config.yaml"]);
+}
+
+#[test]
+fn leaves_the_destination_untouched_when_a_stream_fails_partway() {
+    let dir = Dir::new();
+    let mut json = String::from("[");
+    for index in 0..10_000 {
+        json.push_str(&format!("{{\"id\":{index}}},\n"));
+    }
+    json.push_str("{\"b\":1,\"b\":2}]");
+    dir.write("big.json", &json);
+    dir.write("big.ndjson", "old\n");
+    dir.run(&["big.json", "-f", "ndjson", "--force"]).failure(3);
+    assert_eq!(dir.read("big.ndjson"), "old\n");
+    assert_eq!(listing(&dir), ["big.json", "big.ndjson"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn keeps_the_permissions_of_the_file_it_replaces() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    dir.write("config.toml", "old = 1\n");
+    fs::set_permissions(dir.path("config.toml"), fs::Permissions::from_mode(0o640)).unwrap();
+    dir.run(&["config.yaml", "-f", "toml", "--force"]).success();
+    assert_eq!(dir.read("config.toml"), "a = 1\n");
+    let mode = fs::metadata(dir.path("config.toml"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o640);
+}
+
+#[cfg(unix)]
+#[test]
+fn gives_a_new_file_the_usual_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    dir.write("plain", "");
+    dir.run(&["config.yaml", "-f", "toml"]).success();
+    let mode = |name| fs::metadata(dir.path(name)).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode("config.toml"), mode("plain"));
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn copies_the_destination_aside_before_replacing_it() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    dir.write("config.toml", "hand = \"edited\"\n");
+    dir.run(&["config.yaml", "-f", "toml", "--backup"])
+        .success();
+    assert_eq!(dir.read("config.toml"), "a = 1\n");
+    assert_eq!(dir.read("config.toml.bak"), "hand = \"edited\"\n");
+}
+
+#[test]
+fn takes_a_suffix() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    dir.write("config.toml", "hand = \"edited\"\n");
+    dir.run(&["config.yaml", "-f", "toml", "--backup=.orig"])
+        .success();
+    assert_eq!(dir.read("config.toml.orig"), "hand = \"edited\"\n");
+    assert!(!dir.exists("config.toml.bak"));
+}
+
+#[test]
+fn does_nothing_without_a_destination() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    dir.run(&["config.yaml", "-f", "toml", "--backup"])
+        .success();
+    assert_eq!(dir.read("config.toml"), "a = 1\n");
+    assert!(!dir.exists("config.toml.bak"));
+}
+
+#[test]
+fn refuses_to_overwrite_a_backup_without_force() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    dir.write("config.toml", "current = 1\n");
+    dir.write("config.toml.bak", "older = 1\n");
+    let run = dir
+        .run(&["config.yaml", "-f", "toml", "--backup"])
+        .failure(1);
+    assert_eq!(
+        run.stderr,
+        "Error: Refusing to overwrite the backup config.toml.bak; pass --force\n"
+    );
+    assert_eq!(dir.read("config.toml"), "current = 1\n");
+    assert_eq!(dir.read("config.toml.bak"), "older = 1\n");
+
+    dir.run(&["config.yaml", "-f", "toml", "--backup", "--force"])
+        .success();
+    assert_eq!(dir.read("config.toml"), "a = 1\n");
+    assert_eq!(dir.read("config.toml.bak"), "current = 1\n");
+}
+
+#[test]
+fn keeps_both_files_when_the_conversion_fails() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: null\n");
+    dir.write("config.toml", "current = 1\n");
+    dir.write("config.toml.bak", "older = 1\n");
+    dir.run(&["config.yaml", "-f", "toml", "--backup", "--force"])
+        .failure(4);
+    assert_eq!(dir.read("config.toml"), "current = 1\n");
+    assert_eq!(dir.read("config.toml.bak"), "older = 1\n");
+}
+
+#[test]
+fn aborts_when_the_backup_cant_be_made() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    dir.write("config.toml", "current = 1\n");
+    dir.write("config.toml.bak/keep", "");
+    let run = dir
+        .run(&["config.yaml", "-f", "toml", "--backup", "--force"])
+        .failure(5);
+    assert!(
+        run.stderr
+            .starts_with("Error: Failed to back up config.toml to config.toml.bak:"),
+        "{}",
+        run.stderr
+    );
+    assert_eq!(dir.read("config.toml"), "current = 1\n");
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::Dir;
+
+/// A scratch directory holding two valid YAML files and a broken one between
+/// them
+fn mixed() -> Dir {
+    let dir = Dir::new();
+    dir.write("a.yaml", "a: 1\n");
+    dir.write("b.yaml", "b: [\n");
+    dir.write("c.yaml", "c: 3\n");
+    dir
+}
+
+#[test]
+fn converts_the_rest_past_a_broken_file() {
+    let dir = mixed();
+    let run = dir
+        .run(&["a.yaml", "b.yaml", "c.yaml", "-f", "json"])
+        .failure(3);
+    assert!(run.stderr.contains("Wrote a.yaml to a.json\n"));
+    assert!(run.stderr.contains("Wrote c.yaml to c.json\n"));
+    assert!(run.stderr.contains("b.yaml:2:1: "), "{}", run.stderr);
+    assert!(run
+        .stderr
+        .ends_with("Error: Converted 2 files, skipped 0, 1 error\n"));
+    assert!(dir.exists("a.json") && dir.exists("c.json"));
+    assert!(!dir.exists("b.json"));
+}
+
+#[test]
+fn stops_at_the_first_failure_under_fail_fast() {
+    let dir = mixed();
+    let run = dir
+        .run(&[
+            "a.yaml",
+            "b.yaml",
+            "c.yaml",
+            "-f",
+            "json",
+            "--fail-fast",
+            "--jobs",
+            "1",
+        ])
+        .failure(3);
+    assert!(
+        run.stderr.ends_with(
+            "Error: b.yaml:2:1: did not find expected node content, while parsing a flow node\n"
+        ),
+        "{}",
+        run.stderr
+    );
+    assert!(dir.exists("a.json"));
+    assert!(!dir.exists("c.json"));
+}
+
+#[test]
+fn expands_glob_patterns_itself() {
+    let dir = Dir::new();
+    dir.write("a.yaml", "a: 1\n");
+    dir.write("c.yaml", "c: 3\n");
+    dir.write("d.json", "{}");
+    dir.run(&["*.yaml", "-f", "toml"]).success();
+    assert_eq!(dir.read("a.toml"), "a = 1\n");
+    assert_eq!(dir.read("c.toml"), "c = 3\n");
+    assert!(!dir.exists("d.toml"));
+}
+
+#[test]
+fn needs_to_or_out_dir_for_several_inputs() {
+    let dir = mixed();
+    let run = dir.run(&["a.yaml", "c.yaml", "out.json"]).failure(2);
+    assert_eq!(
+        run.stderr,
+        "Error: Converting several inputs needs --to or --out-dir\n"
+    );
+}
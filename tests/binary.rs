@@ -0,0 +1,171 @@
+mod common;
+
+use common::Dir;
+
+/// `{"b": h'61'}`, a map holding a one-byte byte string
+const CBOR: &[u8] = b"\xa1\x61\x62\x41\x61";
+
+#[test]
+fn bytes_stay_bytes_between_binary_formats() {
+    let dir = Dir::new();
+    dir.write("b.cbor", CBOR);
+    dir.run(&["b.cbor", "b.msgpack"]).success();
+    // A fixmap of one entry whose value is bin 8 of one byte, not a str
+    assert_eq!(dir.bytes("b.msgpack"), b"\x81\xa1b\xc4\x01a");
+
+    dir.run(&["b.msgpack", "back.cbor"]).success();
+    assert_eq!(dir.bytes("back.cbor"), CBOR);
+}
+
+#[test]
+fn bytes_become_base64_in_text_formats() {
+    let dir = Dir::new();
+    dir.write("b.cbor", CBOR);
+    dir.run(&["b.cbor", "b.json"]).success();
+    assert_eq!(
+        common::json(&dir.read("b.json")),
+        common::json(r#"{"b": "YQ=="}"#)
+    );
+    dir.run(&["b.cbor", "b.yaml"]).success();
+    assert_eq!(dir.read("b.yaml"), "b: YQ==\n");
+}
+
+#[test]
+fn bytes_stay_bytes_through_property_lists_and_bson() {
+    let dir = Dir::new();
+    dir.write("b.cbor", CBOR);
+    dir.run(&["b.cbor", "b.plist"]).success();
+    assert!(dir.read("b.plist").contains("<data>"));
+    dir.run(&["b.plist", "b.bson"]).success();
+    dir.run(&["b.bson", "back.cbor"]).success();
+    assert_eq!(dir.bytes("back.cbor"), CBOR);
+}
+
+const MIXED: &str = r#"{"m":{"n":{"a":[1,-2,[3.5,true,false,null]]}},"big":18446744073709551615,"neg":-9223372036854775808,"s":"x"}"#;
+
+#[test]
+fn round_trips_json_through_msgpack() {
+    let dir = Dir::new();
+    dir.write("a.json", MIXED);
+    dir.run(&["a.json", "a.msgpack"]).success();
+    // A fixmap of four entries, starting with the key `m`
+    assert_eq!(&dir.bytes("a.msgpack")[..3], b"\x84\xa1m");
+    dir.run(&["a.msgpack", "back.json", "--compact"]).success();
+    assert_eq!(dir.read("back.json"), format!("{MIXED}\n"));
+}
+
+#[test]
+fn reads_msgpack_by_its_mpk_extension_into_text_formats() {
+    let dir = Dir::new();
+    dir.write("a.json", r#"{"a":[1,2.5],"b":{"c":true}}"#);
+    dir.run(&["a.json", "a.mpk"]).success();
+    dir.run(&["a.mpk", "a.yaml"]).success();
+    assert_eq!(dir.read("a.yaml"), "a:\n- 1\n- 2.5\nb:\n  c: true\n");
+    dir.run(&["a.mpk", "a.toml"]).success();
+    assert_eq!(dir.read("a.toml"), "a = [1, 2.5]\n\n[b]\nc = true\n");
+}
+
+#[test]
+fn round_trips_nested_arrays_and_maps_through_cbor() {
+    let dir = Dir::new();
+    let json =
+        r#"{"l":[[1,[2]],{"k":{"v":[]}}],"big":18446744073709551615,"neg":-9223372036854775808}"#;
+    dir.write("a.json", json);
+    dir.run(&["a.json", "a.cbor"]).success();
+    // A map of three entries whose first key is `l`, holding an array of two
+    assert_eq!(&dir.bytes("a.cbor")[..4], b"\xa3\x61l\x82");
+    dir.run(&["a.cbor", "back.json", "--compact"]).success();
+    assert_eq!(dir.read("back.json"), format!("{json}\n"));
+
+    dir.run(&["a.cbor", "a.yaml"]).success();
+    let run = dir.run(&["a.yaml", "--to", "cbor", "--stdout"]).success();
+    assert_eq!(run.bytes, dir.bytes("a.cbor"));
+}
+
+#[test]
+fn converts_cbor_to_and_from_toml() {
+    let dir = Dir::new();
+    dir.write("a.toml", "a = [1, 2]\n\n[t]\nb = \"x\"\n");
+    dir.run(&["a.toml", "a.cbor"]).success();
+    dir.run(&["a.cbor", "back.toml"]).success();
+    assert_eq!(dir.read("back.toml"), dir.read("a.toml"));
+}
+
+#[test]
+fn refuses_a_cbor_bignum_past_64_bits() {
+    let dir = Dir::new();
+    // `{"b": 2(h'010000000000000000')}`, which is 2^64
+    dir.write(
+        "big.cbor",
+        b"\xa1\x61b\xc2\x49\x01\x00\x00\x00\x00\x00\x00\x00\x00",
+    );
+    let run = dir.run(&["big.cbor", "--stdout", "-f", "json"]).failure(3);
+    assert!(
+        run.stderr
+            .contains("integer 18446744073709551616 does not fit in 64 bits"),
+        "{}",
+        run.stderr
+    );
+}
+
+#[test]
+fn explains_truncated_and_deeply_nested_cbor() {
+    let dir = Dir::new();
+    dir.write("empty.cbor", "");
+    // `{"a": ` and nothing after it
+    dir.write("cut.cbor", b"\xa1\x61a");
+    for name in ["empty.cbor", "cut.cbor"] {
+        let run = dir.run(&[name, "-f", "json"]).failure(3);
+        assert_eq!(
+            run.stderr,
+            format!("Error: {name}: the data ends partway through a value\n")
+        );
+    }
+
+    let mut deep = vec![0x81; 300];
+    deep.push(0x01);
+    dir.write("deep.cbor", deep);
+    let run = dir.run(&["deep.cbor", "-f", "json"]).failure(3);
+    assert_eq!(
+        run.stderr,
+        "Error: deep.cbor: the values are nested too deeply\n"
+    );
+}
+
+/// Maps whose only key is the one byte strings are kept under, or that key
+/// behind `$` escapes, which are only maps
+const LOOKALIKE: &str =
+    r#"{"a":{"$scribe::bytes":"AQI="},"b":{"$$scribe::bytes":1},"c":{"$scribe::bytes":"x","d":1}}"#;
+
+#[test]
+fn keeps_a_map_that_looks_like_a_byte_string_a_map() {
+    let dir = Dir::new();
+    dir.write("k.json", LOOKALIKE);
+    let run = dir
+        .run(&["k.json", "--stdout", "-f", "json", "--compact"])
+        .success();
+    assert_eq!(run.stdout, format!("{LOOKALIKE}\n"));
+
+    for format in ["yaml", "toml", "cbor", "msgpack", "bson", "plist"] {
+        let name = format!("k.{format}");
+        dir.run(&["k.json", &name]).success();
+        let run = dir
+            .run(&[&name, "--stdout", "-f", "json", "--compact"])
+            .success();
+        assert_eq!(run.stdout, format!("{LOOKALIKE}\n"), "{format}");
+    }
+    // Written as a map, not as the bytes 01 02
+    assert!(!dir.bytes("k.cbor").windows(3).any(|w| w == b"\x42\x01\x02"));
+}
+
+#[test]
+fn verifies_a_map_that_looks_like_a_byte_string() {
+    let dir = Dir::new();
+    dir.write("k.json", LOOKALIKE);
+    for format in ["yaml", "cbor"] {
+        let run = dir
+            .run(&["k.json", "-f", format, "--verify=strict"])
+            .success();
+        assert!(!run.stderr.contains("round-trip"), "{}", run.stderr);
+    }
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use common::Dir;
+use serde_json::json;
+
+/// A document with an ObjectId, a date, generic and UUID binary data and a
+/// Decimal128, written from `mongo.json`
+const BSON: &[u8] = include_bytes!("fixtures/mongo.bson");
+const EXTENDED: &str = include_str!("fixtures/mongo.json");
+
+#[test]
+fn maps_bson_types_to_plain_strings() {
+    let dir = Dir::new();
+    dir.write("m.bson", BSON);
+    let run = dir.run(&["m.bson", "--stdout", "-f", "yaml"]).success();
+    assert_eq!(
+        run.stdout,
+        "_id: 65a1b2c3d4e5f60718293a4b\nname: widget\ncreated: 2024-01-12T18:30:00Z\n\
+         blob: AQID/w==\nuuid: ASNFZ4mrze8BI0VniavN7w==\nprice: '19.99'\nqty: 3\n\
+         tags:\n- a\n- b\n"
+    );
+}
+
+#[test]
+fn writes_extended_json_as_bson_types() {
+    let dir = Dir::new();
+    dir.write("m.json", EXTENDED);
+    dir.run(&["m.json", "m.bson"]).success();
+    assert_eq!(dir.bytes("m.bson"), BSON);
+}
+
+#[test]
+fn round_trips_bson_through_extended_json() {
+    let dir = Dir::new();
+    dir.write("m.bson", BSON);
+    dir.run(&["m.bson", "m.json", "--bson-extended"]).success();
+    assert_eq!(common::json(&dir.read("m.json")), common::json(EXTENDED));
+    dir.run(&["m.json", "back.bson"]).success();
+    assert_eq!(dir.bytes("back.bson"), BSON);
+}
+
+#[test]
+fn round_trips_bson_through_yaml_with_bson_extended() {
+    let dir = Dir::new();
+    dir.write("m.bson", BSON);
+    dir.run(&["m.bson", "m.yaml", "--bson-extended"]).success();
+    assert!(dir
+        .read("m.yaml")
+        .contains("$oid: 65a1b2c3d4e5f60718293a4b"));
+    dir.run(&["m.yaml", "back.bson"]).success();
+    assert_eq!(dir.bytes("back.bson"), BSON);
+}
+
+#[test]
+fn reads_documents_one_after_another_as_an_array() {
+    let dir = Dir::new();
+    dir.write("a.json", r#"[{"n":1},{"n":2}]"#);
+    dir.run(&["a.json", "a.bson", "--multi-doc"]).success();
+    let run = dir
+        .run(&["a.bson", "--stdout", "-f", "json", "--compact"])
+        .success();
+    assert_eq!(run.stdout, "[{\"n\":1},{\"n\":2}]\n");
+
+    dir.write("dump.bson", [BSON, BSON].concat());
+    let run = dir.run(&["dump.bson", "--stdout", "-f", "json"]).success();
+    let value = common::json(&run.stdout);
+    assert_eq!(value.as_array().map(Vec::len), Some(2));
+    assert_eq!(value[1]["_id"], json!("65a1b2c3d4e5f60718293a4b"));
+}
+
+#[test]
+fn refuses_an_array_without_multi_doc() {
+    let dir = Dir::new();
+    dir.write("a.json", r#"[{"n":1}]"#);
+    let run = dir.run(&["a.json", "a.bson"]).failure(4);
+    assert_eq!(
+        run.stderr,
+        "Error: a.json: BSON needs a map at the root; pass --multi-doc to write each item of the array as its own document\n"
+    );
+    assert!(!dir.exists("a.bson"));
+}
+
+#[test]
+fn refuses_an_integer_past_i64() {
+    let dir = Dir::new();
+    dir.write("u.json", r#"{"a":{"big":18446744073709551615}}"#);
+    let run = dir.run(&["u.json", "u.bson"]).failure(4);
+    assert!(
+        run.stderr
+            .contains("18446744073709551615 at `/a/big` is too large for a BSON integer"),
+        "{}",
+        run.stderr
+    );
+}
+
+#[test]
+fn explains_a_truncated_or_malformed_document() {
+    let dir = Dir::new();
+    dir.write("cut.bson", &BSON[..3]);
+    let run = dir.run(&["cut.bson", "-f", "json"]).failure(3);
+    assert_eq!(
+        run.stderr,
+        "Error: cut.bson: Failed to read document 1: the data ends partway through it\n"
+    );
+
+    // A document of one element whose type tag is 0x99, which BSON doesn't have
+    dir.write("tag.bson", b"\x0c\x00\x00\x00\x99a\x00\x01\x00\x00\x00\x00");
+    let run = dir.run(&["tag.bson", "-f", "json"]).failure(3);
+    assert_eq!(
+        run.stderr,
+        "Error: tag.bson: Failed to read document 1 at `a`: invalid tag: 153\n"
+    );
+
+    dir.write("second.bson", [BSON, &BSON[..10]].concat());
+    let run = dir.run(&["second.bson", "-f", "json"]).failure(3);
+    assert!(
+        run.stderr
+            .starts_with("Error: second.bson: Failed to read document 2"),
+        "{}",
+        run.stderr
+    );
+}
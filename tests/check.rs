@@ -0,0 +1,79 @@
+mod common;
+
+use common::Dir;
+
+const YAML: &str = "b: 1\na: [x]\n";
+const JSON: &str = "{\n  \"b\": 1,\n  \"a\": [\n    \"x\"\n  ]\n}\n";
+
+#[test]
+fn passes_an_output_that_is_up_to_date() {
+    let dir = Dir::new();
+    dir.write("config.yaml", YAML);
+    dir.write("config.json", JSON);
+    let run = dir.run(&["config.yaml", "-f", "json", "--check"]).success();
+    assert_eq!(run.stderr, "config.json is up to date\n");
+}
+
+#[test]
+fn fails_an_output_that_differs_without_touching_it() {
+    let dir = Dir::new();
+    dir.write("config.yaml", YAML);
+    dir.write("config.json", "{}\n");
+    let run = dir
+        .run(&["config.yaml", "-f", "json", "--check"])
+        .failure(1);
+    assert_eq!(run.stderr, "Error: config.json is out of date\n");
+    assert_eq!(dir.read("config.json"), "{}\n");
+}
+
+#[test]
+fn shows_a_diff_under_verbose() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    dir.write("config.json", "{\n  \"a\": 2\n}\n");
+    let run = dir
+        .run(&["config.yaml", "-f", "json", "--check", "--verbose"])
+        .failure(1);
+    assert!(
+        run.stderr.contains(
+            "--- config.json\n+++ config.json (converted)\n@@ -1,3 +1,3 @@\n {\n-  \"a\": 2\n+  \"a\": 1\n }\n"
+        ),
+        "{}",
+        run.stderr
+    );
+}
+
+#[test]
+fn fails_with_2_without_an_output_or_on_a_parse_error() {
+    let dir = Dir::new();
+    dir.write("config.yaml", YAML);
+    let run = dir
+        .run(&["config.yaml", "-f", "json", "--check"])
+        .failure(2);
+    assert!(run.stderr.starts_with("Error: Failed to read config.json:"));
+    assert!(!dir.exists("config.json"));
+
+    dir.write("bad.yaml", "b: [\n");
+    dir.write("bad.json", "{}\n");
+    dir.run(&["bad.yaml", "-f", "json", "--check"]).failure(2);
+}
+
+#[test]
+fn checks_with_the_same_layout_options() {
+    let dir = Dir::new();
+    dir.write("config.yaml", YAML);
+    dir.run(&["config.yaml", "-f", "json", "--sort-keys", "--indent", "4"])
+        .success();
+    dir.run(&[
+        "config.yaml",
+        "-f",
+        "json",
+        "--check",
+        "--sort-keys",
+        "--indent",
+        "4",
+    ])
+    .success();
+    dir.run(&["config.yaml", "-f", "json", "--check"])
+        .failure(1);
+}
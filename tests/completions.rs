@@ -0,0 +1,34 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn prints_a_script_for_each_shell_without_an_input() {
+    let dir = Dir::new();
+    for shell in ["bash", "zsh", "fish", "powershell", "elvish"] {
+        let run = dir.run(&["--completions", shell]).success();
+        assert!(run.stdout.contains("scribe"), "{shell}: {}", run.stdout);
+        assert_eq!(run.stderr, "", "{shell}");
+    }
+}
+
+#[test]
+fn offers_the_formats_for_output_format_in_zsh() {
+    let dir = Dir::new();
+    let run = dir.run(&["--completions", "zsh"]).success();
+    assert!(
+        run.stdout
+            .contains(":FORMAT:(json yaml toml msgpack cbor bson ron xml csv ini json5 ndjson plist env properties hcl)"),
+        "{}",
+        run.stdout
+    );
+}
+
+#[test]
+fn refuses_an_unknown_shell() {
+    let dir = Dir::new();
+    let run = dir.run(&["--completions", "tcsh"]).failure(2);
+    assert!(run
+        .stderr
+        .contains("invalid value 'tcsh' for '--completions <SHELL>'"));
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn takes_the_format_and_indent_from_a_local_config() {
+    let dir = Dir::new();
+    dir.write(".rscribe.toml", "to = \"json\"\nindent = 4\n");
+    dir.write("a.yaml", "a: [1]\n");
+    dir.run(&["a.yaml"]).success();
+    assert_eq!(dir.read("a.json"), "{\n    \"a\": [\n        1\n    ]\n}\n");
+}
+
+#[test]
+fn falls_back_to_the_config_home() {
+    let dir = Dir::new();
+    dir.write("rscribe/config.toml", "to = \"toml\"\nsort-keys = true\n");
+    dir.write("a.yaml", "b: 1\na: 2\n");
+    dir.run(&["a.yaml"]).success();
+    assert_eq!(dir.read("a.toml"), "a = 2\nb = 1\n");
+
+    // A local config wins over it, whole
+    dir.write(".rscribe.toml", "to = \"json\"\ncompact = true\n");
+    dir.run(&["a.yaml"]).success();
+    assert_eq!(dir.read("a.json"), "{\"b\":1,\"a\":2}\n");
+}
+
+#[test]
+fn lets_flags_and_environment_variables_win() {
+    let dir = Dir::new();
+    dir.write(
+        ".rscribe.toml",
+        "to = \"json\"\nindent = 4\nnull-policy = \"string\"\n",
+    );
+    dir.write("a.yaml", "a: 1\n");
+    // The configured indent only applies to the JSON it defaults to
+    dir.run(&["a.yaml", "--to", "toml"]).success();
+    assert_eq!(dir.read("a.toml"), "a = 1\n");
+    dir.run(&["a.yaml", "--compact"]).success();
+    assert_eq!(dir.read("a.json"), "{\"a\":1}\n");
+
+    dir.write("n.yaml", "a: null\n");
+    dir.run(&["n.yaml", "-f", "toml"]).success();
+    assert_eq!(dir.read("n.toml"), "a = \"null\"\n");
+    let run = common::run(
+        dir.command()
+            .args(["n.yaml", "-f", "toml", "--force"])
+            .env("RSCRIBE_NULL_POLICY", "omit"),
+        None,
+    )
+    .success();
+    assert_eq!(run.stderr, "Wrote n.yaml to n.toml\n");
+    assert_eq!(dir.read("n.toml"), "");
+}
+
+#[test]
+fn warns_about_unknown_keys() {
+    let dir = Dir::new();
+    dir.write(".rscribe.toml", "to = \"json\"\nindnet = 4\n");
+    dir.write("a.yaml", "a: 1\n");
+    let run = dir.run(&["a.yaml"]).success();
+    assert_eq!(
+        run.stderr,
+        "warning: .rscribe.toml: unknown key `indnet`, ignored\nWrote a.yaml to a.json\n"
+    );
+}
+
+#[test]
+fn names_the_config_on_a_bad_value() {
+    let dir = Dir::new();
+    dir.write(".rscribe.toml", "to = \"jsno\"\n");
+    dir.write("a.yaml", "a: 1\n");
+    let run = dir.run(&["a.yaml"]).failure(2);
+    assert!(
+        run.stderr
+            .starts_with("Error: .rscribe.toml: invalid value 'jsno'"),
+        "{}",
+        run.stderr
+    );
+}
+
+#[test]
+fn skips_the_config_under_no_config() {
+    let dir = Dir::new();
+    dir.write(".rscribe.toml", "to = \"json\"\n");
+    dir.write("a.yaml", "a: 1\n");
+    dir.run(&["a.yaml", "--no-config"]).failure(2);
+    dir.run(&["a.yaml", "--no-config", "-f", "toml"]).success();
+    assert!(!dir.exists("a.json"));
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use common::Dir;
+use serde_json::json;
+
+#[test]
+fn writes_the_union_of_keys_as_the_header() {
+    let dir = Dir::new();
+    dir.write(
+        "a.json",
+        r#"[{"name":"a, b","q":"say \"hi\""},{"name":"c","extra":"1"}]"#,
+    );
+    dir.run(&["a.json", "-f", "csv"]).success();
+    assert_eq!(
+        dir.read("a.csv"),
+        "name,q,extra\n\"a, b\",\"say \"\"hi\"\"\",\nc,,1\n"
+    );
+}
+
+#[test]
+fn reads_quoted_fields_with_commas_and_line_breaks() {
+    let dir = Dir::new();
+    dir.write(
+        "q.csv",
+        "a,b\n\"x, y\",\"line\nbreak\"\n\"\"\"quoted\"\"\",2\n",
+    );
+    let run = dir.run(&["q.csv", "--stdout", "-f", "json"]).success();
+    assert_eq!(
+        common::json(&run.stdout),
+        json!([
+            {"a": "x, y", "b": "line\nbreak"},
+            {"a": "\"quoted\"", "b": "2"},
+        ])
+    );
+}
+
+#[test]
+fn reads_a_header_only_file_as_an_empty_array() {
+    let dir = Dir::new();
+    dir.write("h.csv", "name,q\n");
+    let run = dir.run(&["h.csv", "--stdout", "-f", "json"]).success();
+    assert_eq!(run.stdout, "[]\n");
+}
+
+#[test]
+fn converts_csv_to_yaml_and_toml() {
+    let dir = Dir::new();
+    dir.write("a.csv", "a,b\n1,x\n");
+    dir.run(&["a.csv", "-f", "yaml"]).success();
+    assert_eq!(dir.read("a.yaml"), "- a: '1'\n  b: x\n");
+    dir.run(&["a.csv", "-f", "toml", "--toml-wrap", "rows"])
+        .success();
+    assert_eq!(dir.read("a.toml"), "[[rows]]\na = \"1\"\nb = \"x\"\n");
+}
+
+#[test]
+fn names_the_row_and_key_of_a_nested_value() {
+    let dir = Dir::new();
+    dir.write("n.json", r#"[{"a":1},{"a":2},{"address":{"x":1}}]"#);
+    let run = dir.run(&["n.json", "-f", "csv"]).failure(4);
+    assert_eq!(
+        run.stderr,
+        "Error: n.json: nested value at row 3, key 'address'\n"
+    );
+
+    dir.write("o.json", r#"{"a":1}"#);
+    let run = dir.run(&["o.json", "-f", "csv"]).failure(4);
+    assert!(run
+        .stderr
+        .contains("CSV output needs an array of objects, not an object"));
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use common::Dir;
+
+const TOML: &str = "b = 1.0\na = \"x\"\n[t]\nl = [1, 2]\nwhen = 1979-05-27T07:32:00Z\n";
+
+#[test]
+fn finds_no_difference_in_reordered_documents() {
+    let dir = Dir::new();
+    dir.write("config.toml", TOML);
+    dir.write(
+        "config.json",
+        r#"{"t":{"when":"1979-05-27 07:32:00Z","l":[1,2]},"a":"x","b":1}"#,
+    );
+    let run = dir.run(&["config.toml", "--diff", "config.json"]).success();
+    assert_eq!(run.stdout, "");
+    assert_eq!(run.stderr, "");
+
+    dir.write(
+        "config.yaml",
+        "a: x\nb: 1\nt:\n  l: [1, 2]\n  when: 1979-05-27T07:32:00Z\n",
+    );
+    dir.run(&["config.yaml", "--diff", "config.toml"]).success();
+}
+
+#[test]
+fn lists_each_difference_by_pointer() {
+    let dir = Dir::new();
+    dir.write("config.toml", TOML);
+    dir.write(
+        "legacy.json",
+        r#"{"t":{"l":[1,3],"when":"1979-05-27T07:32:00Z","new":true},"b":1.5}"#,
+    );
+    let run = dir
+        .run(&["config.toml", "--diff", "legacy.json"])
+        .failure(1);
+    assert_eq!(
+        run.stdout,
+        "/b: 1.0 became 1.5\n/a: \"x\" was removed\n/t/l/1: 2 became 3\n/t/new: true was added\n"
+    );
+    assert_eq!(
+        run.stderr,
+        "Error: config.toml and legacy.json differ at 4 paths\n"
+    );
+}
+
+#[test]
+fn fails_with_2_when_either_side_cant_be_read() {
+    let dir = Dir::new();
+    dir.write("config.toml", TOML);
+    dir.write("bad.json", "{");
+    let run = dir.run(&["config.toml", "--diff", "bad.json"]).failure(2);
+    assert!(run.stderr.starts_with("Error: bad.json:"), "{}", run.stderr);
+    dir.run(&["missing.toml", "--diff", "bad.json"]).failure(2);
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use common::Dir;
+use serde_json::json;
+
+const CONFIG: &str = "\
+db:
+  main:
+    host: my host
+    port: 5432
+  note: 'a # b'
+name: x
+list: [1, 2]
+q: say \"hi\"
+";
+
+#[test]
+fn flattens_nested_maps_and_quotes_values() {
+    let dir = Dir::new();
+    dir.write("c.yaml", CONFIG);
+    dir.run(&["c.yaml", "-f", "env", "--env-join-arrays"])
+        .success();
+    assert_eq!(
+        dir.read("c.env"),
+        "DB_MAIN_HOST=\"my host\"\nDB_MAIN_PORT=5432\nDB_NOTE=\"a # b\"\nNAME=x\nLIST=1,2\nQ=\"say \\\"hi\\\"\"\n"
+    );
+}
+
+#[test]
+fn joins_keys_with_env_separator() {
+    let dir = Dir::new();
+    dir.write("c.json", r#"{"db":{"main":{"host":"x"}}}"#);
+    let run = dir
+        .run(&["c.json", "--stdout", "-f", "env", "--env-separator", "__"])
+        .success();
+    assert_eq!(run.stdout, "DB__MAIN__HOST=x\n");
+}
+
+#[test]
+fn refuses_arrays_without_env_join_arrays() {
+    let dir = Dir::new();
+    dir.write("c.yaml", CONFIG);
+    let run = dir.run(&["c.yaml", "-f", "env"]).failure(4);
+    assert_eq!(
+        run.stderr,
+        "Error: c.yaml: `list` is an array; pass --env-join-arrays to write it as a comma separated list\n"
+    );
+}
+
+#[test]
+fn reads_env_files_back_as_strings() {
+    let dir = Dir::new();
+    dir.write("c.yaml", CONFIG);
+    dir.run(&["c.yaml", "out.env", "--env-join-arrays"])
+        .success();
+    let run = dir.run(&["out.env", "--stdout", "-f", "json"]).success();
+    assert_eq!(
+        common::json(&run.stdout),
+        json!({
+            "DB_MAIN_HOST": "my host",
+            "DB_MAIN_PORT": "5432",
+            "DB_NOTE": "a # b",
+            "NAME": "x",
+            "LIST": "1,2",
+            "Q": "say \"hi\"",
+        })
+    );
+
+    dir.write("bad.env", "A=1\nnot a variable\n");
+    let run = dir.run(&["bad.env", "-f", "json"]).failure(3);
+    assert_eq!(run.stderr, "Error: bad.env:2: expected `KEY=value`\n");
+}
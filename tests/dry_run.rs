@@ -0,0 +1,68 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn reports_what_it_would_write_without_writing() {
+    let dir = Dir::new();
+    dir.write("a.yaml", "a: 1\n");
+    let run = dir.run(&["a.yaml", "-f", "json", "--dry-run"]).success();
+    assert_eq!(run.stderr, "Would write a.yaml (yaml) to a.json (json)\n");
+    assert!(!dir.exists("a.json"));
+}
+
+#[test]
+fn marks_files_it_would_overwrite() {
+    let dir = Dir::new();
+    dir.write("a.yaml", "a: 1\n");
+    dir.write("a.json", "old");
+    let run = dir
+        .run(&["a.yaml", "-f", "json", "--dry-run", "--force"])
+        .success();
+    assert_eq!(
+        run.stderr,
+        "Would write a.yaml (yaml) to a.json (json), overwriting it\n"
+    );
+    assert_eq!(dir.read("a.json"), "old");
+}
+
+#[test]
+fn skips_removing_and_backing_up() {
+    let dir = Dir::new();
+    dir.write("a.yaml", "a: 1\n");
+    dir.write("a.json", "old");
+    dir.run(&[
+        "a.yaml",
+        "-f",
+        "json",
+        "--dry-run",
+        "--in-place",
+        "--keep-original=false",
+        "--backup",
+    ])
+    .success();
+    assert_eq!(dir.read("a.yaml"), "a: 1\n");
+    assert_eq!(dir.read("a.json"), "old");
+    assert!(!dir.exists("a.json.bak"));
+}
+
+#[test]
+fn fails_when_an_input_wouldnt_convert() {
+    let dir = Dir::new();
+    dir.write("a.yaml", "a: 1\n");
+    dir.write("b.yaml", "b: [\n");
+    let run = dir
+        .run(&["a.yaml", "b.yaml", "-f", "json", "--dry-run", "--jobs", "1"])
+        .failure(3);
+    assert!(run
+        .stderr
+        .starts_with("Would write a.yaml (yaml) to a.json (json)\nb.yaml:2:1: "));
+    assert!(run
+        .stderr
+        .ends_with("Error: Would convert 1 file, skipped 0, 1 error\n"));
+    assert!(!dir.exists("a.json") && !dir.exists("b.json"));
+
+    // Serializing runs too
+    dir.write("n.yaml", "a: null\n");
+    dir.run(&["n.yaml", "-f", "toml", "--dry-run"]).failure(4);
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::Dir;
+use serde_json::json;
+
+const NESTED: &str = "server:\n  port: 80\n  host: a\n  port: 8080\nother: 1\n";
+
+fn converted(args: &[&str]) -> common::Run {
+    let dir = Dir::new();
+    dir.write("d.yaml", NESTED);
+    let mut all = vec!["d.yaml", "--stdout", "-f", "json"];
+    all.extend(args);
+    dir.run(&all)
+}
+
+#[test]
+fn places_a_nested_duplicate_under_error_and_by_default() {
+    for args in [&[][..], &["--duplicate-keys", "error"]] {
+        let run = converted(args).failure(3);
+        assert_eq!(
+            run.stderr,
+            "Error: d.yaml:4:3: duplicate key /server/port\n"
+        );
+        assert_eq!(run.stdout, "");
+    }
+}
+
+#[test]
+fn keeps_the_first_under_first() {
+    let run = converted(&["--duplicate-keys", "first"]).success();
+    assert_eq!(
+        common::json(&run.stdout),
+        json!({"server": {"port": 80, "host": "a"}, "other": 1})
+    );
+}
+
+#[test]
+fn keeps_the_last_under_last() {
+    let run = converted(&["--duplicate-keys", "last"]).success();
+    assert_eq!(
+        common::json(&run.stdout),
+        json!({"server": {"port": 8080, "host": "a"}, "other": 1})
+    );
+}
+
+#[test]
+fn finds_duplicates_in_json() {
+    let dir = Dir::new();
+    dir.write("d.json", r#"{"a":{"b":1,"b":2}}"#);
+    let run = dir.run(&["d.json", "-f", "yaml"]).failure(3);
+    assert!(run.stderr.starts_with("Error: d.json:1:"), "{}", run.stderr);
+    assert!(
+        run.stderr.ends_with(": duplicate key /a/b\n"),
+        "{}",
+        run.stderr
+    );
+    let run = dir
+        .run(&[
+            "d.json",
+            "--stdout",
+            "-f",
+            "yaml",
+            "--duplicate-keys",
+            "last",
+        ])
+        .success();
+    assert_eq!(run.stdout, "a:\n  b: 2\n");
+}
+
+#[test]
+fn reports_toml_duplicates_the_same_way() {
+    let dir = Dir::new();
+    dir.write("d.toml", "a = 1\na = 2\n");
+    let run = dir.run(&["d.toml", "-f", "json"]).failure(3);
+    assert_eq!(run.stderr, "Error: d.toml:2:1: duplicate key /a\n");
+
+    dir.write("t.toml", "[t]\nx = 1\n[t]\ny = 2\n");
+    let run = dir.run(&["t.toml", "-f", "json"]).failure(3);
+    assert_eq!(run.stderr, "Error: t.toml:3:1: duplicate key /t\n");
+}
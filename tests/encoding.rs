@@ -0,0 +1,95 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn utf8_bom_json_converts() {
+    let dir = Dir::new();
+    dir.write("bom.json", b"\xEF\xBB\xBF{\"a\":1}");
+    dir.run(&["bom.json", "-f", "yaml"]).success();
+    assert_eq!(dir.read("bom.yaml"), "a: 1\n");
+}
+
+#[test]
+fn utf8_bom_json_keeps_bom_with_output_bom() {
+    let dir = Dir::new();
+    dir.write("bom.json", b"\xEF\xBB\xBF{\"a\":1}");
+    dir.run(&["bom.json", "out.yaml", "--output-bom"]).success();
+    assert_eq!(dir.bytes("out.yaml"), b"\xEF\xBB\xBFa: 1\n");
+}
+
+/// `{"a": "é"}` as UTF-16 in the byte order `encode` gives, after `bom`
+fn utf16(bom: &[u8], encode: fn(u16) -> [u8; 2]) -> Vec<u8> {
+    let mut bytes = bom.to_vec();
+    bytes.extend("{\"a\": \"é\"}".encode_utf16().flat_map(encode));
+    bytes
+}
+
+#[test]
+fn utf16_json_with_a_bom_converts() {
+    let dir = Dir::new();
+    dir.write("le.json", utf16(b"\xFF\xFE", u16::to_le_bytes));
+    dir.write("be.json", utf16(b"\xFE\xFF", u16::to_be_bytes));
+    for name in ["le.json", "be.json"] {
+        let run = dir.run(&[name, "--stdout", "-f", "yaml"]).success();
+        assert_eq!(run.stdout, "a: é\n", "{name}");
+    }
+}
+
+#[test]
+fn utf16_without_a_bom_needs_input_encoding() {
+    let dir = Dir::new();
+    dir.write("nobom.json", utf16(b"", u16::to_le_bytes));
+    let run = dir.run(&["nobom.json", "-f", "yaml"]).failure(3);
+    assert_eq!(
+        run.stderr,
+        "Error: nobom.json: appears to be UTF-16LE without a byte order mark; re-encode it as UTF-8 or pass --input-encoding utf-16le\n"
+    );
+    let run = dir
+        .run(&[
+            "nobom.json",
+            "--stdout",
+            "-f",
+            "yaml",
+            "--input-encoding",
+            "utf-16le",
+        ])
+        .success();
+    assert_eq!(run.stdout, "a: é\n");
+}
+
+#[test]
+fn latin1_needs_input_encoding() {
+    let dir = Dir::new();
+    dir.write("latin.json", b"{\"a\": \"\xE9\"}");
+    let run = dir.run(&["latin.json", "-f", "yaml"]).failure(3);
+    assert_eq!(
+        run.stderr,
+        "Error: latin.json: isn't valid UTF-8 at byte 7, and may be Latin-1; re-encode it as UTF-8 or pass --input-encoding latin1\n"
+    );
+    let run = dir
+        .run(&[
+            "latin.json",
+            "--stdout",
+            "-f",
+            "yaml",
+            "--input-encoding",
+            "latin1",
+        ])
+        .success();
+    assert_eq!(run.stdout, "a: é\n");
+}
+
+#[test]
+fn refuses_an_unknown_input_encoding() {
+    let dir = Dir::new();
+    dir.write("a.json", "{}");
+    let run = dir
+        .run(&["a.json", "-f", "yaml", "--input-encoding", "bogus"])
+        .failure(2);
+    assert!(
+        run.stderr.contains("`bogus` isn't an encoding"),
+        "{}",
+        run.stderr
+    );
+}
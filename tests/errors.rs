@@ -0,0 +1,81 @@
+mod common;
+
+use common::Dir;
+
+/// The error `scribe` prints converting `name`, holding `content`, to `to`
+fn error(name: &str, content: &str, to: &str) -> String {
+    let dir = Dir::new();
+    dir.write(name, content);
+    let run = dir.run(&[name, "-f", to]).failure(3);
+    run.stderr
+}
+
+#[test]
+fn places_a_json_error() {
+    assert_eq!(
+        error("broken.json", "{\n  \"a\": 1,\n  \"b\" 2\n}", "yaml"),
+        "Error: broken.json:3:7: expected `:`\n"
+    );
+}
+
+#[test]
+fn places_a_json_error_at_the_end_of_a_line_in_column_1() {
+    assert_eq!(
+        error("eof.json", "{\"a\":1,\n", "yaml"),
+        "Error: eof.json:2:1: EOF while parsing a value\n"
+    );
+}
+
+#[test]
+fn places_a_yaml_error() {
+    let stderr = error("broken.yaml", "a: 1\nb: [1, 2\nc: 3\n", "json");
+    assert!(stderr.starts_with("Error: broken.yaml:3:2: "), "{stderr}");
+}
+
+#[test]
+fn places_a_toml_error() {
+    assert_eq!(
+        error("broken.toml", "a = 1\nb = \n", "json"),
+        "Error: broken.toml:2:5: expected a value, found a newline\n"
+    );
+}
+
+#[test]
+fn places_a_toml_duplicate_at_its_second_definition() {
+    assert_eq!(
+        error("d.toml", "a = 1\na = 2\n", "json"),
+        "Error: d.toml:2:1: duplicate key /a\n"
+    );
+    assert_eq!(
+        error("t.toml", "[t]\nx = 1\ny = 2\n  x = 3\n", "json"),
+        "Error: t.toml:4:3: duplicate key /t/x\n"
+    );
+    assert_eq!(
+        error("s.toml", "a = '''\na = 1\n'''\na = 2\n", "json"),
+        "Error: s.toml:4:1: duplicate key /a\n"
+    );
+}
+
+#[test]
+fn places_a_toml_duplicate_in_an_array_of_tables() {
+    assert_eq!(
+        error("a.toml", "[[t]]\nx = 1\n[[t]]\nx = 1\n\"x\" = 2\n", "json"),
+        "Error: a.toml:5:1: duplicate key /t/x\n"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn names_an_input_that_isnt_utf8() {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+    let dir = Dir::new();
+    let name = OsStr::from_bytes(b"caf\xe9.json");
+    std::fs::write(dir.path("").join(name), "{").unwrap();
+    let run = common::run(dir.command().arg(name).args(["-f", "yaml"]), None).failure(3);
+    assert!(
+        run.stderr.starts_with("Error: caf\u{FFFD}.json:"),
+        "{}",
+        run.stderr
+    );
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn success_exits_0() {
+    let dir = Dir::new();
+    dir.write("c.yaml", "a: 1\n");
+    dir.run(&["c.yaml", "c.json"]).success();
+}
+
+#[test]
+fn refusing_to_overwrite_exits_1() {
+    let dir = Dir::new();
+    dir.write("c.yaml", "a: 1\n");
+    dir.write("c.json", "{}");
+    dir.run(&["c.yaml", "c.json"]).failure(1);
+}
+
+#[test]
+fn bad_arguments_exit_2() {
+    let dir = Dir::new();
+    dir.run(&["--no-such-flag"]).failure(2);
+    dir.write("c.yaml", "a: 1\n");
+    dir.run(&["c.yaml", "c.unknown"]).failure(2);
+}
+
+#[test]
+fn parse_errors_exit_3() {
+    let dir = Dir::new();
+    dir.write("c.json", "{\"a\": ");
+    let run = dir.run(&["c.json", "c.yaml"]).failure(3);
+    assert!(run.stderr.contains("c.json:"), "{}", run.stderr);
+}
+
+#[test]
+fn serialize_errors_exit_4() {
+    let dir = Dir::new();
+    dir.write("c.json", "{\"a\": null}");
+    dir.run(&["c.json", "c.toml"]).failure(4);
+}
+
+#[test]
+fn io_errors_exit_5() {
+    let dir = Dir::new();
+    dir.run(&["missing.json", "out.yaml"]).failure(5);
+}
+
+#[test]
+fn schema_violations_exit_6() {
+    let dir = Dir::new();
+    dir.write(
+        "schema.json",
+        r#"{"properties": {"a": {"type": "string"}}}"#,
+    );
+    dir.write("c.json", "{\"a\": 1}");
+    dir.run(&["c.json", "c.yaml", "--schema", "schema.json"])
+        .failure(6);
+}
+
+#[test]
+fn mixed_failures_exit_1() {
+    let dir = Dir::new();
+    dir.write("bad.json", "{");
+    dir.write("null.json", "{\"a\": null}");
+    dir.run(&["bad.json", "null.json", "--to", "toml"])
+        .failure(1);
+}
+
+#[test]
+fn check_exits_1_when_out_of_date() {
+    let dir = Dir::new();
+    dir.write("c.yaml", "a: 1\n");
+    dir.write("c.json", "{}");
+    dir.run(&["c.yaml", "-f", "json", "--check"]).failure(1);
+}
+
+#[test]
+fn check_exits_2_when_output_is_missing() {
+    let dir = Dir::new();
+    dir.write("c.yaml", "a: 1\n");
+    dir.run(&["c.yaml", "-f", "json", "--check"]).failure(2);
+}
+
+#[test]
+fn check_exits_2_when_input_is_broken() {
+    let dir = Dir::new();
+    dir.write("c.yaml", "a: [\n");
+    dir.write("c.json", "{}");
+    dir.run(&["c.yaml", "-f", "json", "--check"]).failure(2);
+}
+
+#[test]
+fn diff_exits_by_outcome() {
+    let dir = Dir::new();
+    dir.write("a.yaml", "a: 1\n");
+    dir.write("b.json", "{\"a\": 1.0}");
+    dir.write("c.json", "{\"a\": 2}");
+    dir.run(&["a.yaml", "--diff", "b.json"]).success();
+    dir.run(&["a.yaml", "--diff", "c.json"]).failure(1);
+    dir.run(&["a.yaml", "--diff", "missing.json"]).failure(2);
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn names_outputs_by_their_source_extension() {
+    let dir = Dir::new();
+    dir.write("a.yml", "a: 1\n");
+    dir.write("b.yaml", "b: 2\n");
+    dir.write("c.json", "{\"c\": 3}");
+    dir.run(&[
+        "a.yml",
+        "b.yaml",
+        "c.json",
+        "--to",
+        "toml",
+        "--ext-map",
+        ".yml=.conf.toml,json=cfg",
+    ])
+    .success();
+    assert_eq!(dir.read("a.conf.toml"), "a = 1\n");
+    assert_eq!(dir.read("c.cfg"), "c = 3\n");
+    // Extensions the map doesn't name get the output format's
+    assert_eq!(dir.read("b.toml"), "b = 2\n");
+    assert!(!dir.exists("a.toml"));
+}
+
+#[test]
+fn maps_extensions_under_out_dir() {
+    let dir = Dir::new();
+    dir.write("a.yml", "a: 1\n");
+    dir.write("c.json", "{\"c\": 3}");
+    dir.run(&[
+        "a.yml",
+        "c.json",
+        "--to",
+        "toml",
+        "--ext-map",
+        ".yml=.conf.toml",
+        "--out-dir",
+        "out",
+    ])
+    .success();
+    assert_eq!(dir.read("out/a.conf.toml"), "a = 1\n");
+    assert_eq!(dir.read("out/c.toml"), "c = 3\n");
+}
+
+#[test]
+fn refuses_a_mapping_without_a_target() {
+    let dir = Dir::new();
+    dir.write("a.yml", "a: 1\n");
+    let run = dir
+        .run(&["a.yml", "--to", "toml", "--ext-map", "yml"])
+        .failure(2);
+    assert!(
+        run.stderr.contains("Expected SOURCE=TARGET, got `yml`"),
+        "{}",
+        run.stderr
+    );
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn converts_each_path_piped_to_files_from_stdin() {
+    let dir = Dir::new();
+    dir.write("my file.yaml", "a: 1\n");
+    dir.write("sub/b.yaml", "b: 2\n");
+    let run = dir
+        .run_with(
+            &["--files-from", "-", "--to", "json"],
+            Some(b"my file.yaml\n\nsub/b.yaml\n"),
+        )
+        .success();
+    assert!(run.stderr.contains("Converted 2 files"), "{}", run.stderr);
+    assert_eq!(
+        common::json(&dir.read("my file.json")),
+        common::json("{\"a\": 1}")
+    );
+    assert_eq!(
+        common::json(&dir.read("sub/b.json")),
+        common::json("{\"b\": 2}")
+    );
+}
+
+#[test]
+fn reads_a_list_with_crlf_line_endings() {
+    let dir = Dir::new();
+    dir.write("a.toml", "a = 1\n");
+    dir.write("b.toml", "b = 2\n");
+    dir.run_with(
+        &["--files-from", "-", "--to", "yaml"],
+        Some(b"a.toml\r\nb.toml\r\n"),
+    )
+    .success();
+    assert_eq!(dir.read("a.yaml"), "a: 1\n");
+    assert_eq!(dir.read("b.yaml"), "b: 2\n");
+}
+
+#[test]
+fn carries_on_past_a_missing_path() {
+    let dir = Dir::new();
+    dir.write("b.yaml", "b: 2\n");
+    let run = dir
+        .run_with(
+            &["--files-from", "-", "--to", "toml"],
+            Some(b"missing.yaml\nb.yaml\n"),
+        )
+        .failure(5);
+    assert!(run.stderr.contains("missing.yaml"), "{}", run.stderr);
+    assert_eq!(dir.read("b.toml"), "b = 2\n");
+}
+
+#[test]
+fn names_a_missing_list() {
+    let dir = Dir::new();
+    let run = dir
+        .run(&["--files-from", "list", "--to", "json"])
+        .failure(5);
+    assert!(
+        run.stderr.contains("Failed to read list: "),
+        "{}",
+        run.stderr
+    );
+}
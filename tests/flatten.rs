@@ -0,0 +1,148 @@
+mod common;
+
+use common::Dir;
+use serde_json::json;
+
+const NESTED: &str = r#"{"server":{"db":{"port":8080,"hosts":["a","b"]},"name":"x"},"debug":true}"#;
+
+#[test]
+fn joins_nested_keys_and_array_indexes() {
+    let dir = Dir::new();
+    dir.write("n.json", NESTED);
+    let run = dir
+        .run(&["n.json", "--stdout", "-f", "json", "--flatten"])
+        .success();
+    assert_eq!(
+        run.stdout,
+        "{\n  \"server.db.port\": 8080,\n  \"server.db.hosts.0\": \"a\",\n  \"server.db.hosts.1\": \"b\",\n  \"server.name\": \"x\",\n  \"debug\": true\n}\n"
+    );
+}
+
+#[test]
+fn round_trips_three_levels_through_flatten_and_unflatten() {
+    let dir = Dir::new();
+    dir.write("n.json", NESTED);
+    dir.run(&["n.json", "flat.yaml", "--flatten"]).success();
+    dir.run(&["flat.yaml", "back.json", "--unflatten"])
+        .success();
+    assert_eq!(common::json(&dir.read("back.json")), common::json(NESTED));
+}
+
+#[test]
+fn joins_with_flatten_sep() {
+    let dir = Dir::new();
+    dir.write("n.json", NESTED);
+    let run = dir
+        .run(&[
+            "n.json",
+            "--stdout",
+            "-f",
+            "yaml",
+            "--flatten",
+            "--flatten-sep",
+            "/",
+        ])
+        .success();
+    assert_eq!(
+        run.stdout,
+        "server/db/port: 8080\nserver/db/hosts/0: a\nserver/db/hosts/1: b\nserver/name: x\ndebug: true\n"
+    );
+    dir.write("flat.yaml", &run.stdout);
+    let run = dir
+        .run(&[
+            "flat.yaml",
+            "--stdout",
+            "-f",
+            "json",
+            "--unflatten",
+            "--flatten-sep",
+            "/",
+        ])
+        .success();
+    assert_eq!(common::json(&run.stdout), common::json(NESTED));
+}
+
+#[test]
+fn keeps_empty_maps_and_arrays() {
+    let dir = Dir::new();
+    dir.write("e.json", r#"{"a":{},"b":[],"c":{"d":[]}}"#);
+    let run = dir
+        .run(&["e.json", "--stdout", "-f", "json", "--flatten", "--compact"])
+        .success();
+    assert_eq!(run.stdout, "{\"a\":{},\"b\":[],\"c.d\":[]}\n");
+}
+
+#[test]
+fn quotes_the_joined_keys_in_toml() {
+    let dir = Dir::new();
+    dir.write("n.json", NESTED);
+    let run = dir
+        .run(&["n.json", "--stdout", "-f", "toml", "--flatten"])
+        .success();
+    assert!(
+        run.stdout.starts_with("\"server.db.port\" = 8080\n"),
+        "{}",
+        run.stdout
+    );
+}
+
+#[test]
+fn refuses_a_key_holding_the_separator() {
+    let dir = Dir::new();
+    dir.write("k.json", r#"{"a.b":{"c":1}}"#);
+    let run = dir.run(&["k.json", "-f", "yaml", "--flatten"]).failure(4);
+    assert!(
+        run.stderr.starts_with(
+            "Error: k.json: The key at `/a.b` holds the separator `.`, so it can't be flattened; pass another with --flatten-sep"
+        ),
+        "{}",
+        run.stderr
+    );
+    let run = dir
+        .run(&[
+            "k.json",
+            "--stdout",
+            "-f",
+            "json",
+            "--flatten",
+            "--flatten-sep",
+            "_",
+        ])
+        .success();
+    assert_eq!(common::json(&run.stdout), json!({"a.b_c": 1}));
+}
+
+#[test]
+fn refuses_a_key_set_both_as_a_map_and_a_value() {
+    let dir = Dir::new();
+    dir.write("c.json", r#"{"a":1,"a.b":2}"#);
+    let run = dir.run(&["c.json", "-f", "yaml", "--unflatten"]).failure(4);
+    assert!(
+        run.stderr
+            .starts_with("Error: c.json: `a.b` needs `a` to be a map, but it's set to 1"),
+        "{}",
+        run.stderr
+    );
+
+    dir.write("d.json", r#"{"a.b":2,"a":1}"#);
+    let run = dir.run(&["d.json", "-f", "yaml", "--unflatten"]).failure(4);
+    assert!(
+        run.stderr
+            .starts_with("Error: d.json: `a` is set both as a map and as a value"),
+        "{}",
+        run.stderr
+    );
+    assert!(!dir.exists("c.yaml") && !dir.exists("d.yaml"));
+}
+
+#[test]
+fn refuses_flatten_with_unflatten() {
+    let dir = Dir::new();
+    dir.write("n.json", NESTED);
+    let run = dir
+        .run(&["n.json", "-f", "yaml", "--flatten", "--unflatten"])
+        .failure(2);
+    assert!(run
+        .stderr
+        .contains("'--flatten' cannot be used with '--unflatten'"));
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn refuses_to_overwrite_a_derived_output_path() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    dir.write("config.toml", "hand = \"edited\"\n");
+    let run = dir.run(&["config.yaml", "-f", "toml"]).failure(1);
+    assert_eq!(
+        run.stderr,
+        "Error: Refusing to overwrite config.toml; pass --force\n"
+    );
+    assert_eq!(dir.read("config.toml"), "hand = \"edited\"\n");
+}
+
+#[test]
+fn refuses_to_overwrite_an_explicit_output_path() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    dir.write("out.toml", "hand = \"edited\"\n");
+    let run = dir.run(&["config.yaml", "out.toml"]).failure(1);
+    assert_eq!(
+        run.stderr,
+        "Error: Refusing to overwrite out.toml; pass --force\n"
+    );
+    assert_eq!(dir.read("out.toml"), "hand = \"edited\"\n");
+}
+
+#[test]
+fn overwrites_under_force() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    dir.write("config.toml", "hand = \"edited\"\n");
+    dir.run(&["config.yaml", "-f", "toml", "--force"]).success();
+    assert_eq!(dir.read("config.toml"), "a = 1\n");
+
+    dir.write("config.yaml", "a: 2\n");
+    dir.run(&["config.yaml", "-f", "toml", "-F"]).success();
+    assert_eq!(dir.read("config.toml"), "a = 2\n");
+}
+
+#[test]
+fn lets_in_place_reformat_a_file_over_itself() {
+    let dir = Dir::new();
+    dir.write("a.json", "{\"a\":1}");
+    dir.run(&["a.json", "-f", "json"]).failure(1);
+    dir.run(&["a.json", "-f", "json", "--in-place"]).success();
+    assert_eq!(dir.read("a.json"), "{\n  \"a\": 1\n}\n");
+}
+
+#[test]
+fn refuses_a_directory_even_under_force() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    dir.write("config.toml/keep", "");
+    for args in [
+        &["config.yaml", "-f", "toml"][..],
+        &["config.yaml", "-f", "toml", "--force"],
+    ] {
+        let run = dir.run(args).failure(1);
+        assert_eq!(run.stderr, "Error: config.toml is a directory\n");
+    }
+    assert!(dir.exists("config.toml/keep"));
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::Dir;
+use scribe::FileFormat;
+
+#[test]
+fn rejects_a_mistyped_output_format_up_front() {
+    let dir = Dir::new();
+    dir.write("c.yaml", "a: 1\n");
+    let run = dir.run(&["c.yaml", "-f", "jsno"]).failure(2);
+    assert!(
+        run.stderr
+            .starts_with("error: invalid value 'jsno' for '--output-format <FORMAT>'"),
+        "{}",
+        run.stderr
+    );
+    assert!(run.stderr.contains("a similar value exists: 'json'"));
+    assert!(!dir.exists("c.txt") && !dir.exists("c.json"));
+}
+
+#[test]
+fn rejects_unknown_as_a_format() {
+    let dir = Dir::new();
+    dir.write("c.yaml", "a: 1\n");
+    dir.run(&["c.yaml", "-f", "unknown"]).failure(2);
+    dir.run(&["c.yaml", "--from", "unknown", "-f", "json"])
+        .failure(2);
+}
+
+#[test]
+fn asks_for_the_format_of_a_file_without_an_extension() {
+    let dir = Dir::new();
+    dir.write("config", "a: 1\n");
+    let run = dir.run(&["config", "-f", "json", "--no-sniff"]).failure(2);
+    assert_eq!(
+        run.stderr,
+        "Error: config: Couldn't infer the format without an extension; pass --input-format (--from)\n"
+    );
+}
+
+#[test]
+fn asks_for_the_format_of_a_file_with_an_unsupported_extension() {
+    let dir = Dir::new();
+    dir.write("c.cfg", "a: 1\n");
+    let run = dir.run(&["c.cfg", "-f", "json", "--no-sniff"]).failure(2);
+    assert_eq!(
+        run.stderr,
+        "Error: c.cfg: Couldn't infer the format from the extension `.cfg`; pass --input-format (--from)\n"
+    );
+}
+
+#[test]
+fn parses_format_names_and_lists_them_on_a_typo() {
+    assert!(matches!("yml".parse(), Ok(FileFormat::Yaml)));
+    assert!(matches!("jsonl".parse(), Ok(FileFormat::Ndjson)));
+    let err = "jsno".parse::<FileFormat>().err().unwrap().to_string();
+    assert!(
+        err.starts_with("Unknown format `jsno`; expected one of json, yaml, toml"),
+        "{err}"
+    );
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn reads_a_txt_file_as_toml_under_from() {
+    let dir = Dir::new();
+    dir.write("config.txt", "a = 1\n[t]\nb = \"x\"\n");
+    dir.run(&["config.txt", "--from", "toml", "-f", "json", "--compact"])
+        .success();
+    assert_eq!(dir.read("config.json"), "{\"a\":1,\"t\":{\"b\":\"x\"}}\n");
+}
+
+#[test]
+fn takes_input_format_over_a_misleading_extension() {
+    let dir = Dir::new();
+    dir.write("config.yaml.bak", "{\"a\": 1}");
+    dir.run(&["config.yaml.bak", "--input-format", "json", "--to", "toml"])
+        .success();
+    assert_eq!(dir.read("config.yaml.toml"), "a = 1\n");
+
+    dir.write("data.json", "a = 1\n");
+    let run = dir
+        .run(&[
+            "data.json",
+            "--stdout",
+            "--from",
+            "toml",
+            "--output-format",
+            "yaml",
+        ])
+        .success();
+    assert_eq!(run.stdout, "a: 1\n");
+}
+
+#[test]
+fn keeps_format_as_a_deprecated_alias() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    let run = dir.run(&["config.yaml", "--format", "toml"]).success();
+    assert_eq!(
+        run.stderr,
+        "warning: --format is deprecated; use --to or --output-format\nWrote config.yaml to config.toml\n"
+    );
+    assert_eq!(dir.read("config.toml"), "a = 1\n");
+}
+
+#[test]
+fn names_the_flag_for_each_side_it_couldnt_detect() {
+    let dir = Dir::new();
+    dir.write("in.cfg", "a: 1\n");
+    let run = dir.run(&["in.cfg", "-f", "json", "--no-sniff"]).failure(2);
+    assert!(run.stderr.contains("pass --input-format (--from)"));
+
+    dir.write("in.yaml", "a: 1\n");
+    let run = dir.run(&["in.yaml", "out.cfg"]).failure(2);
+    assert!(
+        run.stderr.starts_with(
+            "Error: in.yaml: Couldn't infer the format of out.cfg from the extension `.cfg`; give it one of the extensions json, yaml,"
+        ),
+        "{}",
+        run.stderr
+    );
+    assert!(run
+        .stderr
+        .ends_with(", or leave it out and pass --output-format (--to)\n"));
+}
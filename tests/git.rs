@@ -0,0 +1,86 @@
+mod common;
+
+use common::Dir;
+
+/// Run git in `dir`, as a user of its own
+#[cfg(feature = "git")]
+fn git(dir: &Dir, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(dir.path(""))
+        .env("HOME", dir.path(""))
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success(), "git {args:?}");
+}
+
+#[cfg(feature = "git")]
+#[test]
+fn converts_only_the_files_git_sees_changed() {
+    let dir = Dir::new();
+    git(&dir, &["init", "-q"]);
+    dir.write("same.yaml", "a: 1\n");
+    dir.write("edited.yaml", "b: 1\n");
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-qm", "init"]);
+    dir.write("edited.yaml", "b: 2\n");
+    dir.write("conf/new.yaml", "c: 3\n");
+    dir.write("notes.txt", "not config");
+
+    dir.run(&["--git-changed", "--to", "json"]).success();
+    assert_eq!(
+        common::json(&dir.read("edited.json")),
+        common::json("{\"b\": 2}")
+    );
+    assert_eq!(
+        common::json(&dir.read("conf/new.json")),
+        common::json("{\"c\": 3}")
+    );
+    assert!(!dir.exists("same.json"));
+    assert!(!dir.exists("notes.json"));
+}
+
+#[cfg(feature = "git")]
+#[test]
+fn counts_staged_files_as_changed() {
+    let dir = Dir::new();
+    git(&dir, &["init", "-q"]);
+    dir.write("first.toml", "a = 1\n");
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-qm", "init"]);
+    dir.write("staged.toml", "b = 2\n");
+    git(&dir, &["add", "staged.toml"]);
+
+    dir.run(&["--git-changed", "--to", "yaml"]).success();
+    assert_eq!(dir.read("staged.yaml"), "b: 2\n");
+    assert!(!dir.exists("first.yaml"));
+}
+
+#[cfg(feature = "git")]
+#[test]
+fn fails_outside_a_repository() {
+    let dir = Dir::new();
+    dir.write("a.yaml", "a: 1\n");
+    let run = dir.run(&["--git-changed", "--to", "json"]).failure(1);
+    assert!(
+        run.stderr.contains("could not find repository"),
+        "{}",
+        run.stderr
+    );
+    assert!(!dir.exists("a.json"));
+}
+
+#[cfg(not(feature = "git"))]
+#[test]
+fn needs_the_git_feature() {
+    let dir = Dir::new();
+    let run = dir.run(&["--git-changed", "--to", "json"]).failure(1);
+    assert!(
+        run.stderr
+            .contains("requires rscribe to be built with the `git` feature"),
+        "{}",
+        run.stderr
+    );
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use std::io::{Read, Write};
+
+use common::Dir;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+fn gzip(content: &str) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(content.as_bytes()).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn gunzip(content: &[u8]) -> String {
+    let mut decompressed = String::new();
+    GzDecoder::new(content)
+        .read_to_string(&mut decompressed)
+        .unwrap();
+    decompressed
+}
+
+#[test]
+fn converts_gzipped_json_to_gzipped_yaml() {
+    let dir = Dir::new();
+    dir.write("events.json.gz", gzip(r#"{"a":[1,2]}"#));
+    let run = dir.run(&["events.json.gz", "-f", "yaml"]).success();
+    assert_eq!(run.stderr, "Wrote events.json.gz to events.yaml.gz\n");
+    assert_eq!(gunzip(&dir.bytes("events.yaml.gz")), "a:\n- 1\n- 2\n");
+
+    dir.run(&["events.yaml.gz", "back.json"]).success();
+    assert_eq!(
+        dir.read("back.json"),
+        "{\n  \"a\": [\n    1,\n    2\n  ]\n}\n"
+    );
+}
+
+#[test]
+fn goes_by_the_inner_extension_of_the_output() {
+    let dir = Dir::new();
+    dir.write("a.yaml", "a: 1\n");
+    dir.run(&["a.yaml", "out.toml.gz"]).success();
+    assert_eq!(gunzip(&dir.bytes("out.toml.gz")), "a = 1\n");
+}
+
+#[test]
+fn compresses_by_flag_regardless_of_the_extension() {
+    let dir = Dir::new();
+    dir.write("a.yaml", "a: 1\n");
+    dir.run(&["a.yaml", "plain.json", "--compress"]).success();
+    assert_eq!(gunzip(&dir.bytes("plain.json")), "{\n  \"a\": 1\n}\n");
+    dir.run(&["a.yaml", "forced.json.gz", "--no-compress"])
+        .success();
+    assert_eq!(dir.read("forced.json.gz"), "{\n  \"a\": 1\n}\n");
+}
+
+#[test]
+fn sniffs_or_takes_the_format_of_a_bare_gz() {
+    let dir = Dir::new();
+    dir.write("blob.gz", gzip("a: 1\n"));
+    let run = dir
+        .run(&["blob.gz", "--stdout", "-f", "json", "--compact", "-v"])
+        .success();
+    assert_eq!(run.stdout, "{\"a\":1}\n");
+    assert!(run
+        .stderr
+        .starts_with("Read blob.gz (yaml, detected from its content"));
+
+    dir.write("t.gz", gzip("a = 1\n"));
+    let run = dir
+        .run(&[
+            "t.gz",
+            "--stdout",
+            "--from",
+            "toml",
+            "-f",
+            "json",
+            "--compact",
+        ])
+        .success();
+    assert_eq!(run.stdout, "{\"a\":1}\n");
+}
+
+#[test]
+fn names_a_corrupted_gzip_input() {
+    let dir = Dir::new();
+    let mut truncated = gzip(r#"{"a":[1,2,3,4,5,6,7,8,9]}"#);
+    truncated.truncate(12);
+    dir.write("cut.json.gz", truncated);
+    let run = dir.run(&["cut.json.gz", "-f", "yaml"]).failure(5);
+    assert_eq!(
+        run.stderr,
+        "Error: cut.json.gz: invalid gzip data: incomplete deflate stream\n"
+    );
+    for (name, to) in [("plain.yaml.gz", "toml"), ("other.json.gz", "yaml")] {
+        dir.write(name, "not gzip\n");
+        let run = dir.run(&[name, "-f", to]).failure(5);
+        assert_eq!(
+            run.stderr,
+            format!("Error: Failed to read {name}: invalid gzip data: unexpected end of file\n")
+        );
+    }
+    assert!(!dir.exists("cut.yaml.gz") && !dir.exists("plain.toml.gz"));
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use common::Dir;
+use serde_json::json;
+
+const MAIN: &str = r#"variable "region" {
+  default = "eu-west-1"
+}
+
+resource "aws_instance" "web" {
+  ami   = "ami-123"
+  count = 2
+  tags = {
+    Name = "web"
+  }
+}
+
+resource "aws_instance" "db" {
+  ami = "ami-456"
+}
+"#;
+
+#[test]
+fn nests_blocks_under_their_labels() {
+    let dir = Dir::new();
+    dir.write("main.tf", MAIN);
+    let run = dir.run(&["main.tf", "--stdout", "-f", "json"]).success();
+    assert_eq!(
+        common::json(&run.stdout),
+        json!({
+            "variable": {"region": {"default": "eu-west-1"}},
+            "resource": {
+                "aws_instance": {
+                    "web": {"ami": "ami-123", "count": 2, "tags": {"Name": "web"}},
+                    "db": {"ami": "ami-456"},
+                }
+            }
+        })
+    );
+}
+
+#[test]
+fn reads_hcl_by_its_extension_into_yaml() {
+    let dir = Dir::new();
+    dir.write("a.hcl", "name = \"x\"\nports = [80, 443]\n");
+    dir.run(&["a.hcl", "-f", "yaml"]).success();
+    assert_eq!(dir.read("a.yaml"), "name: x\nports:\n- 80\n- 443\n");
+}
+
+#[test]
+fn refuses_hcl_output() {
+    let dir = Dir::new();
+    dir.write("a.json", "{\"a\":1}");
+    let run = dir.run(&["a.json", "-f", "hcl"]).failure(2);
+    assert_eq!(run.stderr, "Error: a.json: HCL output is not supported\n");
+}
+
+#[test]
+fn places_an_hcl_error() {
+    let dir = Dir::new();
+    dir.write("bad.hcl", "a = 1\nb = \n");
+    let run = dir.run(&["bad.hcl", "-f", "json"]).failure(3);
+    assert!(
+        run.stderr.starts_with("Error: bad.hcl:2:"),
+        "{}",
+        run.stderr
+    );
+}
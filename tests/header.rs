@@ -0,0 +1,83 @@
+mod common;
+
+use common::Dir;
+
+/// The timestamp in `line`, after the `at` of the header, if it looks like
+/// `2024-05-01T10:00:00Z`
+fn timestamp(line: &str) -> &str {
+    let (_, at) = line.rsplit_once(" at ").unwrap();
+    assert_eq!(at.len(), 20, "{at}");
+    assert!(at.ends_with('Z') && at.as_bytes()[10] == b'T', "{at}");
+    at
+}
+
+#[test]
+fn starts_yaml_with_a_comment_header() {
+    let dir = Dir::new();
+    dir.write("a.json", "{\"a\": 1}");
+    let run = dir
+        .run(&["a.json", "--stdout", "-f", "yaml", "--emit-header"])
+        .success();
+    let (header, rest) = run.stdout.split_once('\n').unwrap();
+    assert!(header.starts_with("# Generated by rscribe from a.json at "));
+    timestamp(header);
+    assert_eq!(rest, "a: 1\n");
+}
+
+#[test]
+fn starts_toml_with_a_comment_header() {
+    let dir = Dir::new();
+    dir.write("a.json", "{\"a\": 1}");
+    dir.run(&["a.json", "-f", "toml", "--emit-header"])
+        .success();
+    let toml = dir.read("a.toml");
+    assert!(toml.starts_with("# Generated by rscribe from a.json at "));
+    // The header is a comment, so the file reads back the same
+    let run = dir.run(&["a.toml", "--stdout", "-f", "json"]).success();
+    assert_eq!(common::json(&run.stdout), common::json("{\"a\": 1}"));
+}
+
+#[test]
+fn leaves_json_alone_without_header_json_key() {
+    let dir = Dir::new();
+    dir.write("a.yaml", "a: 1\n");
+    let run = dir
+        .run(&["a.yaml", "--stdout", "-f", "json", "--emit-header"])
+        .success();
+    assert_eq!(run.stdout, "{\n  \"a\": 1\n}\n");
+}
+
+#[test]
+fn puts_the_json_header_under_header_json_key() {
+    let dir = Dir::new();
+    dir.write("a.yaml", "a: 1\n");
+    let run = dir
+        .run(&[
+            "a.yaml",
+            "--stdout",
+            "-f",
+            "json",
+            "--emit-header",
+            "--header-json-key",
+        ])
+        .success();
+    let json = common::json(&run.stdout);
+    let header = json["_generated_by"].as_str().unwrap();
+    assert!(header.starts_with("Generated by rscribe from a.yaml at "));
+    timestamp(header);
+    assert_eq!(json["a"], 1);
+    // The header comes first
+    assert!(run.stdout.starts_with("{\n  \"_generated_by\""));
+
+    let run = dir
+        .run(&[
+            "a.yaml",
+            "--stdout",
+            "-f",
+            "json",
+            "--emit-header",
+            "--header-json-key=meta",
+        ])
+        .success();
+    assert!(common::json(&run.stdout)["meta"].is_string());
+}
@@ -0,0 +1,195 @@
+mod common;
+
+use common::Dir;
+
+#[cfg(feature = "http")]
+mod server {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::{TcpListener, TcpStream},
+        thread,
+        time::Duration,
+    };
+
+    /// Serve a few fixed routes on a free local port until the tests end,
+    /// returning the base URL
+    pub fn start() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                thread::spawn(move || respond(stream));
+            }
+        });
+        base
+    }
+
+    fn respond(mut stream: TcpStream) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut request = String::new();
+        reader.read_line(&mut request).unwrap();
+        let path = request.split(' ').nth(1).unwrap_or("/").to_string();
+        // Skip the headers
+        let mut line = String::new();
+        while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+            line.clear();
+        }
+
+        let (status, headers, body) = match path.as_str() {
+            "/config.yaml" => ("200 OK", "", "a: 1\nb: [x]\n"),
+            "/api" => ("200 OK", "Content-Type: application/json\r\n", r#"{"a":1}"#),
+            "/settings" => (
+                "200 OK",
+                "Content-Type: application/toml; charset=utf-8\r\n",
+                "a = 1\n",
+            ),
+            // The extension wins over what the server says
+            "/typed.json" => ("200 OK", "Content-Type: application/yaml\r\n", r#"{"a":2}"#),
+            "/plain" => ("200 OK", "Content-Type: text/plain\r\n", "a = 3\n"),
+            "/moved" => ("302 Found", "Location: /config.yaml\r\n", ""),
+            "/loop" => ("302 Found", "Location: /loop\r\n", ""),
+            "/slow.json" => {
+                thread::sleep(Duration::from_secs(3));
+                ("200 OK", "", "{}")
+            }
+            _ => ("404 Not Found", "", "no such thing"),
+        };
+        let _ = write!(
+            stream,
+            "HTTP/1.1 {status}\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+    }
+}
+
+/// Run `scribe` with `args`, without any proxy from outside in the way
+#[cfg(feature = "http")]
+fn fetch(dir: &Dir, args: &[&str]) -> common::Run {
+    let mut command = dir.command();
+    for proxy in ["http_proxy", "https_proxy", "all_proxy"] {
+        command.env_remove(proxy).env_remove(proxy.to_uppercase());
+    }
+    common::run(command.args(args), None)
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn converts_a_url_by_its_extension() {
+    let base = server::start();
+    let dir = Dir::new();
+    let url = format!("{base}/config.yaml");
+    let run = fetch(&dir, &[&url, "out.json"]).success();
+    assert_eq!(run.stderr, format!("Wrote {url} to out.json\n"));
+    assert_eq!(
+        common::json(&dir.read("out.json")),
+        serde_json::json!({"a": 1, "b": ["x"]})
+    );
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn goes_by_the_content_type_without_an_extension() {
+    let base = server::start();
+    let dir = Dir::new();
+    let api = format!("{base}/api");
+    let run = fetch(&dir, &[&api, "--stdout", "-f", "yaml"]).success();
+    assert_eq!(run.stdout, "a: 1\n");
+    let settings = format!("{base}/settings");
+    let run = fetch(&dir, &[&settings, "--stdout", "-f", "yaml", "--no-sniff"]).success();
+    assert_eq!(run.stdout, "a: 1\n");
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn takes_the_extension_over_the_content_type() {
+    let base = server::start();
+    let dir = Dir::new();
+    let url = format!("{base}/typed.json");
+    let run = fetch(&dir, &[&url, "--stdout", "-f", "yaml"]).success();
+    assert_eq!(run.stdout, "a: 2\n");
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn takes_input_format_for_an_unhelpful_content_type() {
+    let base = server::start();
+    let dir = Dir::new();
+    let url = format!("{base}/plain");
+    fetch(&dir, &[&url, "--stdout", "-f", "json", "--no-sniff"]).failure(2);
+    let run = fetch(
+        &dir,
+        &[
+            &url,
+            "--stdout",
+            "--from",
+            "toml",
+            "-f",
+            "json",
+            "--compact",
+        ],
+    )
+    .success();
+    assert_eq!(run.stdout, "{\"a\":3}\n");
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn follows_redirects_up_to_a_limit() {
+    let base = server::start();
+    let dir = Dir::new();
+    let moved = format!("{base}/moved");
+    let run = fetch(&dir, &[&moved, "--stdout", "-f", "json", "--compact"]).success();
+    assert_eq!(run.stdout, "{\"a\":1,\"b\":[\"x\"]}\n");
+
+    let looping = format!("{base}/loop");
+    let run = fetch(&dir, &[&looping, "--stdout", "-f", "json"]).failure(5);
+    assert_eq!(
+        run.stderr,
+        format!("Error: Failed to fetch {looping}: more than 10 redirects\n")
+    );
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn fails_on_an_error_status() {
+    let base = server::start();
+    let dir = Dir::new();
+    let url = format!("{base}/gone.json");
+    let run = fetch(&dir, &[&url, "--stdout", "-f", "yaml"]).failure(5);
+    assert_eq!(
+        run.stderr,
+        format!("Error: Failed to fetch {url}: the server answered 404\n")
+    );
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn gives_up_after_the_timeout() {
+    let base = server::start();
+    let dir = Dir::new();
+    let url = format!("{base}/slow.json");
+    let run = fetch(
+        &dir,
+        &[&url, "--stdout", "-f", "yaml", "--timeout", "500ms"],
+    )
+    .failure(5);
+    assert_eq!(
+        run.stderr,
+        format!("Error: Failed to fetch {url}: timed out after 500ms; pass a longer --timeout\n")
+    );
+}
+
+#[cfg(not(feature = "http"))]
+#[test]
+fn needs_the_http_feature() {
+    let dir = Dir::new();
+    let run = dir
+        .run(&["http://127.0.0.1:9/config.yaml", "--stdout", "-f", "json"])
+        .failure(5);
+    assert!(
+        run.stderr
+            .contains("reading URLs requires rscribe to be built with the `http` feature"),
+        "{}",
+        run.stderr
+    );
+}
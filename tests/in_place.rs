@@ -0,0 +1,94 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn keeps_the_original_by_default() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    dir.run(&["config.yaml", "-f", "toml", "--in-place"])
+        .success();
+    assert_eq!(dir.read("config.toml"), "a = 1\n");
+    assert_eq!(dir.read("config.yaml"), "a: 1\n");
+}
+
+#[test]
+fn removes_the_original_once_written_under_keep_original_false() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    let run = dir
+        .run(&[
+            "config.yaml",
+            "-f",
+            "toml",
+            "--in-place",
+            "--keep-original=false",
+        ])
+        .success();
+    assert_eq!(
+        run.stderr,
+        "Wrote config.yaml to config.toml\nRemoved config.yaml\n"
+    );
+    assert_eq!(dir.read("config.toml"), "a = 1\n");
+    assert!(!dir.exists("config.yaml"));
+}
+
+#[test]
+fn keeps_the_original_when_the_conversion_fails() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: null\n");
+    dir.run(&[
+        "config.yaml",
+        "-f",
+        "toml",
+        "--in-place",
+        "--keep-original=false",
+    ])
+    .failure(4);
+    assert_eq!(dir.read("config.yaml"), "a: null\n");
+    assert!(!dir.exists("config.toml"));
+}
+
+#[cfg(unix)]
+#[test]
+fn keeps_the_original_when_the_output_directory_is_read_only() {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    let dir = Dir::new();
+    dir.write("ro/config.yaml", "a: 1\n");
+    let ro = dir.path("ro");
+    fs::set_permissions(&ro, fs::Permissions::from_mode(0o555)).unwrap();
+    // Root writes through the permissions, so there's no failure to test
+    if fs::write(ro.join("probe"), "").is_ok() {
+        fs::set_permissions(&ro, fs::Permissions::from_mode(0o755)).unwrap();
+        eprintln!("skipped: the directory is writable despite its permissions");
+        return;
+    }
+
+    let run = dir.run(&[
+        "ro/config.yaml",
+        "-f",
+        "toml",
+        "--in-place",
+        "--keep-original=false",
+    ]);
+    fs::set_permissions(&ro, fs::Permissions::from_mode(0o755)).unwrap();
+    run.failure(5);
+    assert_eq!(dir.read("ro/config.yaml"), "a: 1\n");
+    assert_eq!(fs::read_dir(&ro).unwrap().count(), 1);
+}
+
+#[test]
+fn leaves_a_reformatted_input_in_place() {
+    let dir = Dir::new();
+    dir.write("a.json", "{\"a\":1}");
+    dir.run(&[
+        "a.json",
+        "-f",
+        "json",
+        "--in-place",
+        "--keep-original=false",
+    ])
+    .success();
+    assert_eq!(dir.read("a.json"), "{\n  \"a\": 1\n}\n");
+}
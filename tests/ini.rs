@@ -0,0 +1,60 @@
+mod common;
+
+use common::Dir;
+use serde_json::json;
+
+const INI: &str = "top = 1\n; a comment\n[db]\nhost = \"x\"\nport = 42\nssl = true\n";
+
+#[test]
+fn reads_sections_as_maps_of_strings() {
+    let dir = Dir::new();
+    dir.write("a.ini", INI);
+    let run = dir.run(&["a.ini", "--stdout", "-f", "json"]).success();
+    assert_eq!(
+        common::json(&run.stdout),
+        json!({"top": "1", "db": {"host": "x", "port": "42", "ssl": "true"}})
+    );
+}
+
+#[test]
+fn infers_numbers_and_booleans_under_infer_types() {
+    let dir = Dir::new();
+    dir.write("a.ini", INI);
+    let run = dir
+        .run(&["a.ini", "--stdout", "-f", "json", "--infer-types"])
+        .success();
+    assert_eq!(
+        common::json(&run.stdout),
+        json!({"top": 1, "db": {"host": "x", "port": 42, "ssl": true}})
+    );
+}
+
+#[test]
+fn writes_root_keys_before_sections() {
+    let dir = Dir::new();
+    dir.write("a.json", r#"{"db":{"port":42,"ok":true},"top":1}"#);
+    dir.run(&["a.json", "-f", "ini"]).success();
+    assert_eq!(dir.read("a.ini"), "top = 1\n\n[db]\nport = 42\nok = true\n");
+}
+
+#[test]
+fn names_the_path_too_deep_for_ini() {
+    let dir = Dir::new();
+    dir.write("d.json", r#"{"a":{"b":{"c":1}}}"#);
+    let run = dir.run(&["d.json", "-f", "ini"]).failure(4);
+    assert_eq!(
+        run.stderr,
+        "Error: d.json: `a.b` is nested too deeply for INI, which only has sections of plain values\n"
+    );
+}
+
+#[test]
+fn places_a_line_that_isnt_a_key_or_section() {
+    let dir = Dir::new();
+    dir.write("bad.ini", "[a]\nnonsense\n");
+    let run = dir.run(&["bad.ini", "-f", "json"]).failure(3);
+    assert_eq!(
+        run.stderr,
+        "Error: bad.ini:2: expected `key = value` or a `[section]` header\n"
+    );
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use common::Dir;
+
+const CONFIG: &str = "// frontend config
+{
+  name: 'app', /* inline */
+  port: 8080,
+  hex: 0x10,
+  list: [1, 2, 3,],
+  nested: { ok: true, },
+}
+";
+
+#[test]
+fn converts_json5_with_comments_and_trailing_commas_to_toml() {
+    let dir = Dir::new();
+    dir.write("a.json5", CONFIG);
+    dir.run(&["a.json5", "-f", "toml"]).success();
+    assert_eq!(
+        dir.read("a.toml"),
+        "name = \"app\"\nport = 8080\nhex = 16\nlist = [1, 2, 3]\n\n[nested]\nok = true\n"
+    );
+}
+
+#[test]
+fn reads_json5_given_by_from() {
+    let dir = Dir::new();
+    dir.write("config", CONFIG);
+    let run = dir
+        .run(&[
+            "config",
+            "--from",
+            "json5",
+            "--stdout",
+            "-f",
+            "json",
+            "--compact",
+        ])
+        .success();
+    assert_eq!(
+        run.stdout,
+        "{\"name\":\"app\",\"port\":8080,\"hex\":16,\"list\":[1,2,3],\"nested\":{\"ok\":true}}\n"
+    );
+}
+
+#[test]
+fn refuses_json5_output() {
+    let dir = Dir::new();
+    dir.write("a.json", "{\"a\": 1}");
+    let run = dir.run(&["a.json", "-f", "json5"]).failure(2);
+    assert_eq!(run.stderr, "Error: a.json: JSON5 output is not supported\n");
+}
+
+#[test]
+fn places_a_json5_error() {
+    let dir = Dir::new();
+    dir.write("b.json5", "{\n  a: ,\n}");
+    let run = dir.run(&["b.json5", "-f", "json"]).failure(3);
+    assert!(
+        run.stderr.starts_with("Error: b.json5:2:"),
+        "{}",
+        run.stderr
+    );
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use common::Dir;
+
+const ORDERED: &str = include_str!("fixtures/ordered.toml");
+
+fn converted(to: &str) -> String {
+    let dir = Dir::new();
+    dir.write("ordered.toml", ORDERED);
+    dir.run(&["ordered.toml", "--stdout", "-f", to])
+        .success()
+        .stdout
+}
+
+#[test]
+fn keeps_toml_key_order_in_json() {
+    assert_eq!(converted("json"), include_str!("fixtures/ordered.json"));
+}
+
+#[test]
+fn keeps_toml_key_order_in_yaml() {
+    assert_eq!(converted("yaml"), include_str!("fixtures/ordered.yaml"));
+}
+
+#[test]
+fn keeps_yaml_key_order_in_json() {
+    let dir = Dir::new();
+    dir.write("a.yaml", "z: 1\nm: {y: 2, b: 1}\na: 0\n");
+    let run = dir
+        .run(&["a.yaml", "--stdout", "-f", "json", "--compact"])
+        .success();
+    assert_eq!(run.stdout, "{\"z\":1,\"m\":{\"y\":2,\"b\":1},\"a\":0}\n");
+}
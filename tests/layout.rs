@@ -0,0 +1,86 @@
+mod common;
+
+use common::Dir;
+
+const FIXTURE: &str = "a: 1\nb: [x, 2]\n";
+
+fn json(args: &[&str]) -> String {
+    let dir = Dir::new();
+    dir.write("a.yaml", FIXTURE);
+    let mut all = vec!["a.yaml", "--stdout", "-f", "json"];
+    all.extend(args);
+    dir.run(&all).success().stdout
+}
+
+#[test]
+fn writes_json_on_one_line_under_compact() {
+    assert_eq!(json(&["--compact"]), "{\"a\":1,\"b\":[\"x\",2]}\n");
+}
+
+#[test]
+fn indents_json_under_pretty_and_by_default() {
+    let pretty = "{\n  \"a\": 1,\n  \"b\": [\n    \"x\",\n    2\n  ]\n}\n";
+    assert_eq!(json(&["--pretty"]), pretty);
+    assert_eq!(json(&[]), pretty);
+
+    let dir = Dir::new();
+    dir.write("a.yaml", FIXTURE);
+    dir.run(&["a.yaml", "-f", "json"]).success();
+    assert_eq!(dir.read("a.json"), pretty);
+}
+
+#[test]
+fn refuses_compact_for_a_format_without_it() {
+    let dir = Dir::new();
+    dir.write("a.yaml", FIXTURE);
+    let run = dir.run(&["a.yaml", "-f", "toml", "--compact"]).failure(2);
+    assert!(
+        run.stderr
+            .contains("--compact and --indent only apply to JSON output, not toml"),
+        "{}",
+        run.stderr
+    );
+    assert!(!dir.exists("a.toml"));
+}
+
+#[test]
+fn refuses_pretty_with_compact() {
+    let dir = Dir::new();
+    dir.write("a.yaml", FIXTURE);
+    let run = dir
+        .run(&["a.yaml", "-f", "json", "--pretty", "--compact"])
+        .failure(2);
+    assert!(run
+        .stderr
+        .contains("'--pretty' cannot be used with '--compact'"));
+}
+
+#[test]
+fn indents_json_by_indent() {
+    let dir = Dir::new();
+    dir.write("a.json", r#"{"a":{"b":{"c":1}}}"#);
+    let indented = |n: &str| {
+        dir.run(&["a.json", "--stdout", "-f", "json", "--indent", n])
+            .success()
+            .stdout
+    };
+    assert_eq!(
+        indented("2"),
+        "{\n  \"a\": {\n    \"b\": {\n      \"c\": 1\n    }\n  }\n}\n"
+    );
+    assert_eq!(
+        indented("4"),
+        "{\n    \"a\": {\n        \"b\": {\n            \"c\": 1\n        }\n    }\n}\n"
+    );
+    assert_eq!(indented("0"), "{\"a\":{\"b\":{\"c\":1}}}\n");
+}
+
+#[test]
+fn refuses_an_indent_past_16() {
+    let dir = Dir::new();
+    dir.write("a.json", "{}");
+    let run = dir
+        .run(&["a.json", "--stdout", "-f", "json", "--indent", "17"])
+        .failure(2);
+    assert!(run.stderr.contains("17 is not in 0..=16"), "{}", run.stderr);
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::Dir;
+use serde_json::json;
+
+/// A TOML base, a YAML override and a JSON override on top
+fn layers() -> Dir {
+    let dir = Dir::new();
+    dir.write(
+        "base.toml",
+        "[db]\nhost = \"localhost\"\nport = 5432\nopts = { ssl = false, pool = 5 }\n\n[app]\nlist = [1, 2]\nname = \"base\"\n",
+    );
+    dir.write(
+        "prod.yaml",
+        "db:\n  host: prod-db\n  opts: {ssl: true}\napp:\n  list: [3]\n",
+    );
+    dir.write(
+        "top.json",
+        r#"{"app":{"name":"final","list":[4]},"extra":{"k":1}}"#,
+    );
+    dir
+}
+
+#[test]
+fn merges_three_layers_with_later_ones_winning() {
+    let dir = layers();
+    let run = dir
+        .run(&["base.toml", "prod.yaml", "top.json", "out.json", "--merge"])
+        .success();
+    assert_eq!(
+        run.stderr,
+        "Wrote base.toml + prod.yaml + top.json to out.json\n"
+    );
+    assert_eq!(
+        common::json(&dir.read("out.json")),
+        json!({
+            "db": {"host": "prod-db", "port": 5432, "opts": {"ssl": true, "pool": 5}},
+            "app": {"list": [4], "name": "final"},
+            "extra": {"k": 1},
+        })
+    );
+}
+
+#[test]
+fn appends_arrays_under_append_arrays() {
+    let dir = layers();
+    dir.run(&[
+        "base.toml",
+        "prod.yaml",
+        "top.json",
+        "out.json",
+        "--merge",
+        "--append-arrays",
+    ])
+    .success();
+    assert_eq!(
+        common::json(&dir.read("out.json"))["app"]["list"],
+        json!([1, 2, 3, 4])
+    );
+}
+
+#[test]
+fn reports_a_map_replaced_by_a_scalar_under_verbose() {
+    let dir = layers();
+    dir.write("flat.json", r#"{"db": 5}"#);
+    let run = dir
+        .run(&["base.toml", "flat.json", "out.json", "--merge", "-v"])
+        .success();
+    assert!(
+        run.stderr
+            .contains("flat.json: /db: an object replaced by a number\n"),
+        "{}",
+        run.stderr
+    );
+    assert_eq!(common::json(&dir.read("out.json"))["db"], json!(5));
+}
+
+#[test]
+fn writes_the_merge_in_the_format_of_the_output() {
+    let dir = layers();
+    dir.run(&["base.toml", "prod.yaml", "out.yaml", "--merge"])
+        .success();
+    assert!(dir
+        .read("out.yaml")
+        .starts_with("db:\n  host: prod-db\n  port: 5432\n"));
+}
+
+#[test]
+fn needs_an_output_after_the_inputs() {
+    let dir = layers();
+    let run = dir.run(&["base.toml", "--merge"]).failure(2);
+    assert!(run
+        .stderr
+        .contains("--merge needs inputs followed by the OUTPUT path"));
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn names_the_missing_directory_without_mkdir() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    let run = dir
+        .run(&["config.yaml", "build/generated/config.json"])
+        .failure(5);
+    assert_eq!(
+        run.stderr,
+        "Error: Failed to write build/generated/config.json: the directory build/generated doesn't exist; pass --mkdir to create it\n"
+    );
+    assert!(!dir.exists("build"));
+}
+
+#[test]
+fn creates_missing_directories_under_mkdir() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    dir.run(&["config.yaml", "build/generated/config.json", "--mkdir"])
+        .success();
+    assert_eq!(
+        dir.read("build/generated/config.json"),
+        "{\n  \"a\": 1\n}\n"
+    );
+    dir.run(&["config.yaml", "build/toml/config.toml", "-p"])
+        .success();
+    assert_eq!(dir.read("build/toml/config.toml"), "a = 1\n");
+}
+
+#[test]
+fn creates_the_output_directory_of_a_merge_under_mkdir() {
+    let dir = Dir::new();
+    dir.write("a.yaml", "a: 1\n");
+    dir.write("b.yaml", "b: 2\n");
+    dir.run(&["--merge", "a.yaml", "b.yaml", "out/merged.json"])
+        .failure(5);
+    dir.run(&["--merge", "a.yaml", "b.yaml", "out/merged.json", "-p"])
+        .success();
+    assert_eq!(
+        common::json(&dir.read("out/merged.json")),
+        serde_json::json!({"a": 1, "b": 2})
+    );
+}
+
+#[test]
+fn mirrors_directories_below_out_dir() {
+    let dir = Dir::new();
+    dir.write("src/a/b/d.yaml", "x: 1\n");
+    dir.write("src/e.yaml", "y: 1\n");
+    dir.run(&[
+        "--recursive",
+        "src",
+        "--to",
+        "json",
+        "--out-dir",
+        "out/deep",
+    ])
+    .success();
+    assert_eq!(dir.read("out/deep/a/b/d.json"), "{\n  \"x\": 1\n}\n");
+    assert_eq!(dir.read("out/deep/e.json"), "{\n  \"y\": 1\n}\n");
+}
+
+#[test]
+fn names_a_parent_that_isnt_a_directory() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    dir.write("file", "");
+    for output in ["file/config.json", "file/config.toml"] {
+        let run = dir.run(&["config.yaml", output]).failure(5);
+        assert_eq!(
+            run.stderr,
+            format!("Error: Failed to write {output}: file isn't a directory\n")
+        );
+    }
+    let run = dir
+        .run(&["config.yaml", "file/sub/config.json", "--mkdir"])
+        .failure(5);
+    assert!(
+        run.stderr.starts_with("Error: Failed to create file/sub: "),
+        "{}",
+        run.stderr
+    );
+}
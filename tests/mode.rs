@@ -0,0 +1,97 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn refuses_a_mode_that_isnt_octal() {
+    let dir = Dir::new();
+    dir.write("a.yaml", "a: 1\n");
+    for mode in ["rw", "9", "17777"] {
+        let run = dir
+            .run(&["a.yaml", "-f", "json", "--mode", mode])
+            .failure(2);
+        assert!(
+            run.stderr.contains(&format!(
+                "`{mode}` isn't a mode; give up to four octal digits"
+            )),
+            "{}",
+            run.stderr
+        );
+    }
+    assert!(!dir.exists("a.json"));
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::{
+        fs,
+        os::unix::fs::{MetadataExt, PermissionsExt},
+    };
+
+    use super::common::Dir;
+
+    fn mode(dir: &Dir, name: &str) -> u32 {
+        fs::metadata(dir.path(name)).unwrap().permissions().mode() & 0o7777
+    }
+
+    #[test]
+    fn keeps_a_0600_destination_at_0600() {
+        let dir = Dir::new();
+        dir.write("secrets.yaml", "token: abc\n");
+        dir.write("secrets.json", "{}");
+        fs::set_permissions(dir.path("secrets.json"), fs::Permissions::from_mode(0o600)).unwrap();
+        dir.run(&["secrets.yaml", "-f", "json", "--force"])
+            .success();
+        assert_eq!(dir.read("secrets.json"), "{\n  \"token\": \"abc\"\n}\n");
+        assert_eq!(mode(&dir, "secrets.json"), 0o600);
+    }
+
+    #[test]
+    fn keeps_the_mode_of_a_file_converted_in_place() {
+        let dir = Dir::new();
+        dir.write("c.json", r#"{"a":1}"#);
+        fs::set_permissions(dir.path("c.json"), fs::Permissions::from_mode(0o600)).unwrap();
+        dir.run(&["c.json", "-f", "json", "--in-place"]).success();
+        assert_eq!(dir.read("c.json"), "{\n  \"a\": 1\n}\n");
+        assert_eq!(mode(&dir, "c.json"), 0o600);
+    }
+
+    #[test]
+    fn sets_the_mode_of_a_new_file() {
+        let dir = Dir::new();
+        dir.write("a.yaml", "a: 1\n");
+        dir.run(&["a.yaml", "-f", "json", "--mode", "600"])
+            .success();
+        assert_eq!(mode(&dir, "a.json"), 0o600);
+        dir.run(&["a.yaml", "-f", "toml", "--mode", "0o640"])
+            .success();
+        assert_eq!(mode(&dir, "a.toml"), 0o640);
+    }
+
+    #[test]
+    fn sets_the_mode_over_that_of_the_file_replaced() {
+        let dir = Dir::new();
+        dir.write("a.yaml", "a: 1\n");
+        dir.write("a.json", "{}");
+        fs::set_permissions(dir.path("a.json"), fs::Permissions::from_mode(0o644)).unwrap();
+        dir.run(&["a.yaml", "-f", "json", "--force", "--mode", "0600"])
+            .success();
+        assert_eq!(mode(&dir, "a.json"), 0o600);
+    }
+
+    #[test]
+    fn keeps_the_owner_of_the_file_replaced_where_allowed() {
+        let dir = Dir::new();
+        dir.write("a.yaml", "a: 1\n");
+        dir.write("a.json", "{}");
+        // Only root can give a file away; elsewhere the owner can't be kept
+        // and neither is there one to check
+        if std::os::unix::fs::chown(dir.path("a.json"), Some(65534), Some(65534)).is_err() {
+            return;
+        }
+        dir.run(&["a.yaml", "-f", "json", "--force"]).success();
+        let metadata = fs::metadata(dir.path("a.json")).unwrap();
+        assert_eq!((metadata.uid(), metadata.gid()), (65534, 65534));
+        assert_eq!(dir.read("a.json"), "{\n  \"a\": 1\n}\n");
+    }
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::Dir;
+
+const MANIFEST: &str = "\
+kind: Namespace
+name: a
+---
+kind: Service
+name: b
+---
+kind: Deployment
+name: c
+";
+
+#[test]
+fn reads_a_yaml_stream_as_an_array() {
+    let dir = Dir::new();
+    dir.write("m.yaml", MANIFEST);
+    dir.run(&["m.yaml", "m.json", "--compact"]).success();
+    assert_eq!(
+        dir.read("m.json"),
+        "[{\"kind\":\"Namespace\",\"name\":\"a\"},{\"kind\":\"Service\",\"name\":\"b\"},{\"kind\":\"Deployment\",\"name\":\"c\"}]\n"
+    );
+    dir.run(&["m.yaml", "-f", "toml", "--toml-wrap", "docs"])
+        .success();
+    assert!(dir
+        .read("m.toml")
+        .starts_with("[[docs]]\nkind = \"Namespace\"\n"));
+}
+
+#[test]
+fn reads_a_single_document_as_itself() {
+    let dir = Dir::new();
+    dir.write("one.yaml", "---\na: 1\n");
+    let run = dir
+        .run(&["one.yaml", "--stdout", "-f", "json", "--compact"])
+        .success();
+    assert_eq!(run.stdout, "{\"a\":1}\n");
+}
+
+#[test]
+fn splits_a_yaml_stream_into_numbered_files() {
+    let dir = Dir::new();
+    dir.write("m.yaml", MANIFEST);
+    dir.run(&["m.yaml", "out.json", "--split", "--compact"])
+        .success();
+    assert_eq!(
+        dir.read("out-0.json"),
+        "{\"kind\":\"Namespace\",\"name\":\"a\"}\n"
+    );
+    assert_eq!(
+        dir.read("out-1.json"),
+        "{\"kind\":\"Service\",\"name\":\"b\"}\n"
+    );
+    assert_eq!(
+        dir.read("out-2.json"),
+        "{\"kind\":\"Deployment\",\"name\":\"c\"}\n"
+    );
+    assert!(!dir.exists("out.json") && !dir.exists("out-3.json"));
+}
+
+#[test]
+fn refuses_to_split_a_single_map() {
+    let dir = Dir::new();
+    dir.write("o.json", "{\"a\":1}");
+    let run = dir.run(&["o.json", "o.yaml", "--split"]).failure(1);
+    assert!(run
+        .stderr
+        .contains("--split needs a multi-document YAML stream or an array at the root"));
+}
+
+#[test]
+fn writes_an_array_as_a_yaml_stream_under_multi_doc() {
+    let dir = Dir::new();
+    dir.write("m.yaml", MANIFEST);
+    dir.run(&["m.yaml", "m.json"]).success();
+    dir.run(&["m.json", "back.yaml", "--multi-doc"]).success();
+    assert_eq!(dir.read("back.yaml"), MANIFEST);
+
+    let run = dir.run(&["m.json", "m.toml", "--multi-doc"]).failure(2);
+    assert!(run
+        .stderr
+        .contains("--multi-doc only applies to YAML and BSON output, not toml"));
+}
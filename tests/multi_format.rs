@@ -0,0 +1,58 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn reads_once_and_writes_each_format() {
+    let dir = Dir::new();
+    dir.write("c.yaml", "a: 1\n");
+    let run = dir
+        .run(&["c.yaml", "-f", "json", "-f", "toml", "-f", "json", "-v"])
+        .success();
+    assert_eq!(
+        run.stderr,
+        "Read c.yaml (yaml, 5 bytes)\nWrote c.yaml to c.json (json, 13 bytes)\nWrote c.yaml to c.toml (toml, 6 bytes)\n"
+    );
+    assert_eq!(dir.read("c.json"), "{\n  \"a\": 1\n}\n");
+    assert_eq!(dir.read("c.toml"), "a = 1\n");
+}
+
+#[test]
+fn skips_the_input_format() {
+    let dir = Dir::new();
+    dir.write("c.yaml", "a:   1\n");
+    let run = dir.run(&["c.yaml", "-f", "yaml", "-f", "json"]).success();
+    assert_eq!(
+        run.stderr,
+        "Skipped c.yaml to yaml, its own format\nWrote c.yaml to c.json\n"
+    );
+    assert_eq!(dir.read("c.yaml"), "a:   1\n");
+}
+
+#[test]
+fn writes_the_other_formats_past_a_failing_one() {
+    let dir = Dir::new();
+    dir.write("n.yaml", "a: null\n");
+    let run = dir.run(&["n.yaml", "-f", "toml", "-f", "json"]).failure(4);
+    assert_eq!(
+        run.stderr,
+        "Wrote n.yaml to n.json\nn.yaml: null at `a`, which TOML can't represent; pass --null-policy omit or string\nError: n.yaml: 1 of 2 outputs failed\n"
+    );
+    assert_eq!(dir.read("n.json"), "{\n  \"a\": null\n}\n");
+    assert!(!dir.exists("n.toml"));
+}
+
+#[test]
+fn refuses_an_output_path_with_several_formats() {
+    let dir = Dir::new();
+    dir.write("c.yaml", "a: 1\n");
+    let run = dir
+        .run(&["c.yaml", "out.json", "--to", "json", "--to", "toml"])
+        .failure(2);
+    assert!(run
+        .stderr
+        .contains("An OUTPUT path can't be given with several --to formats"));
+    assert!(!dir.exists("out.json"));
+    assert!(!dir.exists("c.json"));
+    assert!(!dir.exists("c.toml"));
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn reads_json_lines_as_an_array_skipping_blank_lines() {
+    let dir = Dir::new();
+    dir.write("d.jsonl", "{\"a\":1}\n\n  \n{\"a\":[2]}\n");
+    dir.run(&["d.jsonl", "d.json", "--compact"]).success();
+    assert_eq!(dir.read("d.json"), "[{\"a\":1},{\"a\":[2]}]\n");
+}
+
+#[test]
+fn writes_an_array_as_one_line_per_item() {
+    let dir = Dir::new();
+    dir.write("l.yaml", "- a: 1\n- b: [2]\n- 3\n");
+    dir.run(&["l.yaml", "-f", "ndjson"]).success();
+    assert_eq!(dir.read("l.ndjson"), "{\"a\":1}\n{\"b\":[2]}\n3\n");
+}
+
+#[test]
+fn names_the_line_that_fails_to_parse() {
+    let dir = Dir::new();
+    dir.write("bad.ndjson", "{\"a\":1}\n\n{\"a\":\n");
+    let run = dir.run(&["bad.ndjson", "-f", "json"]).failure(3);
+    assert_eq!(
+        run.stderr,
+        "Error: bad.ndjson:3:5: EOF while parsing a value\n"
+    );
+    assert!(!dir.exists("bad.json"));
+}
+
+#[test]
+fn refuses_ndjson_output_without_an_array() {
+    let dir = Dir::new();
+    dir.write("o.json", "{\"a\":1}");
+    let run = dir.run(&["o.json", "-f", "ndjson"]).failure(4);
+    assert_eq!(
+        run.stderr,
+        "Error: o.json: NDJSON output needs an array at the root, with one line written per item\n"
+    );
+    assert!(!dir.exists("o.ndjson"));
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use common::Dir;
+
+/// Lines kept in strings, which JSON escapes and YAML writes as block scalars
+const FIXTURE: &str = r#"{"s":"line1\nline2","b":"x\ny\n"}"#;
+
+fn yaml(newline: &str) -> Vec<u8> {
+    let dir = Dir::new();
+    dir.write("a.json", FIXTURE);
+    dir.run(&["a.json", "out.yaml", "--newline", newline])
+        .success();
+    dir.bytes("out.yaml")
+}
+
+const LF: &[u8] = b"s: |-\n  line1\n  line2\nb: |\n  x\n  y\n";
+const CRLF: &[u8] = b"s: |-\r\n  line1\r\n  line2\r\nb: |\r\n  x\r\n  y\r\n";
+
+#[test]
+fn writes_lf_by_default() {
+    let dir = Dir::new();
+    dir.write("a.json", FIXTURE);
+    dir.run(&["a.json", "out.yaml"]).success();
+    assert_eq!(dir.bytes("out.yaml"), LF);
+    assert_eq!(yaml("lf"), LF);
+}
+
+#[test]
+fn writes_crlf_under_newline_crlf() {
+    assert_eq!(yaml("crlf"), CRLF);
+}
+
+#[test]
+fn writes_the_platform_line_ending_under_newline_native() {
+    let native = if cfg!(windows) { CRLF } else { LF };
+    assert_eq!(yaml("native"), native);
+}
+
+#[test]
+fn leaves_escaped_line_breaks_alone() {
+    let dir = Dir::new();
+    dir.write("a.json", FIXTURE);
+    let run = dir
+        .run(&[
+            "a.json",
+            "--stdout",
+            "-f",
+            "json",
+            "--compact",
+            "--newline",
+            "crlf",
+        ])
+        .success();
+    assert_eq!(run.bytes, format!("{FIXTURE}\r\n").into_bytes());
+}
+
+#[test]
+fn reads_block_scalars_written_with_crlf_back() {
+    let dir = Dir::new();
+    dir.write("a.json", FIXTURE);
+    dir.run(&["a.json", "out.yaml", "--newline", "crlf"])
+        .success();
+    let run = dir
+        .run(&["out.yaml", "--stdout", "-f", "json", "--compact"])
+        .success();
+    assert_eq!(run.stdout, format!("{FIXTURE}\n"));
+}
+
+#[test]
+fn ends_output_with_a_line_break() {
+    let dir = Dir::new();
+    dir.write("a.json", r#"{"a":1}"#);
+    dir.write("t.hbs", "a={{a}}");
+    for (newline, expected) in [("lf", &b"a=1\n"[..]), ("crlf", b"a=1\r\n")] {
+        let run = dir
+            .run(&[
+                "a.json",
+                "--stdout",
+                "--template",
+                "t.hbs",
+                "--newline",
+                newline,
+            ])
+            .success();
+        assert_eq!(run.bytes, expected, "{newline}");
+    }
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn skips_a_same_format_conversion_under_exit_zero_on_noop() {
+    let dir = Dir::new();
+    dir.write("a.json", "{\"a\":   1}");
+    dir.run(&["a.json", "-f", "json", "--exit-zero-on-noop"])
+        .success();
+    assert_eq!(dir.read("a.json"), "{\"a\":   1}");
+    let entries = std::fs::read_dir(dir.path("")).unwrap().count();
+    assert_eq!(entries, 1, "wrote something besides a.json");
+}
+
+#[test]
+fn refuses_a_same_format_conversion_without_the_flag() {
+    let dir = Dir::new();
+    dir.write("a.json", "{\"a\":   1}");
+    let run = dir.run(&["a.json", "-f", "json"]).failure(1);
+    assert!(run
+        .stderr
+        .contains("Refusing to overwrite the input file a.json"));
+    assert_eq!(dir.read("a.json"), "{\"a\":   1}");
+}
+
+#[test]
+fn still_converts_to_another_format_under_exit_zero_on_noop() {
+    let dir = Dir::new();
+    dir.write("a.json", "{\"a\": 1}");
+    dir.run(&["a.json", "-f", "yaml", "--exit-zero-on-noop"])
+        .success();
+    assert_eq!(dir.read("a.yaml"), "a: 1\n");
+}
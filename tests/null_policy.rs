@@ -0,0 +1,62 @@
+mod common;
+
+use common::Dir;
+
+const NULLS: &str = r#"{"top":null,"server":{"tls":{"cert":null,"key":"k"}},"workers":[{"id":1,"note":null}],"list":[1,null,2]}"#;
+
+fn to_toml(args: &[&str]) -> common::Run {
+    let dir = Dir::new();
+    dir.write("n.json", NULLS);
+    let mut all = vec!["n.json", "--stdout", "-f", "toml"];
+    all.extend(args);
+    dir.run(&all)
+}
+
+#[test]
+fn fails_naming_the_null_by_default() {
+    for args in [&[][..], &["--null-policy", "error"]] {
+        let run = to_toml(args).failure(4);
+        assert_eq!(
+            run.stderr,
+            "Error: n.json: null at `top`, which TOML can't represent; pass --null-policy omit or string\n"
+        );
+    }
+
+    let dir = Dir::new();
+    dir.write("deep.json", r#"{"server":{"tls":{"cert":null}}}"#);
+    let run = dir.run(&["deep.json", "-f", "toml"]).failure(4);
+    assert!(
+        run.stderr.contains("null at `server.tls.cert`"),
+        "{}",
+        run.stderr
+    );
+    assert!(!dir.exists("deep.toml"));
+}
+
+#[test]
+fn drops_nulls_under_omit() {
+    let run = to_toml(&["--null-policy", "omit"]).success();
+    assert_eq!(
+        run.stdout,
+        "list = [1, 2]\n\n[[workers]]\nid = 1\n[server.tls]\nkey = \"k\"\n"
+    );
+}
+
+#[test]
+fn writes_nulls_as_strings_under_string() {
+    let run = to_toml(&["--null-policy", "string"]).success();
+    assert_eq!(
+        run.stdout,
+        "top = \"null\"\nlist = [1, \"null\", 2]\n\n[[workers]]\nid = 1\nnote = \"null\"\n[server.tls]\ncert = \"null\"\nkey = \"k\"\n"
+    );
+}
+
+#[test]
+fn leaves_nulls_alone_for_formats_that_have_them() {
+    let dir = Dir::new();
+    dir.write("n.json", NULLS);
+    let run = dir
+        .run(&["n.json", "--stdout", "-f", "yaml", "--null-policy", "omit"])
+        .success();
+    assert!(run.stdout.starts_with("top: null\n"), "{}", run.stdout);
+}
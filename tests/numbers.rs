@@ -0,0 +1,83 @@
+mod common;
+
+use common::Dir;
+
+const U64_MAX: &str = "18446744073709551615";
+const I64_MIN: &str = "-9223372036854775808";
+/// 20 significant digits, more than a double holds
+const PRECISE: &str = "1.2345678901234567890";
+
+/// Convert `content`, named `from`, to each of `to` in turn, each read from
+/// the last, and return what the final one holds
+fn chain(from: &str, content: &str, to: &[&str]) -> String {
+    let dir = Dir::new();
+    dir.write(from, content);
+    let mut last = from.to_string();
+    for name in to {
+        dir.run(&[&last, name]).success();
+        last = name.to_string();
+    }
+    dir.read(&last)
+}
+
+#[test]
+fn keeps_u64_max_between_json_and_yaml() {
+    let json = format!("{{\n  \"max\": {U64_MAX}\n}}\n");
+    assert_eq!(
+        chain("a.json", &json, &["a.yaml"]),
+        format!("max: {U64_MAX}\n")
+    );
+    assert_eq!(chain("a.json", &json, &["a.yaml", "b.json"]), json);
+    assert_eq!(chain("a.json", &json, &["a.msgpack", "b.json"]), json);
+    assert_eq!(chain("a.json", &json, &["a.cbor", "b.json"]), json);
+}
+
+#[test]
+fn keeps_i64_min_and_integral_floats_in_every_direction() {
+    let toml = format!("min = {I64_MIN}\none = 1.0\n");
+    let json = format!("{{\n  \"min\": {I64_MIN},\n  \"one\": 1.0\n}}\n");
+    let yaml = format!("min: {I64_MIN}\none: 1.0\n");
+    assert_eq!(chain("a.toml", &toml, &["a.json"]), json);
+    assert_eq!(chain("a.toml", &toml, &["a.yaml"]), yaml);
+    assert_eq!(chain("a.json", &json, &["a.toml"]), toml);
+    assert_eq!(chain("a.yaml", &yaml, &["a.toml"]), toml);
+    assert_eq!(
+        chain("a.toml", &toml, &["a.yaml", "a.json", "b.toml"]),
+        toml
+    );
+}
+
+#[test]
+fn keeps_every_digit_of_a_precise_decimal_between_json_and_yaml() {
+    let json = format!("{{\n  \"d\": {PRECISE}\n}}\n");
+    assert_eq!(
+        chain("a.json", &json, &["a.yaml"]),
+        format!("d: {PRECISE}\n")
+    );
+    assert_eq!(chain("a.json", &json, &["a.yaml", "b.json"]), json);
+}
+
+#[test]
+fn refuses_numbers_the_output_cant_hold() {
+    let dir = Dir::new();
+    dir.write("max.yaml", format!("server:\n  id: {U64_MAX}\n"));
+    let run = dir.run(&["max.yaml", "-f", "toml"]).failure(4);
+    assert_eq!(
+        run.stderr,
+        format!(
+            "Error: max.yaml: `server.id` is {U64_MAX}, which is too large for a TOML integer\n"
+        )
+    );
+
+    dir.write("precise.json", format!("{{\"d\": {PRECISE}}}"));
+    let run = dir.run(&["precise.json", "-f", "toml"]).failure(4);
+    assert_eq!(
+        run.stderr,
+        format!("Error: precise.json: `d` is {PRECISE}, which has more digits than a TOML float holds\n")
+    );
+    let run = dir.run(&["precise.json", "-f", "msgpack"]).failure(4);
+    assert!(run
+        .stderr
+        .contains("has more digits than a MessagePack float holds"));
+    assert!(!dir.exists("max.toml") && !dir.exists("precise.toml"));
+}
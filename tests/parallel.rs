@@ -0,0 +1,87 @@
+mod common;
+
+use common::Dir;
+
+/// A scratch directory of 100 YAML files, `f00.yaml` to `f99.yaml`, and their
+/// names
+fn hundred() -> (Dir, Vec<String>) {
+    let dir = Dir::new();
+    let names = (0..100)
+        .map(|n| {
+            let name = format!("f{n:02}.yaml");
+            dir.write(&name, format!("n: {n}\nname: file {n}\n"));
+            name
+        })
+        .collect();
+    (dir, names)
+}
+
+fn args<'a>(names: &'a [String], rest: &[&'a str]) -> Vec<&'a str> {
+    let mut args: Vec<_> = names.iter().map(String::as_str).collect();
+    args.extend(rest);
+    args
+}
+
+#[test]
+fn converts_a_hundred_files_in_parallel() {
+    let (dir, names) = hundred();
+    let run = dir
+        .run(&args(&names, &["-f", "json", "--compact", "--jobs", "4"]))
+        .success();
+    for n in 0..100 {
+        assert_eq!(
+            dir.read(&format!("f{n:02}.json")),
+            format!("{{\"n\":{n},\"name\":\"file {n}\"}}\n")
+        );
+    }
+
+    // Lines come in the order files finish, but each is whole
+    let mut lines: Vec<_> = run.stderr.lines().collect();
+    assert_eq!(
+        lines.pop(),
+        Some("Converted 100 files, skipped 0, 0 errors")
+    );
+    lines.sort_unstable();
+    let expected: Vec<_> = (0..100)
+        .map(|n| format!("Wrote f{n:02}.yaml to f{n:02}.json"))
+        .collect();
+    assert_eq!(lines, expected);
+}
+
+#[test]
+fn converts_in_order_under_jobs_1() {
+    let (dir, names) = hundred();
+    let run = dir
+        .run(&args(&names, &["-f", "json", "--jobs", "1"]))
+        .success();
+    let mut expected: String = (0..100)
+        .map(|n| format!("Wrote f{n:02}.yaml to f{n:02}.json\n"))
+        .collect();
+    expected.push_str("Converted 100 files, skipped 0, 0 errors\n");
+    assert_eq!(run.stderr, expected);
+}
+
+#[test]
+fn fails_when_any_file_does() {
+    let (dir, names) = hundred();
+    dir.write("f42.yaml", "n: [\n");
+    let run = dir
+        .run(&args(&names, &["-f", "json", "--jobs", "8"]))
+        .failure(3);
+    assert!(
+        run.stderr
+            .ends_with("Error: Converted 99 files, skipped 0, 1 error\n"),
+        "{}",
+        run.stderr
+    );
+    assert!(!dir.exists("f42.json"));
+    assert!(dir.exists("f41.json") && dir.exists("f43.json"));
+}
+
+#[test]
+fn refuses_no_jobs() {
+    let (dir, names) = hundred();
+    dir.run(&args(&names[..2], &["-f", "json", "--jobs", "0"]))
+        .failure(2);
+    assert!(!dir.exists("f00.json"));
+}
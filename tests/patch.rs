@@ -0,0 +1,88 @@
+mod common;
+
+use common::Dir;
+use serde_json::json;
+
+const CONFIG: &str = "a: 1\nb:\n  c: 2\n  d: [1, 2]\nkeep: x\n";
+
+fn patched(patches: &[(&str, &str)]) -> serde_json::Value {
+    let dir = Dir::new();
+    dir.write("config.yaml", CONFIG);
+    let mut args = vec!["config.yaml", "--stdout", "-f", "json"];
+    for (name, patch) in patches {
+        dir.write(name, patch);
+        args.extend(["--patch", name]);
+    }
+    common::json(&dir.run(&args).success().stdout)
+}
+
+#[test]
+fn deletes_keys_patched_to_null() {
+    assert_eq!(
+        patched(&[("p.json", r#"{"a": null, "b": {"c": null}}"#)]),
+        json!({"b": {"d": [1, 2]}, "keep": "x"})
+    );
+}
+
+#[test]
+fn merges_maps_and_replaces_everything_else() {
+    assert_eq!(
+        patched(&[("p.json", r#"{"b": {"c": 3, "d": {"new": true}}, "e": [0]}"#)]),
+        json!({"a": 1, "b": {"c": 3, "d": {"new": true}}, "keep": "x", "e": [0]})
+    );
+}
+
+#[test]
+fn replaces_the_whole_document_with_a_patch_that_isnt_a_map() {
+    assert_eq!(patched(&[("p.json", "[1, 2]")]), json!([1, 2]));
+}
+
+#[test]
+fn applies_patches_in_order_in_any_format() {
+    assert_eq!(
+        patched(&[("1.toml", "keep = \"y\"\na = 5\n"), ("2.yaml", "a: 6\n")]),
+        json!({"a": 6, "b": {"c": 2, "d": [1, 2]}, "keep": "y"})
+    );
+}
+
+#[test]
+fn patches_what_the_query_selects() {
+    let dir = Dir::new();
+    dir.write("q.yaml", "services:\n  web: {a: 1, b: 2}\n");
+    dir.write("p.json", r#"{"b": null}"#);
+    let run = dir
+        .run(&[
+            "q.yaml",
+            "--stdout",
+            "-f",
+            "json",
+            "--compact",
+            "--query",
+            "/services/web",
+            "--patch",
+            "p.json",
+        ])
+        .success();
+    assert_eq!(run.stdout, "{\"a\":1}\n");
+}
+
+#[test]
+fn tells_a_broken_patch_from_a_broken_input() {
+    let dir = Dir::new();
+    dir.write("config.yaml", CONFIG);
+    dir.write("bad.json", "{");
+    let run = dir
+        .run(&["config.yaml", "-f", "json", "--patch", "bad.json"])
+        .failure(3);
+    assert_eq!(
+        run.stderr,
+        "Error: Invalid patch: bad.json:1:1: EOF while parsing an object\n"
+    );
+    let run = dir
+        .run(&["config.yaml", "-f", "json", "--patch", "missing.json"])
+        .failure(5);
+    assert!(run
+        .stderr
+        .starts_with("Error: Invalid patch: Failed to read missing.json:"));
+    assert!(!dir.exists("config.json"));
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::Dir;
+use serde_json::json;
+
+const PLIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>created</key>
+	<date>2024-05-01T10:00:00Z</date>
+	<key>blob</key>
+	<data>aGVsbG8=</data>
+	<key>nested</key>
+	<dict>
+		<key>n</key>
+		<integer>3</integer>
+		<key>f</key>
+		<real>1.5</real>
+		<key>ok</key>
+		<true/>
+		<key>list</key>
+		<array>
+			<string>x</string>
+		</array>
+	</dict>
+</dict>
+</plist>
+"#;
+
+/// `{"a": 1, "d": <data "hi">}` as a binary plist
+const BINARY: &[u8] = b"bplist00\xd2\x01\x02\x03\x04QaQd\x10\x01Bhi\x08\r\x0f\x11\x13\x00\x00\x00\x00\x00\x00\x01\x01\x00\x00\x00\x00\x00\x00\x00\x05\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x16";
+
+#[test]
+fn reads_dates_as_strings_and_data_as_base64() {
+    let dir = Dir::new();
+    dir.write("a.plist", PLIST);
+    let run = dir.run(&["a.plist", "--stdout", "-f", "json"]).success();
+    assert_eq!(
+        common::json(&run.stdout),
+        json!({
+            "created": "2024-05-01T10:00:00Z",
+            "blob": "aGVsbG8=",
+            "nested": {"n": 3, "f": 1.5, "ok": true, "list": ["x"]},
+        })
+    );
+}
+
+#[test]
+fn round_trips_through_json_with_dates_restored() {
+    let dir = Dir::new();
+    dir.write("a.plist", PLIST);
+    dir.run(&["a.plist", "a.json"]).success();
+    dir.run(&["a.json", "back.plist"]).success();
+    // Base64 isn't told apart from other strings, so only the data changes
+    assert_eq!(
+        dir.read("back.plist"),
+        PLIST.replace("<data>aGVsbG8=</data>", "<string>aGVsbG8=</string>")
+    );
+}
+
+#[test]
+fn keeps_data_as_data_from_plist_to_plist() {
+    let dir = Dir::new();
+    dir.write("a.plist", PLIST);
+    dir.run(&["a.plist", "out.plist"]).success();
+    let out = dir.read("out.plist");
+    assert!(out.contains("<data>\n\taGVsbG8=\n\t</data>"), "{out}");
+    assert!(out.contains("<date>2024-05-01T10:00:00Z</date>"));
+}
+
+#[test]
+fn reads_binary_plists() {
+    let dir = Dir::new();
+    dir.write("b.plist", BINARY);
+    let run = dir.run(&["b.plist", "--stdout", "-f", "json"]).success();
+    assert_eq!(common::json(&run.stdout), json!({"a": 1, "d": "aGk="}));
+}
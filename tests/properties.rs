@@ -0,0 +1,157 @@
+mod common;
+
+use common::Dir;
+use serde_json::json;
+
+const APP: &str = "server.host = example.com\nserver.port=8080\n\
+                   query = a=b&c=d\ngreeting = caf\\u00e9\n\
+                   long.text = first, \\\n    second\n";
+
+#[test]
+fn properties_round_trip_unchanged() {
+    let dir = Dir::new();
+    dir.write("app.properties", APP);
+    dir.run(&["app.properties", "out.properties"]).success();
+    assert_eq!(
+        dir.read("out.properties"),
+        "server.host=example.com\nserver.port=8080\nquery=a=b&c=d\n\
+         greeting=caf\\u00E9\nlong.text=first, second\n"
+    );
+}
+
+#[test]
+fn properties_round_trip_through_yaml() {
+    let dir = Dir::new();
+    dir.write("app.properties", APP);
+    dir.run(&["app.properties", "app.yaml", "--unflatten", "--infer-types"])
+        .success();
+    assert_eq!(
+        dir.read("app.yaml"),
+        "server:\n  host: example.com\n  port: 8080\nquery: a=b&c=d\n\
+         greeting: café\nlong:\n  text: first, second\n"
+    );
+    dir.run(&["app.yaml", "back.properties"]).success();
+    dir.run(&["back.properties", "back.json"]).success();
+    dir.run(&["app.properties", "app.json"]).success();
+    assert_eq!(
+        common::json(&dir.read("back.json")),
+        common::json(&dir.read("app.json"))
+    );
+}
+
+#[test]
+fn flat_yaml_keeps_its_dotted_keys() {
+    let dir = Dir::new();
+    dir.write("flat.yaml", "server.port: 8080\n");
+    dir.run(&["flat.yaml", "flat.properties"]).success();
+    assert_eq!(dir.read("flat.properties"), "server.port=8080\n");
+}
+
+#[test]
+fn reads_separators_continuations_and_escapes() {
+    let dir = Dir::new();
+    dir.write(
+        "a.properties",
+        "# comment\n! comment\na=1\nb: 2\nc   3\nd = x=y\n\
+         long = one, \\\n    two\ncafe = caf\\u00e9 \\uD83D\\uDE00\n",
+    );
+    let run = dir
+        .run(&["a.properties", "--stdout", "-f", "json"])
+        .success();
+    assert_eq!(
+        common::json(&run.stdout),
+        json!({
+            "a": "1",
+            "b": "2",
+            "c": "3",
+            "d": "x=y",
+            "long": "one, two",
+            "cafe": "café 😀",
+        })
+    );
+}
+
+#[test]
+fn places_a_short_unicode_escape() {
+    let dir = Dir::new();
+    dir.write("u.properties", "a=\\u12\n");
+    let run = dir.run(&["u.properties", "-f", "json"]).failure(3);
+    assert_eq!(
+        run.stderr,
+        "Error: u.properties:1: \\u12 needs four hex digits\n"
+    );
+}
+
+#[test]
+fn joins_nested_keys_and_keeps_dotted_ones() {
+    let dir = Dir::new();
+    dir.write(
+        "a.json",
+        r#"{"server.port": 8080, "db": {"host": "x", "ports": [1, 2]}, "none": null}"#,
+    );
+    let run = dir
+        .run(&["a.json", "--stdout", "-f", "properties"])
+        .success();
+    assert_eq!(
+        run.stdout,
+        "server.port=8080\ndb.host=x\ndb.ports.0=1\ndb.ports.1=2\nnone=\n"
+    );
+}
+
+#[test]
+fn joins_keys_with_flatten_sep() {
+    let dir = Dir::new();
+    dir.write("a.json", r#"{"db": {"host": "x", "ports": [1]}}"#);
+    let run = dir
+        .run(&[
+            "a.json",
+            "--stdout",
+            "-f",
+            "properties",
+            "--flatten-sep",
+            "_",
+        ])
+        .success();
+    assert_eq!(run.stdout, "db_host=x\ndb_ports_0=1\n");
+}
+
+#[test]
+fn escapes_keys_and_values() {
+    let dir = Dir::new();
+    dir.write("k.json", r#"{"a key=": " café"}"#);
+    dir.run(&["k.json", "k.properties"]).success();
+    assert_eq!(dir.read("k.properties"), "a\\ key\\==\\ caf\\u00E9\n");
+    let run = dir
+        .run(&["k.properties", "--stdout", "-f", "json"])
+        .success();
+    assert_eq!(common::json(&run.stdout), json!({"a key=": " café"}));
+}
+
+#[test]
+fn refuses_keys_that_collide() {
+    let dir = Dir::new();
+    dir.write("c.json", r#"{"a.b": 1, "a": {"b": 2}}"#);
+    let run = dir.run(&["c.json", "-f", "properties"]).failure(4);
+    assert_eq!(
+        run.stderr,
+        "Error: c.json: `/a/b` and another value both become `a.b`\n"
+    );
+    assert!(!dir.exists("c.properties"));
+}
+
+#[test]
+fn refuses_empty_maps_and_arrays() {
+    let dir = Dir::new();
+    dir.write("m.json", r#"{"a": {"b": {}}}"#);
+    let run = dir.run(&["m.json", "-f", "properties"]).failure(4);
+    assert_eq!(
+        run.stderr,
+        "Error: m.json: `a.b` is an empty map, which has no place in properties\n"
+    );
+    dir.write("l.json", r#"{"a": {"l": []}}"#);
+    let run = dir.run(&["l.json", "-f", "properties"]).failure(4);
+    assert_eq!(
+        run.stderr,
+        "Error: l.json: `a.l` is an empty array, which has no place in properties\n"
+    );
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::Dir;
+use serde_json::json;
+
+const DEPLOY: &str = "services:
+  web:
+    env: {A: \"1\", B: two}
+    ports: [80, 443]
+  a/b: {x: 1}
+  m~n: 5
+items: [{name: first}, {name: second}]
+";
+
+fn query(pointer: &str, to: &str) -> common::Run {
+    let dir = Dir::new();
+    dir.write("deploy.yaml", DEPLOY);
+    dir.run(&["deploy.yaml", "--stdout", "-f", to, "--query", pointer])
+}
+
+#[test]
+fn extracts_a_map() {
+    let run = query("/services/web/env", "json").success();
+    assert_eq!(common::json(&run.stdout), json!({"A": "1", "B": "two"}));
+    let run = query("/services/web/env", "toml").success();
+    assert_eq!(run.stdout, "A = \"1\"\nB = \"two\"\n");
+}
+
+#[test]
+fn indexes_arrays() {
+    let run = query("/items/1", "json").success();
+    assert_eq!(common::json(&run.stdout), json!({"name": "second"}));
+    let run = query("/services/web/ports/0", "json").success();
+    assert_eq!(run.stdout, "80\n");
+}
+
+#[test]
+fn extracts_a_scalar() {
+    assert_eq!(
+        query("/items/0/name", "json").success().stdout,
+        "\"first\"\n"
+    );
+    assert_eq!(query("/items/0/name", "yaml").success().stdout, "first\n");
+    let run = query("/items/0/name", "toml").failure(4);
+    assert!(run
+        .stderr
+        .contains("TOML needs a table at the root, not a string; pass --toml-wrap KEY"));
+
+    let dir = Dir::new();
+    dir.write("deploy.yaml", DEPLOY);
+    let run = dir
+        .run(&[
+            "deploy.yaml",
+            "--stdout",
+            "-f",
+            "toml",
+            "--query",
+            "/items/0/name",
+            "--toml-wrap",
+            "value",
+        ])
+        .success();
+    assert_eq!(run.stdout, "value = \"first\"\n");
+}
+
+#[test]
+fn unescapes_slashes_and_tildes() {
+    let run = query("/services/a~1b", "json").success();
+    assert_eq!(common::json(&run.stdout), json!({"x": 1}));
+    assert_eq!(query("/services/m~0n", "json").success().stdout, "5\n");
+}
+
+#[test]
+fn selects_the_whole_document_with_an_empty_pointer() {
+    let run = query("", "json").success();
+    assert_eq!(
+        common::json(&run.stdout),
+        serde_yaml::from_str::<serde_json::Value>(DEPLOY).unwrap()
+    );
+}
+
+#[test]
+fn names_the_first_missing_segment() {
+    let run = query("/services/api/env", "json").failure(4);
+    assert_eq!(
+        run.stderr,
+        "Error: deploy.yaml: --query `/services/api/env`: /services has no key `api`\n"
+    );
+    let run = query("/items/5/name", "json").failure(4);
+    assert_eq!(
+        run.stderr,
+        "Error: deploy.yaml: --query `/items/5/name`: /items has no item `5`\n"
+    );
+    let run = query("services", "json").failure(4);
+    assert_eq!(
+        run.stderr,
+        "Error: deploy.yaml: --query `services` must start with `/`\n"
+    );
+}
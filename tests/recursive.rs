@@ -0,0 +1,79 @@
+mod common;
+
+use common::Dir;
+
+fn tree() -> Dir {
+    let dir = Dir::new();
+    dir.write("configs/a.yaml", "a: 1\n");
+    dir.write("configs/sub/b.toml", "b = 2\n");
+    dir.write("configs/sub/deep/c.yml", "c: 3\n");
+    dir.write("configs/d.json", "{}");
+    dir.write("configs/notes.txt", "not config");
+    dir
+}
+
+#[test]
+fn mirrors_the_tree_under_out_dir() {
+    let dir = tree();
+    let run = dir
+        .run(&["configs", "--to", "json", "--recursive", "--out-dir", "out"])
+        .success();
+    assert!(
+        run.stderr
+            .ends_with("Converted 3 files, skipped 2, 0 errors\n"),
+        "{}",
+        run.stderr
+    );
+    assert_eq!(
+        common::json(&dir.read("out/a.json")),
+        common::json("{\"a\": 1}")
+    );
+    assert_eq!(
+        common::json(&dir.read("out/sub/b.json")),
+        common::json("{\"b\": 2}")
+    );
+    assert_eq!(
+        common::json(&dir.read("out/sub/deep/c.json")),
+        common::json("{\"c\": 3}")
+    );
+    // Files already in the target format, or in none, are skipped
+    assert!(!dir.exists("out/d.json") && !dir.exists("out/notes.json"));
+}
+
+#[test]
+fn counts_errors_in_the_summary_and_exit_status() {
+    let dir = tree();
+    dir.write("configs/sub/e.yaml", "e: [\n");
+    let run = dir
+        .run(&["configs", "--to", "json", "--recursive", "--out-dir", "out"])
+        .failure(3);
+    assert!(
+        run.stderr.contains("configs/sub/e.yaml:2:1: "),
+        "{}",
+        run.stderr
+    );
+    assert!(run
+        .stderr
+        .ends_with("Error: Converted 3 files, skipped 2, 1 error\n"));
+    assert!(dir.exists("out/sub/deep/c.json"));
+}
+
+#[cfg(unix)]
+#[test]
+fn doesnt_follow_symlinked_directories() {
+    let dir = tree();
+    std::os::unix::fs::symlink("..", dir.path("configs/sub/loop")).unwrap();
+    dir.run(&["configs", "--to", "json", "--recursive", "--out-dir", "out"])
+        .success();
+    assert!(!dir.exists("out/sub/loop"));
+}
+
+#[test]
+fn asks_for_recursive_for_a_directory() {
+    let dir = tree();
+    let run = dir.run(&["configs", "--to", "json"]).failure(2);
+    assert_eq!(
+        run.stderr,
+        "Error: configs is a directory; pass --recursive to convert the files in it\n"
+    );
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn pretty_prints_minified_json() {
+    let dir = Dir::new();
+    dir.write("min.json", r#"{"b":[1,2],"a":{"c":"x"}}"#);
+    dir.run(&["min.json", "pretty.json"]).success();
+    assert_eq!(
+        dir.read("pretty.json"),
+        "{\n  \"b\": [\n    1,\n    2\n  ],\n  \"a\": {\n    \"c\": \"x\"\n  }\n}\n"
+    );
+}
+
+#[test]
+fn reformats_yaml_to_the_same_document() {
+    let dir = Dir::new();
+    dir.write(
+        "in.yaml",
+        "a:   1\nb:\n    - 'x'\n    - \"y\"\nc: {d: true}\n",
+    );
+    dir.run(&["in.yaml", "out.yaml"]).success();
+    assert_eq!(
+        dir.read("out.yaml"),
+        "a: 1\nb:\n- x\n- 'y'\nc:\n  d: true\n"
+    );
+
+    let as_json =
+        |name: &str| common::json(&dir.run(&[name, "--stdout", "-f", "json"]).success().stdout);
+    assert_eq!(as_json("in.yaml"), as_json("out.yaml"));
+}
+
+#[test]
+fn reformats_toml() {
+    let dir = Dir::new();
+    dir.write("in.toml", "a=1\n[t]\nb  =  \"x\"\n");
+    dir.run(&["in.toml", "out.toml"]).success();
+    assert_eq!(dir.read("out.toml"), "a = 1\n\n[t]\nb = \"x\"\n");
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn reports_a_successful_batch() {
+    let dir = Dir::new();
+    dir.write("a.yaml", "a: 1\n");
+    dir.write("b.toml", "b = 2\n");
+    let run = dir
+        .run(&["a.yaml", "b.toml", "-f", "json", "--report", "json"])
+        .success();
+    let report = common::json(&run.stdout);
+
+    let mut conversions = report["conversions"].as_array().unwrap().clone();
+    conversions.sort_by_key(|conversion| conversion["input"].to_string());
+    assert_eq!(conversions.len(), 2);
+    let a = &conversions[0];
+    assert_eq!(a["input"], "a.yaml");
+    assert_eq!(a["output"], "a.json");
+    assert_eq!(a["input_format"], "yaml");
+    assert_eq!(a["output_format"], "json");
+    assert_eq!(a["bytes_read"], 5);
+    assert_eq!(a["bytes_written"], dir.bytes("a.json").len());
+    assert!(a["duration_ms"].is_number());
+    assert_eq!(a["status"], "ok");
+    assert!(a["error"].is_null());
+    assert_eq!(conversions[1]["input_format"], "toml");
+
+    let summary = &report["summary"];
+    assert_eq!(summary["total"], 2);
+    assert_eq!(summary["ok"], 2);
+    assert_eq!(summary["skipped"], 0);
+    assert_eq!(summary["error"], 0);
+    assert_eq!(summary["exit_status"], 0);
+}
+
+#[test]
+fn reports_a_failure_with_its_message_and_exit_status() {
+    let dir = Dir::new();
+    dir.write("good.json", "{}");
+    dir.write("bad.json", "{");
+    dir.run(&[
+        "good.json",
+        "bad.json",
+        "-f",
+        "yaml",
+        "--report",
+        "json",
+        "--report-file",
+        "report.json",
+    ])
+    .failure(3);
+    let report = common::json(&dir.read("report.json"));
+
+    let conversions = report["conversions"].as_array().unwrap();
+    let bad = conversions
+        .iter()
+        .find(|conversion| conversion["input"] == "bad.json")
+        .unwrap();
+    assert_eq!(bad["status"], "error");
+    assert!(bad["error"].as_str().unwrap().starts_with("bad.json:"));
+    assert!(bad["bytes_written"].is_null());
+
+    let summary = &report["summary"];
+    assert_eq!(summary["total"], 2);
+    assert_eq!(summary["ok"], 1);
+    assert_eq!(summary["error"], 1);
+    assert_eq!(summary["exit_status"], 3);
+}
+
+#[test]
+fn names_formats_as_the_format_flags_do() {
+    let dir = Dir::new();
+    dir.write("a.yaml", "a: 1\n");
+    let run = dir.run(&["--validate", "a.yaml"]).success();
+    assert_eq!(run.stderr, "a.yaml: valid yaml\n");
+    let run = dir.run(&["a.yaml", "-f", "toml", "--dry-run"]).success();
+    assert_eq!(run.stderr, "Would write a.yaml (yaml) to a.toml (toml)\n");
+}
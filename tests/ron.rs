@@ -0,0 +1,79 @@
+mod common;
+
+use common::Dir;
+use serde_json::json;
+
+const HERO: &str = r#"(
+    name: "hero",
+    stats: (hp: 10, speed: 1.5),
+    tags: ["a", "b"],
+    opt: Some(3),
+    alive: true,
+)
+"#;
+
+#[test]
+fn reads_ron_structs_as_maps() {
+    let dir = Dir::new();
+    dir.write("hero.ron", HERO);
+    let run = dir.run(&["hero.ron", "--stdout", "-f", "json"]).success();
+    assert_eq!(
+        common::json(&run.stdout),
+        json!({
+            "name": "hero",
+            "stats": {"hp": 10, "speed": 1.5},
+            "tags": ["a", "b"],
+            "opt": 3,
+            "alive": true,
+        })
+    );
+}
+
+#[test]
+fn round_trips_json_through_ron() {
+    let dir = Dir::new();
+    dir.write("a.json", r#"{"a":{"b":[1,2.5,"x"]},"ok":false}"#);
+    dir.run(&["a.json", "--to", "ron"]).success();
+    assert_eq!(
+        dir.read("a.ron"),
+        "{\n    \"a\": {\n        \"b\": [\n            1,\n            2.5,\n            \"x\",\n        ],\n    },\n    \"ok\": false,\n}\n"
+    );
+    dir.run(&["a.ron", "back.json"]).success();
+    assert_eq!(
+        common::json(&dir.read("back.json")),
+        common::json(&dir.read("a.json"))
+    );
+}
+
+#[test]
+fn refuses_names_that_would_be_lost() {
+    let dir = Dir::new();
+    dir.write("unit.ron", "(kind: Fire)");
+    let run = dir.run(&["unit.ron", "-f", "json"]).failure(3);
+    assert_eq!(
+        run.stderr,
+        "Error: unit.ron:1: `Fire` is a unit struct or enum variant, which can't be converted without losing it\n"
+    );
+
+    dir.write("tuple.ron", "(\n  p: Point(1, 2),\n)");
+    let run = dir.run(&["tuple.ron", "-f", "json"]).failure(3);
+    assert!(
+        run.stderr
+            .starts_with("Error: tuple.ron:2: `Point(..)` is a tuple struct or enum variant"),
+        "{}",
+        run.stderr
+    );
+    assert!(!dir.exists("unit.json") && !dir.exists("tuple.json"));
+}
+
+#[test]
+fn places_a_ron_syntax_error() {
+    let dir = Dir::new();
+    dir.write("bad.ron", "(a: 1, b: [1, 2)");
+    let run = dir.run(&["bad.ron", "-f", "json"]).failure(3);
+    assert!(
+        run.stderr.starts_with("Error: bad.ron:1:"),
+        "{}",
+        run.stderr
+    );
+}
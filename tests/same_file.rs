@@ -0,0 +1,70 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn refuses_the_input_path_itself() {
+    let dir = Dir::new();
+    dir.write("a.json", "{\"a\":1}");
+    let run = dir.run(&["a.json", "a.json"]).failure(1);
+    assert!(run
+        .stderr
+        .contains("Refusing to overwrite the input file a.json; pass --in-place"));
+    assert_eq!(dir.read("a.json"), "{\"a\":1}");
+}
+
+#[test]
+fn refuses_a_path_through_dot_dot() {
+    let dir = Dir::new();
+    dir.write("a.json", "{\"a\":1}");
+    dir.write("sub/b.json", "{}");
+    let run = dir.run(&["a.json", "sub/../a.json"]).failure(1);
+    assert!(run.stderr.contains("which sub/../a.json leads to"));
+    assert_eq!(dir.read("a.json"), "{\"a\":1}");
+}
+
+#[cfg(unix)]
+#[test]
+fn refuses_a_symlink_to_the_input() {
+    let dir = Dir::new();
+    dir.write("a.json", "{\"a\":1}");
+    std::os::unix::fs::symlink("a.json", dir.path("link.yaml")).unwrap();
+    let run = dir.run(&["a.json", "link.yaml"]).failure(1);
+    assert!(run.stderr.contains("which link.yaml leads to"));
+    assert_eq!(dir.read("a.json"), "{\"a\":1}");
+}
+
+#[cfg(unix)]
+#[test]
+fn refuses_a_symlinked_directory_leading_back() {
+    let dir = Dir::new();
+    dir.write("a.json", "{\"a\":1}");
+    std::fs::create_dir(dir.path("sub")).unwrap();
+    std::os::unix::fs::symlink("..", dir.path("sub/up")).unwrap();
+    dir.run(&["a.json", "sub/up/a.json"]).failure(1);
+    assert_eq!(dir.read("a.json"), "{\"a\":1}");
+}
+
+#[test]
+fn in_place_reformats_the_input() {
+    let dir = Dir::new();
+    dir.write("a.json", "{\"a\":1}");
+    dir.run(&["a.json", "a.json", "--in-place"]).success();
+    assert_eq!(dir.read("a.json"), "{\n  \"a\": 1\n}\n");
+}
+
+#[test]
+fn in_place_keeps_an_input_it_wrote_over() {
+    let dir = Dir::new();
+    dir.write("a.json", "{\"a\":1}");
+    dir.run(&[
+        "./a.json",
+        "--in-place",
+        "--keep-original=false",
+        "-f",
+        "json",
+    ])
+    .success();
+    assert!(dir.exists("a.json"));
+    assert_eq!(common::json(&dir.read("a.json")), common::json("{\"a\":1}"));
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use common::Dir;
+
+const SCHEMA: &str = include_str!("fixtures/schema.yaml");
+const VALID: &str = include_str!("fixtures/valid.yaml");
+const INVALID: &str = include_str!("fixtures/invalid.yaml");
+
+fn dir() -> Dir {
+    let dir = Dir::new();
+    dir.write("schema.yaml", SCHEMA);
+    dir.write("valid.yaml", VALID);
+    dir.write("invalid.yaml", INVALID);
+    dir
+}
+
+#[test]
+fn writes_a_document_that_matches() {
+    let dir = dir();
+    dir.run(&["valid.yaml", "valid.json", "--schema", "schema.yaml"])
+        .success();
+    assert_eq!(
+        common::json(&dir.read("valid.json")),
+        serde_json::json!({"name": "api", "port": 8080, "tags": ["web", "public"]})
+    );
+}
+
+#[test]
+fn lists_every_violation_and_writes_nothing() {
+    let dir = dir();
+    let run = dir
+        .run(&["invalid.yaml", "invalid.json", "--schema", "schema.yaml"])
+        .failure(6);
+    assert_eq!(
+        run.stderr,
+        "Error: invalid.yaml: doesn't match the schema in schema.yaml:\n  \
+         the root: \"name\" is a required property (required)\n  \
+         `/port`: 0 is less than the minimum of 1 (minimum)\n  \
+         `/tags/1`: 1 is not of type \"string\" (type)\n"
+    );
+    assert!(!dir.exists("invalid.json"));
+}
+
+#[test]
+fn reads_a_schema_kept_as_json() {
+    let dir = dir();
+    dir.run(&["schema.yaml", "schema.json"]).success();
+    dir.run(&[
+        "valid.yaml",
+        "--stdout",
+        "-f",
+        "toml",
+        "--schema",
+        "schema.json",
+    ])
+    .success();
+    let run = dir
+        .run(&[
+            "invalid.yaml",
+            "--stdout",
+            "-f",
+            "toml",
+            "--schema",
+            "schema.json",
+        ])
+        .failure(6);
+    assert!(run.stdout.is_empty());
+    assert!(run
+        .stderr
+        .contains("doesn't match the schema in schema.json:"));
+}
+
+#[test]
+fn validates_without_an_output() {
+    let dir = dir();
+    let run = dir
+        .run(&["valid.yaml", "--schema", "schema.yaml"])
+        .success();
+    assert!(
+        run.stderr
+            .contains("valid.yaml: valid yaml and matches schema.yaml"),
+        "{}",
+        run.stderr
+    );
+    let run = dir
+        .run(&["invalid.yaml", "--schema", "schema.yaml"])
+        .failure(6);
+    assert!(run.stderr.contains("(minimum)"), "{}", run.stderr);
+}
+
+#[test]
+fn refuses_a_schema_that_isnt_valid() {
+    let dir = dir();
+    dir.write("broken.yaml", "type: nope\n");
+    let run = dir
+        .run(&["valid.yaml", "valid.json", "--schema", "broken.yaml"])
+        .failure(2);
+    assert!(
+        run.stderr
+            .starts_with("Error: Invalid schema: broken.yaml: "),
+        "{}",
+        run.stderr
+    );
+    assert!(!dir.exists("valid.json"));
+
+    let run = dir
+        .run(&["valid.yaml", "valid.json", "--schema", "missing.yaml"])
+        .failure(5);
+    assert!(run
+        .stderr
+        .starts_with("Error: Invalid schema: Failed to read missing.yaml"));
+}
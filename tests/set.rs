@@ -0,0 +1,199 @@
+mod common;
+
+use common::Dir;
+
+const CONFIG: &str = r#"{"server":{"port":80},"name":"x","tags":["a"],"old":1}"#;
+
+fn edited(args: &[&str]) -> String {
+    let dir = Dir::new();
+    dir.write("c.json", CONFIG);
+    let mut all = vec!["c.json", "--stdout", "-f", "json", "--compact"];
+    all.extend(args);
+    dir.run(&all).success().stdout
+}
+
+#[test]
+fn replaces_scalars_with_values_parsed_as_json() {
+    assert_eq!(
+        edited(&[
+            "--set",
+            "/server/port=8080",
+            "--set",
+            "/name=\"web\"",
+            "--set",
+            "/tags=[\"a\",\"b\"]",
+        ]),
+        "{\"server\":{\"port\":8080},\"name\":\"web\",\"tags\":[\"a\",\"b\"],\"old\":1}\n"
+    );
+}
+
+#[test]
+fn creates_missing_parents_as_maps() {
+    assert_eq!(
+        edited(&["--set", "/a/b/c=1", "--set", "/a~1b=2"]),
+        "{\"server\":{\"port\":80},\"name\":\"x\",\"tags\":[\"a\"],\"old\":1,\"a\":{\"b\":{\"c\":1}},\"a/b\":2}\n"
+    );
+}
+
+#[test]
+fn appends_one_past_the_end_of_an_array() {
+    let appended = "{\"server\":{\"port\":80},\"name\":\"x\",\"tags\":[\"a\",\"b\"],\"old\":1}\n";
+    assert_eq!(edited(&["--set", "/tags/1=\"b\""]), appended);
+    assert_eq!(edited(&["--set", "/tags/-=\"b\""]), appended);
+
+    let dir = Dir::new();
+    dir.write("c.json", CONFIG);
+    let run = dir
+        .run(&["c.json", "-f", "yaml", "--set", "/tags/5=1"])
+        .failure(4);
+    assert_eq!(
+        run.stderr,
+        "Error: c.json: --set `/tags/5`: /tags has no item `5`\n"
+    );
+    assert!(!dir.exists("c.yaml"));
+}
+
+#[test]
+fn unsets_keys_and_items() {
+    assert_eq!(
+        edited(&[
+            "--unset",
+            "/old",
+            "--unset",
+            "/tags/0",
+            "--unset",
+            "/server/port"
+        ]),
+        "{\"server\":{},\"name\":\"x\",\"tags\":[]}\n"
+    );
+}
+
+#[test]
+fn applies_edits_in_command_line_order() {
+    assert_eq!(
+        edited(&["--set", "/x=1", "--unset", "/x", "--set", "/old=2"]),
+        "{\"server\":{\"port\":80},\"name\":\"x\",\"tags\":[\"a\"],\"old\":2}\n"
+    );
+    assert_eq!(
+        edited(&["--unset", "/old", "--set", "/old=3"]),
+        "{\"server\":{\"port\":80},\"name\":\"x\",\"tags\":[\"a\"],\"old\":3}\n"
+    );
+}
+
+#[test]
+fn edits_after_query() {
+    assert_eq!(
+        edited(&["--query", "/server", "--set", "/port=1"]),
+        "{\"port\":1}\n"
+    );
+}
+
+#[test]
+fn edits_after_patch() {
+    let dir = Dir::new();
+    dir.write("c.json", CONFIG);
+    dir.write("p.json", r#"{"server":{"port":81},"p":1}"#);
+    let run = dir
+        .run(&[
+            "c.json",
+            "--stdout",
+            "-f",
+            "json",
+            "--compact",
+            "--patch",
+            "p.json",
+            "--set",
+            "/p=2",
+        ])
+        .success();
+    assert_eq!(
+        run.stdout,
+        "{\"server\":{\"port\":81},\"name\":\"x\",\"tags\":[\"a\"],\"old\":1,\"p\":2}\n"
+    );
+}
+
+#[test]
+fn warns_about_an_unset_leading_nowhere_only_under_verbose() {
+    let dir = Dir::new();
+    dir.write("c.json", CONFIG);
+    let run = dir
+        .run(&["c.json", "--stdout", "-f", "json", "--unset", "/nope"])
+        .success();
+    assert_eq!(run.stderr, "");
+    assert_eq!(common::json(&run.stdout), common::json(CONFIG));
+
+    let run = dir
+        .run(&[
+            "c.json",
+            "--stdout",
+            "-f",
+            "json",
+            "--unset",
+            "/nope",
+            "--verbose",
+        ])
+        .success();
+    assert!(
+        run.stderr
+            .contains("warning: c.json: --unset `/nope` leads nowhere, so nothing was removed\n"),
+        "{}",
+        run.stderr
+    );
+}
+
+#[test]
+fn refuses_a_set_that_cant_apply() {
+    let dir = Dir::new();
+    dir.write("c.json", CONFIG);
+    let run = dir
+        .run(&["c.json", "-f", "yaml", "--set", "/name/q=1"])
+        .failure(4);
+    assert_eq!(
+        run.stderr,
+        "Error: c.json: --set `/name/q`: /name is a string, which has no `q`\n"
+    );
+}
+
+#[test]
+fn refuses_a_malformed_set() {
+    let dir = Dir::new();
+    dir.write("c.json", CONFIG);
+    let run = dir
+        .run(&["c.json", "-f", "yaml", "--set", "/name=web"])
+        .failure(2);
+    assert!(
+        run.stderr.contains(
+            "`web` isn't JSON (expected value at line 1 column 1); quote a string as '\"web\"'"
+        ),
+        "{}",
+        run.stderr
+    );
+    let run = dir
+        .run(&["c.json", "-f", "yaml", "--set", "name=1"])
+        .failure(2);
+    assert!(
+        run.stderr.contains("`name` must start with `/`"),
+        "{}",
+        run.stderr
+    );
+    assert!(!dir.exists("c.yaml"));
+}
+
+#[test]
+fn keeps_a_set_string_as_given_under_infer_types() {
+    let dir = Dir::new();
+    dir.write("a.ini", "[s]\na = 1\n");
+    let run = dir
+        .run(&[
+            "a.ini",
+            "--stdout",
+            "-f",
+            "json",
+            "--compact",
+            "--infer-types",
+            "--set",
+            "/s/b=\"123\"",
+        ])
+        .success();
+    assert_eq!(run.stdout, "{\"s\":{\"a\":1,\"b\":\"123\"}}\n");
+}
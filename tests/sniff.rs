@@ -0,0 +1,72 @@
+mod common;
+
+use common::Dir;
+use serde_json::json;
+
+/// Convert the extension-less `content` to compact JSON, verbosely
+fn sniffed(content: &str) -> common::Run {
+    let dir = Dir::new();
+    dir.write("config", content);
+    dir.run(&["config", "--stdout", "-f", "json", "--compact", "-v"])
+}
+
+#[test]
+fn detects_json_without_an_extension() {
+    let run = sniffed("{\"a\": [1]}").success();
+    assert_eq!(
+        run.stderr,
+        "Read config (json, detected from its content, 10 bytes)\n"
+    );
+    assert_eq!(common::json(&run.stdout), json!({"a": [1]}));
+}
+
+#[test]
+fn detects_toml_before_yaml() {
+    let run = sniffed("a = 1\n[t]\nb = 2\n").success();
+    assert!(
+        run.stderr.starts_with("Read config (toml, detected"),
+        "{}",
+        run.stderr
+    );
+    assert_eq!(common::json(&run.stdout), json!({"a": 1, "t": {"b": 2}}));
+}
+
+#[test]
+fn falls_back_to_yaml() {
+    let run = sniffed("a: 1\nb: [x]\n").success();
+    assert!(
+        run.stderr.starts_with("Read config (yaml, detected"),
+        "{}",
+        run.stderr
+    );
+    assert_eq!(common::json(&run.stdout), json!({"a": 1, "b": ["x"]}));
+}
+
+#[test]
+fn detects_standard_input() {
+    let dir = Dir::new();
+    let run = dir
+        .run_with(&["-", "--stdout", "-f", "json", "-v"], Some(b"a = 1\n"))
+        .success();
+    assert!(run
+        .stderr
+        .starts_with("Read standard input (toml, detected from its content"));
+}
+
+#[test]
+fn lists_every_attempt_for_garbage() {
+    let run = sniffed("\u{0}\u{1} garbage: [ {\n").failure(2);
+    assert_eq!(
+        run.stderr,
+        "Error: config: Couldn't detect the format; pass --input-format (--from). Tried JSON: doesn't start with `{` or `[`; TOML: 1:1: unexpected character found: `\\u{0}`; YAML: 1:1: control characters are not allowed\n"
+    );
+}
+
+#[test]
+fn goes_by_extension_alone_under_no_sniff() {
+    let dir = Dir::new();
+    dir.write("config", "{}");
+    dir.run(&["config", "--stdout", "-f", "json", "--no-sniff"])
+        .failure(2);
+    dir.run(&["config", "--stdout", "-f", "json"]).success();
+}
@@ -0,0 +1,187 @@
+#![cfg(feature = "sops")]
+
+mod common;
+
+use aes_gcm::{
+    aead::{consts::U32, Aead, KeyInit, Payload},
+    aes::Aes256,
+    AesGcm, Nonce,
+};
+use age::secrecy::ExposeSecret;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use common::Dir;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha512};
+
+type Cipher = AesGcm<Aes256, U32>;
+
+const DATA_KEY: [u8; 32] = [7; 32];
+const LAST_MODIFIED: &str = "2024-05-01T10:00:00Z";
+
+/// A test age key, and documents encrypted for it as `sops` would
+struct Sops {
+    identity: age::x25519::Identity,
+    cipher: Cipher,
+    ivs: u8,
+}
+
+impl Sops {
+    fn new() -> Self {
+        Self {
+            identity: age::x25519::Identity::generate(),
+            cipher: Cipher::new_from_slice(&DATA_KEY).unwrap(),
+            ivs: 0,
+        }
+    }
+
+    fn key(&self) -> String {
+        self.identity.to_string().expose_secret().to_string()
+    }
+
+    /// `plaintext` as an encrypted leaf, authenticated with `aad`
+    fn leaf(&mut self, plaintext: &str, kind: &str, aad: &str) -> Value {
+        self.ivs += 1;
+        let iv = [self.ivs; 32];
+        let nonce = Nonce::<U32>::try_from(&iv[..]).unwrap();
+        let sealed = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext.as_bytes(),
+                    aad: aad.as_bytes(),
+                },
+            )
+            .unwrap();
+        let (data, tag) = sealed.split_at(sealed.len() - 16);
+        Value::String(format!(
+            "ENC[AES256_GCM,data:{},iv:{},tag:{},type:{kind}]",
+            STANDARD.encode(data),
+            STANDARD.encode(iv),
+            STANDARD.encode(tag)
+        ))
+    }
+
+    /// The `sops` block for a document whose values hash to `values`
+    fn metadata(&mut self, values: &[&str]) -> Value {
+        let mut hasher = Sha512::new();
+        for value in values {
+            hasher.update(value.as_bytes());
+        }
+        let mac: String = hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect();
+        let enc = age::encrypt_and_armor(&self.identity.to_public(), &DATA_KEY).unwrap();
+        json!({
+            "age": [{"recipient": self.identity.to_public().to_string(), "enc": enc}],
+            "lastmodified": LAST_MODIFIED,
+            "mac": self.leaf(&mac, "str", LAST_MODIFIED),
+            "unencrypted_suffix": "_unencrypted",
+            "version": "3.8.1",
+        })
+    }
+
+    /// A small encrypted document, with one value left unencrypted
+    fn document(&mut self) -> Value {
+        let mut document = json!({
+            "db": {
+                "user": self.leaf("admin", "str", "db:user:"),
+                "port": self.leaf("5432", "int", "db:port:"),
+                "tls": self.leaf("true", "bool", "db:tls:"),
+            },
+            "hosts": [self.leaf("a", "str", "hosts:"), self.leaf("b", "str", "hosts:")],
+            "name_unencrypted": "x",
+        });
+        document["sops"] = self.metadata(&["admin", "5432", "True", "a", "b", "x"]);
+        document
+    }
+
+    fn run(&self, dir: &Dir, document: &Value) -> common::Run {
+        dir.write("secrets.yaml", serde_yaml::to_string(document).unwrap());
+        common::run(
+            dir.command()
+                .args(["secrets.yaml", "--sops-decrypt", "--stdout", "-f", "json"])
+                .env("SOPS_AGE_KEY", self.key()),
+            None,
+        )
+    }
+}
+
+#[test]
+fn decrypts_sops_yaml_to_json() {
+    let dir = Dir::new();
+    let mut sops = Sops::new();
+    let document = sops.document();
+    let run = sops.run(&dir, &document).success();
+    assert_eq!(
+        common::json(&run.stdout),
+        json!({
+            "db": {"user": "admin", "port": 5432, "tls": true},
+            "hosts": ["a", "b"],
+            "name_unencrypted": "x",
+        })
+    );
+}
+
+#[test]
+fn refuses_reordered_values() {
+    let dir = Dir::new();
+    let mut sops = Sops::new();
+    // Items of a list share their additional data, so only the MAC catches this
+    let mut document = sops.document();
+    document["hosts"].as_array_mut().unwrap().reverse();
+    let run = sops.run(&dir, &document).failure(3);
+    assert!(run.stderr.contains("MAC doesn't match"), "{}", run.stderr);
+}
+
+#[test]
+fn refuses_a_changed_unencrypted_value() {
+    let dir = Dir::new();
+    let mut sops = Sops::new();
+    let mut document = sops.document();
+    document["name_unencrypted"] = json!("y");
+    let run = sops.run(&dir, &document).failure(3);
+    assert!(run.stderr.contains("MAC doesn't match"), "{}", run.stderr);
+}
+
+#[test]
+fn hashes_only_encrypted_values_under_mac_only_encrypted() {
+    let dir = Dir::new();
+    let mut sops = Sops::new();
+    let mut document = sops.document();
+    document["sops"] = sops.metadata(&["admin", "5432", "True", "a", "b"]);
+    document["sops"]["mac_only_encrypted"] = json!(true);
+    document["name_unencrypted"] = json!("changed");
+    sops.run(&dir, &document).success();
+}
+
+#[test]
+fn refuses_a_document_without_a_mac() {
+    let dir = Dir::new();
+    let mut sops = Sops::new();
+    let mut document = sops.document();
+    document["sops"].as_object_mut().unwrap().remove("mac");
+    let run = sops.run(&dir, &document);
+    assert!(
+        run.stderr
+            .contains("no `mac`, so the document can't be verified"),
+        "{}",
+        run.stderr
+    );
+}
+
+#[test]
+fn refuses_a_key_that_isnt_a_recipient() {
+    let dir = Dir::new();
+    let mut sops = Sops::new();
+    let document = sops.document();
+    let other = Sops::new();
+    let run = other.run(&dir, &document);
+    assert!(
+        run.stderr.contains("None of the available age identities"),
+        "{}",
+        run.stderr
+    );
+}
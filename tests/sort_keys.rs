@@ -0,0 +1,48 @@
+mod common;
+
+use common::Dir;
+
+const SHUFFLED: &str = include_str!("fixtures/shuffled.json");
+
+fn sorted(to: &str) -> String {
+    let dir = Dir::new();
+    dir.write("shuffled.json", SHUFFLED);
+    dir.run(&["shuffled.json", "--stdout", "-f", to, "--sort-keys"])
+        .success()
+        .stdout
+}
+
+#[test]
+fn sorts_nested_keys_bytewise_and_keeps_array_order() {
+    assert_eq!(sorted("json"), include_str!("fixtures/sorted.json"));
+    assert_eq!(sorted("yaml"), include_str!("fixtures/sorted.yaml"));
+}
+
+#[test]
+fn sorts_the_same_way_every_run() {
+    let first = sorted("json");
+    for _ in 0..5 {
+        assert_eq!(sorted("json"), first);
+    }
+}
+
+#[test]
+fn sorts_keys_for_toml_output() {
+    let dir = Dir::new();
+    dir.write("a.json", r#"{"z":1,"t":{"b":2,"a":1},"a":0}"#);
+    let run = dir
+        .run(&["a.json", "--stdout", "-f", "toml", "--sort-keys"])
+        .success();
+    assert_eq!(run.stdout, "a = 0\nz = 1\n\n[t]\na = 1\nb = 2\n");
+}
+
+#[test]
+fn keeps_the_input_order_without_sort_keys() {
+    let dir = Dir::new();
+    dir.write("shuffled.json", SHUFFLED);
+    let run = dir
+        .run(&["shuffled.json", "--stdout", "-f", "json", "--compact"])
+        .success();
+    assert_eq!(run.stdout, common::json(SHUFFLED).to_string() + "\n");
+    assert!(run.stdout.starts_with("{\"zeta\":1,\"Beta\":{\"y\":2"));
+}
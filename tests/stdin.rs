@@ -0,0 +1,61 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn reads_stdin_for_a_dash() {
+    let dir = Dir::new();
+    let run = dir
+        .run_with(&["-", "out.toml", "--from", "json"], Some(b"{\"a\": 1}"))
+        .success();
+    assert_eq!(dir.read("out.toml"), "a = 1\n");
+    // There's no file named `-` to have read
+    assert_eq!(run.stderr, "Wrote standard input to out.toml\n");
+}
+
+#[test]
+fn detects_the_format_of_stdin() {
+    let dir = Dir::new();
+    let run = dir
+        .run_with(&["-", "--stdout", "-f", "yaml"], Some(b"{\"a\": [1, 2]}"))
+        .success();
+    assert_eq!(run.stdout, "a:\n- 1\n- 2\n");
+}
+
+#[test]
+fn asks_for_input_format_when_stdin_cant_be_told() {
+    let dir = Dir::new();
+    let run = dir
+        .run_with(&["-", "out.toml"], Some(b"just some words\n"))
+        .failure(2);
+    assert!(
+        run.stderr
+            .starts_with("Error: standard input: Couldn't detect the format; pass --input-format"),
+        "{}",
+        run.stderr
+    );
+
+    let run = dir
+        .run_with(&["-", "out.toml", "--no-sniff"], Some(b"{\"a\": 1}"))
+        .failure(2);
+    assert_eq!(
+        run.stderr,
+        "Error: Cannot detect the format of standard input; pass --input-format (--from)\n"
+    );
+    assert!(!dir.exists("out.toml"));
+}
+
+#[test]
+fn names_stdin_in_parse_errors() {
+    let dir = Dir::new();
+    let run = dir
+        .run_with(
+            &["-", "--from", "json", "--stdout", "-f", "yaml"],
+            Some(b"{\n"),
+        )
+        .failure(3);
+    assert_eq!(
+        run.stderr,
+        "Error: standard input:2:1: EOF while parsing an object\n"
+    );
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn prints_just_the_document_and_writes_no_file() {
+    let dir = Dir::new();
+    dir.write("config.toml", "a = 1\n");
+    let run = dir
+        .run(&["config.toml", "-f", "json", "--stdout"])
+        .success();
+    assert_eq!(run.stdout, "{\n  \"a\": 1\n}\n");
+    assert_eq!(run.stderr, "");
+    assert!(!dir.exists("config.json"));
+}
+
+#[test]
+fn refuses_an_output_path_with_stdout() {
+    let dir = Dir::new();
+    dir.write("config.toml", "a = 1\n");
+    let run = dir.run(&["config.toml", "out.json", "--stdout"]).failure(2);
+    assert_eq!(
+        run.stderr,
+        "Error: --stdout needs a single input PATH, without an OUTPUT, and --to or --template\n"
+    );
+    assert!(!dir.exists("out.json"));
+}
+
+#[test]
+fn pipes_binary_output() {
+    let dir = Dir::new();
+    dir.write("config.toml", "a = 1\n");
+    let run = dir
+        .run(&["config.toml", "-f", "msgpack", "--stdout"])
+        .success();
+    assert_eq!(run.bytes, b"\x81\xa1a\x01");
+}
+
+/// Run `scribe` with `args` in `dir` with a terminal for its output, if
+/// `script` is there to give it one
+#[cfg(target_os = "linux")]
+fn on_terminal(dir: &Dir, args: &str) -> Option<common::Run> {
+    use std::process::Command;
+
+    Command::new("script").arg("--version").output().ok()?;
+    let exe = env!("CARGO_BIN_EXE_scribe");
+    let mut command = Command::new("script");
+    command
+        .args(["-qec", &format!("{exe} {args}"), "/dev/null"])
+        .current_dir(dir.path(""))
+        .env("XDG_CONFIG_HOME", dir.path(""))
+        .env("RUST_BACKTRACE", "0");
+    Some(common::run(&mut command, None))
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn refuses_binary_output_to_a_terminal_without_force() {
+    let dir = Dir::new();
+    dir.write("config.toml", "a = 1\n");
+    let Some(run) = on_terminal(&dir, "config.toml -f msgpack --stdout") else {
+        eprintln!("skipped: no `script` to run it on a terminal");
+        return;
+    };
+    let run = run.failure(2);
+    assert!(
+        run.stdout.contains(
+            "Refusing to print binary output to a terminal; pipe it elsewhere or pass --force"
+        ),
+        "{}",
+        run.stdout
+    );
+
+    let run = on_terminal(&dir, "config.toml -f msgpack --stdout --force").unwrap();
+    assert_eq!(run.success().bytes, b"\x81\xa1a\x01");
+}
@@ -0,0 +1,160 @@
+mod common;
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use common::Dir;
+
+/// Write a JSON array of records `size` bytes or so long to `name`
+fn fixture(dir: &Dir, name: &str, size: usize) {
+    let mut writer = BufWriter::new(File::create(dir.path(name)).unwrap());
+    let mut written = 1;
+    writer.write_all(b"[").unwrap();
+    for index in 0.. {
+        let record = format!(
+            r#"{}{{"id":{index},"name":"item {index}","tags":["a","b","c"],"nested":{{"x":{index}.5,"y":"{}"}}}}"#,
+            if index == 0 { "" } else { "," },
+            "z".repeat(40),
+        );
+        writer.write_all(record.as_bytes()).unwrap();
+        written += record.len();
+        if written >= size {
+            break;
+        }
+    }
+    writer.write_all(b"]").unwrap();
+}
+
+/// The most memory a run of `scribe` with `args` took at once, in bytes, as
+/// the kernel counts it while the run goes
+#[cfg(target_os = "linux")]
+fn peak_memory(dir: &Dir, args: &[&str]) -> usize {
+    use std::{process::Stdio, thread, time::Duration};
+
+    let mut child = dir
+        .command()
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let status = format!("/proc/{}/status", child.id());
+    let mut peak = 0;
+    loop {
+        // A zombie has no memory left to report, so keep the last reading
+        let high_water = std::fs::read_to_string(&status).ok().and_then(|status| {
+            let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+            line.split_whitespace().nth(1)?.parse::<usize>().ok()
+        });
+        peak = peak.max(high_water.unwrap_or(0) * 1024);
+        if let Some(status) = child.try_wait().unwrap() {
+            assert!(status.success());
+            return peak;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+const MB: usize = 1024 * 1024;
+
+#[cfg(target_os = "linux")]
+#[test]
+fn converts_a_large_json_array_to_ndjson_in_bounded_memory() {
+    let dir = Dir::new();
+    fixture(&dir, "big.json", 10 * MB);
+    let peak = peak_memory(&dir, &["big.json", "-f", "ndjson"]);
+    assert!(peak < 20 * MB, "peaked at {} MB", peak / MB);
+
+    let lines = dir.read("big.ndjson");
+    assert!(lines.starts_with("{\"id\":0,\"name\":\"item 0\""));
+    assert!(lines.lines().all(|line| line.ends_with("}}")));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn converts_a_large_json_array_to_yaml_in_bounded_memory() {
+    let dir = Dir::new();
+    fixture(&dir, "big.json", 10 * MB);
+    let peak = peak_memory(&dir, &["big.json", "-f", "yaml"]);
+    assert!(peak < 20 * MB, "peaked at {} MB", peak / MB);
+    assert!(dir
+        .read("big.yaml")
+        .starts_with("- id: 0\n  name: item 0\n"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+#[ignore = "writes and converts a 100 MB fixture; run with --ignored"]
+fn converts_a_100_mb_json_array_in_bounded_memory() {
+    let dir = Dir::new();
+    fixture(&dir, "big.json", 100 * MB);
+    for to in ["ndjson", "yaml", "json"] {
+        let peak = peak_memory(&dir, &["big.json", &format!("out.{to}")]);
+        assert!(peak < 20 * MB, "{to} peaked at {} MB", peak / MB);
+    }
+}
+
+#[test]
+fn streams_the_same_output_as_reading_whole() {
+    let dir = Dir::new();
+    let json = "\n  [{\"a\":1,\"b\":[1,2.5,{\"c\":null}]}, \"x\", 3, [], {}, true]\n";
+    dir.write("a.json", json);
+    for to in ["yaml", "ndjson", "json"] {
+        let whole = dir
+            .run_with(
+                &["-", "--from", "json", "--stdout", "-f", to],
+                Some(json.as_bytes()),
+            )
+            .success();
+        let output = format!("out.{to}");
+        dir.run(&["a.json", &output]).success();
+        assert_eq!(dir.read(&output), whole.stdout, "{to}");
+    }
+}
+
+#[test]
+fn streams_an_empty_array() {
+    let dir = Dir::new();
+    dir.write("a.json", "[]");
+    dir.run(&["a.json", "-f", "yaml"]).success();
+    assert_eq!(dir.read("a.yaml"), "[]\n");
+    dir.run(&["a.json", "-f", "ndjson"]).success();
+    assert_eq!(dir.read("a.ndjson"), "");
+}
+
+#[test]
+fn places_errors_partway_through_a_streamed_array() {
+    let dir = Dir::new();
+    dir.write("dup.json", "[{\"a\":1},\n{\"b\":1,\"b\":2}]");
+    let run = dir.run(&["dup.json", "-f", "ndjson"]).failure(3);
+    assert!(run.stderr.contains("dup.json:2:"), "{}", run.stderr);
+    assert!(run.stderr.contains("duplicate key /1/b"), "{}", run.stderr);
+    assert!(!dir.exists("dup.ndjson"));
+
+    dir.write("eof.json", "[1,\n2,\n");
+    let run = dir.run(&["eof.json", "-f", "yaml"]).failure(3);
+    assert!(run
+        .stderr
+        .contains("eof.json:3:1: EOF while parsing a value"));
+
+    dir.write("trailing.json", "[1] x");
+    let run = dir.run(&["trailing.json", "-f", "ndjson"]).failure(3);
+    assert!(run
+        .stderr
+        .contains("trailing.json:1:5: trailing characters"));
+}
+
+#[test]
+fn converts_ndjson_record_by_record() {
+    let dir = Dir::new();
+    dir.write("a.ndjson", "{\"a\":1}\n\n{\"a\":2}\n");
+    dir.run(&["a.ndjson", "-f", "json"]).success();
+    assert_eq!(
+        common::json(&dir.read("a.json")),
+        common::json("[{\"a\":1},{\"a\":2}]")
+    );
+    dir.run(&["a.ndjson", "-f", "yaml"]).success();
+    assert_eq!(dir.read("a.yaml"), "- a: 1\n- a: 2\n");
+}
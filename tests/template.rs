@@ -0,0 +1,111 @@
+mod common;
+
+use common::Dir;
+
+const SITES: &str = include_str!("fixtures/sites.yaml");
+const NGINX: &str = include_str!("fixtures/nginx.hbs");
+
+fn dir() -> Dir {
+    let dir = Dir::new();
+    dir.write("sites.yaml", SITES);
+    dir.write("nginx.hbs", NGINX);
+    dir
+}
+
+#[test]
+fn renders_a_yaml_fixture_as_the_golden_file() {
+    let dir = dir();
+    dir.run(&["sites.yaml", "nginx.conf", "--template", "nginx.hbs"])
+        .success();
+    assert_eq!(dir.read("nginx.conf"), include_str!("fixtures/nginx.conf"));
+}
+
+#[test]
+fn renders_to_stdout() {
+    let dir = dir();
+    let run = dir
+        .run(&["sites.yaml", "--stdout", "--template", "nginx.hbs"])
+        .success();
+    assert_eq!(run.stdout, include_str!("fixtures/nginx.conf"));
+}
+
+#[test]
+fn renders_arrays_nested_maps_and_nulls_without_escaping() {
+    let dir = Dir::new();
+    dir.write("n.json", r#"{"a":null,"list":[1,[2,3]],"m":{"k":"<b>"}}"#);
+    dir.write(
+        "n.hbs",
+        "[{{a}}] {{#each list}}{{this}};{{/each}} {{m.k}}\n",
+    );
+    let run = dir
+        .run(&["n.json", "--stdout", "--template", "n.hbs"])
+        .success();
+    assert_eq!(run.stdout, "[] 1;[2, 3]; <b>\n");
+}
+
+#[test]
+fn places_a_key_the_document_doesnt_have() {
+    let dir = dir();
+    dir.write("m.hbs", "upstream {{upstream}}\n{{servers.0.host}}\n");
+    let run = dir
+        .run(&["sites.yaml", "out.conf", "--template", "m.hbs"])
+        .failure(4);
+    assert_eq!(
+        run.stderr,
+        "Error: sites.yaml: m.hbs:2:1: `servers.0.host` isn't in the document\n"
+    );
+    assert!(!dir.exists("out.conf"));
+}
+
+#[test]
+fn places_a_syntax_error_in_the_template() {
+    let dir = dir();
+    dir.write("bad.hbs", "ok\n{{#each servers}}\n");
+    let run = dir
+        .run(&["sites.yaml", "out.conf", "--template", "bad.hbs"])
+        .failure(2);
+    assert_eq!(
+        run.stderr,
+        "Error: Invalid template: bad.hbs:3:1: invalid Handlebars syntax\n"
+    );
+
+    dir.write("mismatch.hbs", "{{#if upstream}}x{{/each}}");
+    let run = dir
+        .run(&["sites.yaml", "out.conf", "--template", "mismatch.hbs"])
+        .failure(2);
+    assert_eq!(
+        run.stderr,
+        "Error: Invalid template: mismatch.hbs:1:18: helper \"if\" was opened, but \"each\" is closing\n"
+    );
+    assert!(!dir.exists("out.conf"));
+}
+
+#[test]
+fn refuses_an_output_format_with_a_template() {
+    let dir = dir();
+    let run = dir
+        .run(&[
+            "sites.yaml",
+            "--stdout",
+            "--template",
+            "nginx.hbs",
+            "-f",
+            "json",
+        ])
+        .failure(2);
+    assert!(
+        run.stderr
+            .contains("'--template <FILE>' cannot be used with '--output-format <FORMAT>'"),
+        "{}",
+        run.stderr
+    );
+}
+
+#[test]
+fn fails_on_a_missing_template() {
+    let dir = dir();
+    let run = dir
+        .run(&["sites.yaml", "--stdout", "--template", "nope.hbs"])
+        .failure(5);
+    assert!(run.stderr.starts_with("Error: Failed to read nope.hbs: "));
+}
@@ -0,0 +1,131 @@
+mod common;
+
+use common::Dir;
+use serde_json::json;
+
+const DATETIMES: &str = "\
+odt = 1979-05-27T07:32:00Z
+off = 1979-05-27T00:32:00-07:00
+ldt = 1979-05-27T07:32:00
+ld = 1979-05-27
+lt = 07:32:00
+";
+
+fn from_toml(mode: &str) -> serde_json::Value {
+    let dir = Dir::new();
+    dir.write("dt.toml", DATETIMES);
+    let run = dir
+        .run(&["dt.toml", "--stdout", "-f", "json", "--datetime", mode])
+        .success();
+    common::json(&run.stdout)
+}
+
+#[test]
+fn reads_datetimes_as_rfc_3339_strings() {
+    assert_eq!(
+        from_toml("string"),
+        json!({
+            "odt": "1979-05-27T07:32:00Z",
+            "off": "1979-05-27T00:32:00-07:00",
+            "ldt": "1979-05-27T07:32:00",
+            "ld": "1979-05-27",
+            "lt": "07:32:00",
+        })
+    );
+}
+
+#[test]
+fn reads_datetimes_with_an_offset_as_timestamps_under_epoch() {
+    assert_eq!(
+        from_toml("epoch"),
+        json!({
+            "odt": 296638320,
+            "off": 296638320,
+            "ldt": "1979-05-27T07:32:00",
+            "ld": "1979-05-27",
+            "lt": "07:32:00",
+        })
+    );
+}
+
+#[test]
+fn writes_datetime_strings_as_datetimes_under_detect() {
+    let dir = Dir::new();
+    dir.write(
+        "dt.json",
+        r#"{"odt":"1979-05-27T07:32:00Z","ld":"1979-05-27","lt":"07:32:00","bad":"2020-13-45"}"#,
+    );
+    let run = dir
+        .run(&["dt.json", "--stdout", "-f", "toml", "--datetime", "detect"])
+        .success();
+    assert_eq!(
+        run.stdout,
+        "odt = 1979-05-27T07:32:00Z\nld = 1979-05-27\nlt = 07:32:00\nbad = \"2020-13-45\"\n"
+    );
+    let run = dir.run(&["dt.json", "--stdout", "-f", "toml"]).success();
+    assert!(run.stdout.starts_with("odt = \"1979-05-27T07:32:00Z\"\n"));
+}
+
+#[test]
+fn refuses_an_array_mixing_tables_with_other_values() {
+    let dir = Dir::new();
+    dir.write("in.json", r#"{"b":{"c":[1,2,{"d":3}]}}"#);
+    let run = dir.run(&["in.json", "out.toml"]).failure(4);
+    assert!(
+        run.stderr.contains("`b.c` mixes tables with other values"),
+        "{}",
+        run.stderr
+    );
+    assert!(!dir.exists("out.toml"));
+}
+
+#[test]
+fn refuses_tables_in_an_array_of_arrays() {
+    let dir = Dir::new();
+    dir.write("in.json", r#"{"c":[[{"d":3}],[1]]}"#);
+    let run = dir.run(&["in.json", "out.toml"]).failure(4);
+    assert!(
+        run.stderr.contains("`c` has tables in an array inside it"),
+        "{}",
+        run.stderr
+    );
+}
+
+#[test]
+fn writes_arrays_of_tables_that_read_back() {
+    let dir = Dir::new();
+    let value = json!({"c": [[1, 2], ["a"]], "t": [{"a": [{"b": 1}]}, {"d": {"e": 1}}]});
+    dir.write("in.json", value.to_string());
+    dir.run(&["in.json", "out.toml"]).success();
+    dir.run(&["out.toml", "back.json"]).success();
+    assert_eq!(common::json(&dir.read("back.json")), value);
+}
+
+#[test]
+fn writes_null_as_an_empty_table_under_null_as_empty_table() {
+    let dir = Dir::new();
+    dir.write("in.json", r#"{"section": null}"#);
+    let run = dir
+        .run(&["in.json", "--stdout", "-f", "toml", "--null-as-empty-table"])
+        .success();
+    assert_eq!(run.stdout, "[section]\n");
+
+    dir.write("nested.json", r#"{"a": {"b": null, "c": 1}}"#);
+    dir.run(&["nested.json", "out.toml", "--null-as-empty-table"])
+        .success();
+    dir.run(&["out.toml", "back.json"]).success();
+    assert_eq!(
+        common::json(&dir.read("back.json")),
+        json!({"a": {"c": 1, "b": {}}})
+    );
+}
+
+#[test]
+fn still_refuses_null_in_an_array_under_null_as_empty_table() {
+    let dir = Dir::new();
+    dir.write("in.json", r#"{"l": [null]}"#);
+    let run = dir
+        .run(&["in.json", "-f", "toml", "--null-as-empty-table"])
+        .failure(4);
+    assert!(run.stderr.contains("null at `l[0]`"), "{}", run.stderr);
+}
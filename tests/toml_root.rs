@@ -0,0 +1,75 @@
+mod common;
+
+use common::Dir;
+
+fn to_toml(json: &str, args: &[&str]) -> common::Run {
+    let dir = Dir::new();
+    dir.write("in.json", json);
+    let mut all = vec!["in.json", "--stdout", "-f", "toml"];
+    all.extend(args);
+    dir.run(&all)
+}
+
+#[test]
+fn explains_that_toml_needs_a_table_at_the_root() {
+    let run = to_toml("[1, 2, 3]", &[]).failure(4);
+    assert_eq!(
+        run.stderr,
+        "Error: in.json: TOML needs a table at the root, not an array; pass --toml-wrap KEY to put it under KEY\n"
+    );
+    let run = to_toml("\"hi\"", &[]).failure(4);
+    assert!(run.stderr.contains("not a string;"), "{}", run.stderr);
+}
+
+#[test]
+fn wraps_an_array_root_under_toml_wrap() {
+    let run = to_toml("[1, 2, 3]", &["--toml-wrap", "items"]).success();
+    assert_eq!(run.stdout, "items = [1, 2, 3]\n");
+}
+
+#[test]
+fn wraps_a_scalar_root_under_toml_wrap() {
+    let run = to_toml("\"hi\"", &["--toml-wrap", "value"]).success();
+    assert_eq!(run.stdout, "value = \"hi\"\n");
+}
+
+#[test]
+fn wraps_an_array_of_tables() {
+    let run = to_toml(r#"[{"a":1},{"a":2}]"#, &["--toml-wrap", "items"]).success();
+    assert_eq!(run.stdout, "[[items]]\na = 1\n\n[[items]]\na = 2\n");
+}
+
+#[test]
+fn leaves_a_table_root_alone_under_toml_wrap() {
+    let run = to_toml(r#"{"a":{"b":1}}"#, &["--toml-wrap", "items"]).success();
+    assert_eq!(run.stdout, "[a]\nb = 1\n");
+}
+
+#[test]
+fn unwraps_a_single_key_on_the_way_back() {
+    let dir = Dir::new();
+    let json = r#"[{"a":1},{"a":2}]"#;
+    dir.write("in.json", json);
+    dir.run(&["in.json", "out.toml", "--toml-wrap", "items"])
+        .success();
+    let run = dir
+        .run(&[
+            "out.toml",
+            "--stdout",
+            "-f",
+            "json",
+            "--compact",
+            "--toml-unwrap",
+        ])
+        .success();
+    assert_eq!(run.stdout, format!("{json}\n"));
+
+    dir.write("two.toml", "a = 1\nb = 2\n");
+    let run = dir
+        .run(&["two.toml", "-f", "json", "--toml-unwrap"])
+        .failure(4);
+    assert_eq!(
+        run.stderr,
+        "Error: two.toml: --toml-unwrap needs a single key at the root of the input, not 2\n"
+    );
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use common::Dir;
+
+/// A valid and an invalid document in each format, by extension
+const FIXTURES: &[(&str, &[u8], &[u8])] = &[
+    ("json", b"{\"a\":1}", b"{\"a\":"),
+    ("yaml", b"a: 1\n", b"a: [1\n"),
+    ("toml", b"a = 1\n", b"a =\n"),
+    ("json5", b"{a: 1,}", b"{a: }"),
+    ("ron", b"(a: 1)", b"(a: "),
+    ("xml", b"<a>1</a>", b"<a>1</b>"),
+    ("csv", b"a,b\n1,2\n", b"a,b\n1,2,3\n"),
+    ("ini", b"[s]\na=1\n", b"[s\na=1\n"),
+    ("ndjson", b"{\"a\":1}\n{\"a\":2}\n", b"{\"a\":1}\n{\"a\"\n"),
+    (
+        "plist",
+        b"<?xml version=\"1.0\"?><plist version=\"1.0\"><dict><key>a</key><integer>1</integer></dict></plist>",
+        b"<plist><dict><key>a</key></plist>",
+    ),
+    ("env", b"A=1\n", b"just words\n"),
+    ("properties", b"a=1\n", b"a=\\u12\n"),
+    ("hcl", b"a = 1\n", b"a = \n"),
+    ("msgpack", b"\x81\xa1a\x01", b"\x81\xa1a"),
+    ("cbor", b"\xa1\x61a\x01", b"\xa1\x61a"),
+    ("bson", b"\x0c\x00\x00\x00\x10a\x00\x01\x00\x00\x00\x00", b"\x0c\x00\x00\x00\x10a"),
+];
+
+#[test]
+fn accepts_a_valid_file_in_each_format() {
+    let dir = Dir::new();
+    for (ext, valid, _) in FIXTURES {
+        let name = format!("ok.{ext}");
+        dir.write(&name, valid);
+        let run = dir.run(&["--validate", &name]).success();
+        assert!(
+            run.stderr.contains(&format!("{name}: valid")),
+            "{}",
+            run.stderr
+        );
+    }
+    assert!(!dir.exists("ok.yml"));
+}
+
+#[test]
+fn rejects_an_invalid_file_in_each_format() {
+    let dir = Dir::new();
+    for (ext, _, invalid) in FIXTURES {
+        let name = format!("bad.{ext}");
+        dir.write(&name, invalid);
+        let run = dir.run(&["--validate", &name]).failure(3);
+        assert!(run.stderr.contains(&format!("bad.{ext}")), "{}", run.stderr);
+    }
+}
+
+#[test]
+fn reports_the_position_of_a_parse_error() {
+    let dir = Dir::new();
+    dir.write("bad.toml", "a = 1\nb =\n");
+    let run = dir.run(&["--validate", "bad.toml"]).failure(3);
+    assert!(run.stderr.contains("bad.toml:2:4: "), "{}", run.stderr);
+}
+
+#[test]
+fn checks_every_file_of_a_mixed_batch() {
+    let dir = Dir::new();
+    dir.write("a.json", "{}");
+    dir.write("b.toml", "b =\n");
+    dir.write("c.yaml", "c: 1\n");
+    let run = dir
+        .run(&["--validate", "a.json", "b.toml", "c.yaml"])
+        .failure(3);
+    assert!(run.stderr.contains("a.json: valid json"));
+    assert!(run.stderr.contains("b.toml:1:4: "));
+    assert!(run.stderr.contains("c.yaml: valid"));
+    assert!(run.stderr.contains("Validated 3 files, 1 error"));
+}
+
+#[test]
+fn checks_the_files_listed_by_files_from() {
+    let dir = Dir::new();
+    dir.write("a.json", "{}");
+    dir.write("b.toml", "b =\n");
+    dir.write("list", "a.json\nb.toml\n");
+    let run = dir.run(&["--validate", "--files-from", "list"]).failure(3);
+    assert!(run.stderr.contains("a.json: valid json"));
+    assert!(run.stderr.contains("Validated 2 files, 1 error"));
+    assert!(!dir.exists("a.yaml"));
+}
+
+#[test]
+fn files_from_still_needs_a_format_to_convert() {
+    let dir = Dir::new();
+    dir.write("a.json", "{}");
+    dir.write("list", "a.json\n");
+    let run = dir.run(&["--files-from", "list"]).failure(2);
+    assert!(run.stderr.contains("--files-from needs --to"));
+}
+
+#[cfg(feature = "git")]
+#[test]
+fn checks_only_the_files_git_sees_changed() {
+    let dir = Dir::new();
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir.path(""))
+            .env("HOME", dir.path(""))
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?}");
+    };
+    git(&["init", "-q"]);
+    dir.write("committed.toml", "a =\n");
+    git(&["add", "committed.toml"]);
+    git(&["commit", "-qm", "init"]);
+    dir.write("changed.json", "{}");
+    dir.write("notes.txt", "not config");
+
+    let run = dir.run(&["--validate", "--git-changed"]).success();
+    assert!(
+        run.stderr.contains("changed.json: valid json"),
+        "{}",
+        run.stderr
+    );
+    assert!(!run.stderr.contains("committed.toml"));
+}
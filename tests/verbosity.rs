@@ -0,0 +1,75 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn reports_writes_on_stderr_only() {
+    let dir = Dir::new();
+    dir.write("c.yaml", "a: 1\n");
+    let run = dir.run(&["c.yaml", "-f", "json"]).success();
+    assert_eq!(run.stdout, "");
+    assert_eq!(run.stderr, "Wrote c.yaml to c.json\n");
+}
+
+#[test]
+fn keeps_stdout_for_the_converted_document() {
+    let dir = Dir::new();
+    dir.write("c.yaml", "a: 1\n");
+    let run = dir
+        .run(&["c.yaml", "--stdout", "-f", "json", "-v"])
+        .success();
+    assert_eq!(run.stdout, "{\n  \"a\": 1\n}\n");
+    assert_eq!(run.stderr, "Read c.yaml (yaml, 5 bytes)\n");
+}
+
+#[test]
+fn prints_nothing_but_errors_under_quiet() {
+    let dir = Dir::new();
+    dir.write("c.yaml", "a: 1\n");
+    let run = dir.run(&["c.yaml", "-f", "json", "-q"]).success();
+    assert_eq!((run.stdout.as_str(), run.stderr.as_str()), ("", ""));
+    assert!(dir.exists("c.json"));
+
+    dir.write("bad.yaml", "a: [\n");
+    let run = dir.run(&["bad.yaml", "-f", "json", "--quiet"]).failure(3);
+    assert_eq!(run.stdout, "");
+    assert!(
+        run.stderr.starts_with("Error: bad.yaml:2:1: "),
+        "{}",
+        run.stderr
+    );
+}
+
+#[test]
+fn shows_formats_and_sizes_under_verbose() {
+    let dir = Dir::new();
+    dir.write("c.yaml", "a: 1\n");
+    let run = dir.run(&["c.yaml", "-f", "toml", "-v"]).success();
+    assert_eq!(
+        run.stderr,
+        "Read c.yaml (yaml, 5 bytes)\nWrote c.yaml to c.toml (toml, 6 bytes)\n"
+    );
+}
+
+#[test]
+fn shows_timings_under_vv() {
+    let dir = Dir::new();
+    dir.write("c.yaml", "a: 1\n");
+    let run = dir.run(&["c.yaml", "-f", "toml", "-vv"]).success();
+    let lines: Vec<_> = run.stderr.lines().collect();
+    assert_eq!(lines.len(), 4, "{}", run.stderr);
+    assert!(lines[1].starts_with("Parsed c.yaml in "), "{}", run.stderr);
+    assert!(
+        lines[2].starts_with("Serialized c.toml in "),
+        "{}",
+        run.stderr
+    );
+    assert_eq!(run.stdout, "");
+}
+
+#[test]
+fn refuses_quiet_with_verbose() {
+    let dir = Dir::new();
+    dir.write("c.yaml", "a: 1\n");
+    dir.run(&["c.yaml", "-f", "json", "-q", "-v"]).failure(2);
+}
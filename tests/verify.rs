@@ -0,0 +1,152 @@
+mod common;
+
+use common::Dir;
+
+#[test]
+fn passes_a_json_to_yaml_conversion() {
+    let dir = Dir::new();
+    dir.write(
+        "a.json",
+        r#"{"a":1,"b":[1.5,"x",null],"c":{"d":true,"e":"yes"}}"#,
+    );
+    let run = dir
+        .run(&["a.json", "-f", "yaml", "--verify=strict"])
+        .success();
+    assert!(!run.stderr.contains("round-trip"), "{}", run.stderr);
+    assert_eq!(
+        common::json(&dir.read("a.json")),
+        common::json(
+            &dir.run(&["a.yaml", "--stdout", "-f", "json"])
+                .success()
+                .stdout
+        )
+    );
+}
+
+#[test]
+fn warns_about_a_lossy_conversion_and_writes_it() {
+    let dir = Dir::new();
+    dir.write("a.json", r#"{"a":1,"b":null,"n":1.5}"#);
+    let run = dir
+        .run(&["a.json", "-f", "toml", "--null-policy", "omit", "--verify"])
+        .success();
+    assert!(
+        run.stderr.contains(
+            "warning: a.toml: output does not round-trip to the input:\n  /b: null was removed\n"
+        ),
+        "{}",
+        run.stderr
+    );
+    assert_eq!(dir.read("a.toml"), "a = 1\nn = 1.5\n");
+}
+
+#[test]
+fn refuses_a_lossy_conversion_under_strict() {
+    let dir = Dir::new();
+    dir.write("a.json", r#"{"a":1,"n":1.5}"#);
+    let run = dir
+        .run(&["a.json", "-f", "ini", "--verify=strict"])
+        .failure(4);
+    assert!(
+        run.stderr.contains("  /a: 1 became \"1\""),
+        "{}",
+        run.stderr
+    );
+    assert!(run.stderr.contains("  /n: 1.5 became \"1.5\""));
+    assert!(!dir.exists("a.ini"));
+}
+
+#[test]
+fn reads_a_toml_datetime_back_from_a_json_string() {
+    let dir = Dir::new();
+    dir.write("c.toml", "a = 1\nwhen = 1979-05-27T07:32:00Z\n");
+    let run = dir.run(&["c.toml", "c.json", "--verify=strict"]).success();
+    assert!(!run.stderr.contains("round-trip"), "{}", run.stderr);
+    let run = dir
+        .run(&["c.json", "back.toml", "--verify=strict"])
+        .success();
+    assert!(!run.stderr.contains("round-trip"), "{}", run.stderr);
+    assert_eq!(
+        dir.read("back.toml"),
+        "a = 1\nwhen = \"1979-05-27T07:32:00Z\"\n"
+    );
+}
+
+#[test]
+fn lists_every_path_that_changed() {
+    let dir = Dir::new();
+    dir.write("s.json", r#"{"a":null,"b":[1,null]}"#);
+    let run = dir
+        .run(&[
+            "s.json",
+            "-f",
+            "toml",
+            "--null-policy",
+            "string",
+            "--verify",
+        ])
+        .success();
+    assert!(
+        run.stderr.contains(
+            "warning: s.toml: output does not round-trip to the input:\n  /a: null became \"null\"\n  /b/1: null became \"null\"\n"
+        ),
+        "{}",
+        run.stderr
+    );
+
+    let run = dir
+        .run(&[
+            "s.json",
+            "o.toml",
+            "--null-policy",
+            "omit",
+            "--verify=strict",
+        ])
+        .failure(4);
+    assert_eq!(
+        run.stderr,
+        "Error: s.json: Output does not round-trip to the input:\n  /a: null was removed\n  /b/1: null was removed\n"
+    );
+    assert!(!dir.exists("o.toml"));
+}
+
+#[test]
+fn leaves_the_warning_out_under_quiet() {
+    let dir = Dir::new();
+    dir.write("s.json", r#"{"a":1,"b":null}"#);
+    let run = dir
+        .run(&[
+            "s.json",
+            "-f",
+            "toml",
+            "--null-policy",
+            "omit",
+            "--verify",
+            "--quiet",
+        ])
+        .success();
+    assert_eq!(run.stderr, "");
+    assert_eq!(dir.read("s.toml"), "a = 1\n");
+}
+
+#[test]
+fn names_standard_output_in_the_warning_under_stdout() {
+    let dir = Dir::new();
+    dir.write("a.json", r#"{"a":1,"b":null}"#);
+    let run = dir
+        .run(&[
+            "a.json",
+            "--stdout",
+            "-f",
+            "toml",
+            "--null-policy",
+            "omit",
+            "--verify",
+        ])
+        .success();
+    assert_eq!(
+        run.stderr,
+        "warning: standard output: output does not round-trip to the input:\n  /b: null was removed\n"
+    );
+    assert_eq!(run.stdout, "a = 1\n");
+}
@@ -0,0 +1,165 @@
+mod common;
+
+use std::{
+    fs::{self, File},
+    process::{Child, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use common::Dir;
+
+/// A `scribe --watch` run in the background, its standard error going to
+/// `stderr.log` so it can be read while it runs
+struct Watch<'a> {
+    dir: &'a Dir,
+    child: Child,
+}
+
+impl<'a> Watch<'a> {
+    fn start(dir: &'a Dir, args: &[&str]) -> Self {
+        let child = dir
+            .command()
+            .args(args)
+            .arg("--watch")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(File::create(dir.path("stderr.log")).unwrap())
+            .spawn()
+            .unwrap();
+        Self { dir, child }
+    }
+
+    fn stderr(&self) -> String {
+        self.dir.read("stderr.log")
+    }
+
+    /// Wait up to ten seconds for `done` to hold
+    #[track_caller]
+    fn wait_for(&self, what: &str, done: impl Fn() -> bool) {
+        let started = Instant::now();
+        while !done() {
+            assert!(
+                started.elapsed() < Duration::from_secs(10),
+                "timed out waiting for {what}; stderr: {}",
+                self.stderr()
+            );
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    #[track_caller]
+    fn wait_for_output(&self, name: &str, content: &str) {
+        self.wait_for(&format!("{name} to hold {content:?}"), || {
+            fs::read_to_string(self.dir.path(name)).is_ok_and(|read| read == content)
+        });
+    }
+
+    fn rebuilds(&self) -> usize {
+        self.stderr().matches("changed").count()
+    }
+}
+
+impl Drop for Watch<'_> {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Give the watcher time to settle, so one change isn't mistaken for two
+fn settle() {
+    thread::sleep(Duration::from_millis(500));
+}
+
+#[test]
+fn converts_first_then_again_on_every_change() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    let watch = Watch::start(&dir, &["config.yaml", "-f", "json", "--compact"]);
+    watch.wait_for_output("config.json", "{\"a\":1}\n");
+    settle();
+
+    dir.write("config.yaml", "a: 2\n");
+    watch.wait_for_output("config.json", "{\"a\":2}\n");
+    let stderr = watch.stderr();
+    let line = stderr
+        .lines()
+        .find(|line| line.ends_with("] config.yaml changed"));
+    let line = line.unwrap_or_else(|| panic!("{stderr}"));
+    humantime::parse_rfc3339(&line[1..line.find(']').unwrap()]).unwrap();
+}
+
+#[test]
+fn rebuilds_once_for_a_burst_of_saves() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    let watch = Watch::start(&dir, &["config.yaml", "-f", "json", "--compact"]);
+    watch.wait_for_output("config.json", "{\"a\":1}\n");
+    settle();
+
+    dir.write("config.yaml", "a: 2\n");
+    dir.write("config.yaml", "a: 3\n");
+    watch.wait_for_output("config.json", "{\"a\":3}\n");
+    settle();
+    assert_eq!(watch.rebuilds(), 1, "{}", watch.stderr());
+}
+
+#[test]
+fn follows_a_file_renamed_into_place() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    let watch = Watch::start(&dir, &["config.yaml", "-f", "json", "--compact"]);
+    watch.wait_for_output("config.json", "{\"a\":1}\n");
+    settle();
+
+    for n in 2..4 {
+        dir.write("config.yaml.swp", format!("a: {n}\n"));
+        fs::rename(dir.path("config.yaml.swp"), dir.path("config.yaml")).unwrap();
+        watch.wait_for_output("config.json", &format!("{{\"a\":{n}}}\n"));
+        settle();
+    }
+}
+
+#[test]
+fn carries_on_after_a_failed_conversion() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    let mut watch = Watch::start(&dir, &["config.yaml", "-f", "json", "--compact"]);
+    watch.wait_for_output("config.json", "{\"a\":1}\n");
+    settle();
+
+    dir.write("config.yaml", "a: [1\n");
+    watch.wait_for("the error", || watch.stderr().contains("config.yaml:"));
+    assert!(watch.child.try_wait().unwrap().is_none());
+    assert_eq!(dir.read("config.json"), "{\"a\":1}\n");
+
+    settle();
+    dir.write("config.yaml", "a: 4\n");
+    watch.wait_for_output("config.json", "{\"a\":4}\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn exits_cleanly_on_interrupt() {
+    let dir = Dir::new();
+    dir.write("config.yaml", "a: 1\n");
+    let mut watch = Watch::start(&dir, &["config.yaml", "-f", "json"]);
+    watch.wait_for("the first conversion", || dir.exists("config.json"));
+    let interrupted = Command::new("kill")
+        .args(["-INT", &watch.child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(interrupted.success());
+    assert_eq!(watch.child.wait().unwrap().code(), Some(0));
+}
+
+#[test]
+fn refuses_standard_input() {
+    let dir = Dir::new();
+    let run = dir.run_with(
+        &["-", "--from", "yaml", "--stdout", "-f", "json", "--watch"],
+        Some(b"a: 1\n"),
+    );
+    assert!(run.code != 0, "{}", run.stderr);
+}
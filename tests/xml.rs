@@ -0,0 +1,70 @@
+mod common;
+
+use common::Dir;
+use serde_json::json;
+
+const SERVER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<server name="web" port="80">
+  <host>a</host>
+  <host>b</host>
+  <deep>
+    <er>
+      <est lang="en">hi</est>
+    </er>
+  </deep>
+  <empty/>
+</server>
+"#;
+
+fn server() -> serde_json::Value {
+    json!({
+        "server": {
+            "@name": "web",
+            "@port": "80",
+            "host": ["a", "b"],
+            "deep": {"er": {"est": {"@lang": "en", "#text": "hi"}}},
+            "empty": null,
+        }
+    })
+}
+
+#[test]
+fn reads_attributes_repeated_and_nested_elements() {
+    let dir = Dir::new();
+    dir.write("a.xml", SERVER);
+    let run = dir.run(&["a.xml", "--stdout", "-f", "json"]).success();
+    assert_eq!(common::json(&run.stdout), server());
+}
+
+#[test]
+fn writes_the_same_document_back_as_xml() {
+    let dir = Dir::new();
+    dir.write("a.json", server().to_string());
+    dir.run(&["a.json", "a.xml"]).success();
+    assert_eq!(dir.read("a.xml"), SERVER);
+}
+
+#[test]
+fn refuses_text_mixed_with_elements() {
+    let dir = Dir::new();
+    dir.write("mixed.xml", "<a>\n  text<b/>\n</a>");
+    let run = dir.run(&["mixed.xml", "-f", "json"]).failure(3);
+    assert_eq!(
+        run.stderr,
+        "Error: mixed.xml:3: <a> mixes text with child elements, which can't be converted\n"
+    );
+    assert!(!dir.exists("mixed.json"));
+}
+
+#[test]
+fn needs_a_single_root_for_xml_output() {
+    let dir = Dir::new();
+    dir.write("two.json", r#"{"a":1,"b":2}"#);
+    let run = dir.run(&["two.json", "-f", "xml"]).failure(4);
+    assert!(
+        run.stderr
+            .contains("XML output needs a map with a single key to use as the root element"),
+        "{}",
+        run.stderr
+    );
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use common::Dir;
+use serde_json::json;
+
+/// The Norway problem, and the rest of what YAML 1.1 reads differently
+const NORWAY: &str =
+    "country: NO\nok: yes\nflag: off\ntime: 22:22\nmode: 0755\nreal: true\nquoted: \"yes\"\n";
+
+fn resolved(args: &[&str]) -> serde_json::Value {
+    let dir = Dir::new();
+    dir.write("n.yaml", NORWAY);
+    let mut all = vec!["n.yaml", "--stdout", "-f", "json"];
+    all.extend(args);
+    common::json(&dir.run(&all).success().stdout)
+}
+
+#[test]
+fn reads_only_true_and_false_as_booleans_by_default() {
+    let strings = json!({
+        "country": "NO",
+        "ok": "yes",
+        "flag": "off",
+        "time": "22:22",
+        "mode": "0755",
+        "real": true,
+        "quoted": "yes",
+    });
+    assert_eq!(resolved(&[]), strings);
+    assert_eq!(resolved(&["--yaml-compat", "1.2"]), strings);
+}
+
+#[test]
+fn reads_yaml_1_1_scalars_under_yaml_compat_1_1() {
+    assert_eq!(
+        resolved(&["--yaml-compat", "1.1"]),
+        json!({
+            "country": false,
+            "ok": true,
+            "flag": false,
+            "time": 1342,
+            "mode": 493,
+            "real": true,
+            "quoted": "yes",
+        })
+    );
+}
+
+#[test]
+fn quotes_strings_either_spec_would_misread() {
+    let dir = Dir::new();
+    dir.write(
+        "s.json",
+        r#"{"a":"yes","b":"NO","c":"on","d":"true","e":"y","f":"22:22","g":"0755","h":"plain"}"#,
+    );
+    let run = dir.run(&["s.json", "--stdout", "-f", "yaml"]).success();
+    assert_eq!(
+        run.stdout,
+        "a: 'yes'\nb: 'NO'\nc: 'on'\nd: 'true'\ne: 'y'\nf: '22:22'\ng: '0755'\nh: plain\n"
+    );
+
+    dir.write("s.yaml", &run.stdout);
+    let run = dir
+        .run(&[
+            "s.yaml",
+            "--stdout",
+            "-f",
+            "json",
+            "--compact",
+            "--yaml-compat",
+            "1.1",
+        ])
+        .success();
+    assert_eq!(common::json(&run.stdout), common::json(&dir.read("s.json")));
+}
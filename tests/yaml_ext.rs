@@ -0,0 +1,45 @@
+mod common;
+
+use common::Dir;
+use scribe::FileFormat;
+
+#[test]
+fn derives_a_yaml_extension_by_default() {
+    let dir = Dir::new();
+    dir.write("c.json", "{\"a\":1}");
+    let run = dir.run(&["c.json", "-f", "yaml"]).success();
+    assert_eq!(run.stderr, "Wrote c.json to c.yaml\n");
+    assert_eq!(dir.read("c.yaml"), "a: 1\n");
+}
+
+#[test]
+fn derives_a_yml_extension_under_yaml_ext_yml() {
+    let dir = Dir::new();
+    dir.write("c.json", "{\"a\":1}");
+    let run = dir
+        .run(&["c.json", "-f", "yaml", "--yaml-ext", "yml"])
+        .success();
+    assert_eq!(run.stderr, "Wrote c.json to c.yml\n");
+    assert!(!dir.exists("c.yaml"));
+
+    dir.write(".rscribe.toml", "yaml-ext = \"yml\"\n");
+    dir.write("d.json", "{}");
+    dir.run(&["d.json", "-f", "yaml"]).success();
+    assert!(dir.exists("d.yml") && !dir.exists("d.yaml"));
+}
+
+#[test]
+fn reads_both_extensions() {
+    let dir = Dir::new();
+    dir.write("a.yml", "a: 1\n");
+    dir.write("b.yaml", "b: 2\n");
+    dir.run(&["a.yml", "-f", "toml"]).success();
+    dir.run(&["b.yaml", "-f", "toml"]).success();
+    assert_eq!(dir.read("a.toml"), "a = 1\n");
+    assert_eq!(dir.read("b.toml"), "b = 2\n");
+}
+
+#[test]
+fn displays_yaml_as_yaml() {
+    assert_eq!(FileFormat::Yaml.to_string(), "yaml");
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use common::Dir;
+
+const FIXTURE: &str = include_str!("fixtures/styled.json");
+
+const GOLDEN: [(&str, &str, &str); 6] = [
+    (
+        "block",
+        "auto",
+        include_str!("fixtures/styled.block.auto.yaml"),
+    ),
+    (
+        "block",
+        "always",
+        include_str!("fixtures/styled.block.always.yaml"),
+    ),
+    (
+        "block",
+        "never-unsafe",
+        include_str!("fixtures/styled.block.never-unsafe.yaml"),
+    ),
+    (
+        "flow",
+        "auto",
+        include_str!("fixtures/styled.flow.auto.yaml"),
+    ),
+    (
+        "flow",
+        "always",
+        include_str!("fixtures/styled.flow.always.yaml"),
+    ),
+    (
+        "flow",
+        "never-unsafe",
+        include_str!("fixtures/styled.flow.never-unsafe.yaml"),
+    ),
+];
+
+#[test]
+fn writes_each_style_and_quoting_as_the_golden_files() {
+    let dir = Dir::new();
+    dir.write("styled.json", FIXTURE);
+    for (style, quote, golden) in GOLDEN {
+        let run = dir
+            .run(&[
+                "styled.json",
+                "--stdout",
+                "-f",
+                "yaml",
+                "--yaml-style",
+                style,
+                "--yaml-quote",
+                quote,
+            ])
+            .success();
+        assert_eq!(
+            run.stdout, golden,
+            "--yaml-style {style} --yaml-quote {quote}"
+        );
+    }
+}
+
+#[test]
+fn reads_each_golden_file_back_as_the_fixture() {
+    let dir = Dir::new();
+    for (style, quote, golden) in GOLDEN {
+        let name = format!("{style}.{quote}.yaml");
+        dir.write(&name, golden);
+        let run = dir.run(&[&name, "--stdout", "-f", "json"]).success();
+        assert_eq!(common::json(&run.stdout), common::json(FIXTURE), "{name}");
+    }
+}
+
+#[test]
+fn writes_block_style_with_auto_quoting_by_default() {
+    let dir = Dir::new();
+    dir.write("styled.json", FIXTURE);
+    let run = dir
+        .run(&["styled.json", "--stdout", "-f", "yaml"])
+        .success();
+    assert_eq!(run.stdout, GOLDEN[0].2);
+}
+
+#[test]
+fn leaves_keys_plain_under_always_unless_they_need_quoting() {
+    let dir = Dir::new();
+    dir.write("k.json", r#"{"plain":"x","on":"y","1":"z"}"#);
+    let run = dir
+        .run(&["k.json", "--stdout", "-f", "yaml", "--yaml-quote", "always"])
+        .success();
+    assert_eq!(run.stdout, "plain: \"x\"\n'on': \"y\"\n'1': \"z\"\n");
+}
+
+#[test]
+fn refuses_an_unknown_style() {
+    let dir = Dir::new();
+    dir.write("styled.json", FIXTURE);
+    let run = dir
+        .run(&["styled.json", "-f", "yaml", "--yaml-style", "folded"])
+        .failure(2);
+    assert!(
+        run.stderr.contains("invalid value 'folded'"),
+        "{}",
+        run.stderr
+    );
+}